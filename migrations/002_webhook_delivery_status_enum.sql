@@ -0,0 +1,11 @@
+-- Store webhook delivery status as a proper enum so it maps onto WebhookDeliveryStatus
+CREATE TYPE webhook_delivery_status AS ENUM ('pending', 'delivered', 'failed', 'retrying');
+
+ALTER TABLE webhook_deliveries DROP CONSTRAINT webhook_deliveries_status_check;
+ALTER TABLE webhook_deliveries ALTER COLUMN status DROP DEFAULT;
+ALTER TABLE webhook_deliveries
+    ALTER COLUMN status TYPE webhook_delivery_status USING status::webhook_delivery_status;
+ALTER TABLE webhook_deliveries ALTER COLUMN status SET DEFAULT 'pending';
+
+-- Replays scan an account's transactions by time range
+CREATE INDEX idx_transactions_account_id_created_at ON transactions(account_id, created_at);
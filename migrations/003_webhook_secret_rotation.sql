@@ -0,0 +1,6 @@
+-- Secrets are stored encrypted (enc:v1:<hex>), which no longer fits VARCHAR(255)
+ALTER TABLE webhooks ALTER COLUMN secret TYPE TEXT;
+
+-- Previous secret stays valid for signing until the rotation overlap window ends
+ALTER TABLE webhooks ADD COLUMN previous_secret TEXT;
+ALTER TABLE webhooks ADD COLUMN previous_secret_expires_at TIMESTAMP WITH TIME ZONE;
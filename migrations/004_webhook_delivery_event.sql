@@ -0,0 +1,9 @@
+-- A transaction can produce several events, so each delivery records which one it carries
+ALTER TABLE webhook_deliveries ADD COLUMN event VARCHAR(100);
+
+UPDATE webhook_deliveries wd
+SET event = 'transaction.' || t.type
+FROM transactions t
+WHERE wd.transaction_id = t.id;
+
+ALTER TABLE webhook_deliveries ALTER COLUMN event SET NOT NULL;
@@ -0,0 +1,3 @@
+-- Transfers are also delivered to and replayed for the receiving account
+CREATE INDEX idx_transactions_counterparty_account_id_created_at
+    ON transactions(counterparty_account_id, created_at);
@@ -0,0 +1,2 @@
+-- Events such as webhook.test are not tied to a transaction
+ALTER TABLE webhook_deliveries ALTER COLUMN transaction_id DROP NOT NULL;
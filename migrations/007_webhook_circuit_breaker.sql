@@ -0,0 +1,16 @@
+-- Track endpoint health so persistently failing webhooks can be disabled automatically
+ALTER TABLE webhooks ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE webhooks ADD COLUMN disabled_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE webhooks ADD COLUMN disabled_reason TEXT;
+
+-- Account-level notifications raised by the system (e.g. a webhook being disabled)
+CREATE TABLE account_notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    event VARCHAR(100) NOT NULL,
+    data JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_account_notifications_account_id ON account_notifications(account_id, created_at);
+CREATE INDEX idx_webhook_deliveries_webhook_id_updated_at ON webhook_deliveries(webhook_id, updated_at);
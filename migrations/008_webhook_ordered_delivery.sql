@@ -0,0 +1,11 @@
+-- Optional FIFO delivery per webhook, with per-webhook event sequence numbers
+ALTER TABLE webhooks ADD COLUMN ordered BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE webhook_deliveries ADD COLUMN sequence BIGINT;
+
+-- Kept apart from webhooks so allocating a sequence does not touch updated_at
+CREATE TABLE webhook_sequences (
+    webhook_id UUID PRIMARY KEY REFERENCES webhooks(id) ON DELETE CASCADE,
+    last_sequence BIGINT NOT NULL
+);
+
+CREATE INDEX idx_webhook_deliveries_webhook_id_sequence ON webhook_deliveries(webhook_id, sequence);
@@ -0,0 +1,4 @@
+-- Optional batching of several events into one signed webhook request
+ALTER TABLE webhooks ADD COLUMN batch_max_size INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE webhooks ADD COLUMN batch_max_linger_ms INTEGER NOT NULL DEFAULT 1000;
+ALTER TABLE webhook_deliveries ADD COLUMN batch_id UUID;
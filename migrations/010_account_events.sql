@@ -0,0 +1,12 @@
+-- Per-account event log for clients that poll instead of receiving webhooks
+CREATE TABLE account_events (
+    id BIGSERIAL PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    event VARCHAR(100) NOT NULL,
+    transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_account_events_account_id_id ON account_events(account_id, id);
+CREATE INDEX idx_account_events_created_at ON account_events(created_at);
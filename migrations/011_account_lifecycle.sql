@@ -0,0 +1,17 @@
+-- Account lifecycle: active, frozen or closed
+CREATE TYPE account_status AS ENUM ('active', 'frozen', 'closed');
+ALTER TABLE accounts ADD COLUMN status account_status NOT NULL DEFAULT 'active';
+
+-- History of account changes with who made them and why
+CREATE TABLE account_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    field VARCHAR(50) NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    reason TEXT,
+    actor VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_account_changes_account_id ON account_changes(account_id, created_at);
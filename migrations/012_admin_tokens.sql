@@ -0,0 +1,12 @@
+-- Credentials for platform operators, separate from merchant API keys
+CREATE TABLE admin_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    token_hash VARCHAR(255) NOT NULL UNIQUE,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX idx_accounts_created_at ON accounts(created_at);
+CREATE INDEX idx_accounts_balance ON accounts(balance);
@@ -0,0 +1,6 @@
+-- Role-based permissions for admin credentials
+CREATE TYPE admin_role AS ENUM ('support', 'finance', 'superuser');
+
+-- Tokens issued before roles existed keep full access
+ALTER TABLE admin_tokens ADD COLUMN role admin_role NOT NULL DEFAULT 'superuser';
+ALTER TABLE admin_tokens ALTER COLUMN role SET DEFAULT 'support';
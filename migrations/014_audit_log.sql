@@ -0,0 +1,77 @@
+-- Append-only, hash-chained audit log of state changes
+CREATE SEQUENCE audit_log_id_seq;
+
+CREATE TABLE audit_log (
+    id BIGINT PRIMARY KEY,
+    actor VARCHAR(255) NOT NULL,
+    action VARCHAR(100) NOT NULL,
+    resource_type VARCHAR(50) NOT NULL,
+    resource_id UUID NOT NULL,
+    before JSONB,
+    after JSONB,
+    request_id VARCHAR(255),
+    ip VARCHAR(64),
+    prev_hash VARCHAR(64),
+    hash VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_audit_log_resource ON audit_log(resource_type, resource_id, id);
+CREATE INDEX idx_audit_log_actor ON audit_log(actor, id);
+CREATE INDEX idx_audit_log_request_id ON audit_log(request_id);
+
+-- Canonical hash of an entry; jsonb text output is normalized, so this is
+-- stable for verification.
+CREATE FUNCTION audit_entry_hash(
+    prev_hash TEXT,
+    created_at TIMESTAMP WITH TIME ZONE,
+    actor TEXT,
+    action TEXT,
+    resource_type TEXT,
+    resource_id UUID,
+    before JSONB,
+    after JSONB,
+    request_id TEXT,
+    ip TEXT
+) RETURNS TEXT AS $$
+    SELECT encode(sha256(convert_to(jsonb_build_array(
+        prev_hash,
+        (extract(epoch FROM created_at) * 1000000)::bigint,
+        actor, action, resource_type, resource_id, before, after, request_id, ip
+    )::text, 'UTF8')), 'hex')
+$$ LANGUAGE SQL IMMUTABLE;
+
+-- Entries are chained in commit order: the lock is held until the inserting
+-- transaction ends, and ids are only allocated while holding it.
+CREATE FUNCTION audit_log_chain() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('audit_log'));
+
+    NEW.id := nextval('audit_log_id_seq');
+    NEW.created_at := NOW();
+    NEW.prev_hash := (SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1);
+    NEW.hash := audit_entry_hash(
+        NEW.prev_hash, NEW.created_at, NEW.actor, NEW.action, NEW.resource_type,
+        NEW.resource_id, NEW.before, NEW.after, NEW.request_id, NEW.ip
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_chain
+    BEFORE INSERT ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_chain();
+
+CREATE FUNCTION audit_log_immutable() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_no_update
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_immutable();
+
+CREATE TRIGGER audit_log_no_truncate
+    BEFORE TRUNCATE ON audit_log
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_log_immutable();
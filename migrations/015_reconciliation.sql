@@ -0,0 +1,26 @@
+-- Balance reconciliation runs and the discrepancies they found
+CREATE TABLE reconciliation_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    trigger VARCHAR(20) NOT NULL,
+    accounts_checked BIGINT NOT NULL,
+    discrepancies BIGINT NOT NULL,
+    total_abs_drift BIGINT NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    finished_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_reconciliation_runs_started_at ON reconciliation_runs(started_at);
+
+CREATE TABLE balance_discrepancies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    run_id UUID NOT NULL REFERENCES reconciliation_runs(id) ON DELETE CASCADE,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    stored_balance BIGINT NOT NULL,
+    expected_balance BIGINT NOT NULL,
+    drift BIGINT NOT NULL,
+    account_frozen BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_balance_discrepancies_run_id ON balance_discrepancies(run_id);
+CREATE INDEX idx_balance_discrepancies_account_id ON balance_discrepancies(account_id, created_at);
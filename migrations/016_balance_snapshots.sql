@@ -0,0 +1,26 @@
+-- Balance effect of every completed transaction, per affected account
+CREATE VIEW account_ledger AS
+SELECT
+    account_id,
+    id AS transaction_id,
+    CASE WHEN type = 'credit' THEN amount ELSE -amount END AS delta,
+    created_at
+FROM transactions
+WHERE status = 'completed'
+UNION ALL
+SELECT
+    counterparty_account_id,
+    id,
+    amount,
+    created_at
+FROM transactions
+WHERE status = 'completed' AND type = 'transfer' AND counterparty_account_id IS NOT NULL;
+
+-- End-of-day (UTC) balances, so point-in-time queries don't replay all history
+CREATE TABLE balance_snapshots (
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    snapshot_date DATE NOT NULL,
+    closing_balance BIGINT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (account_id, snapshot_date)
+);
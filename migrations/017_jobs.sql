@@ -0,0 +1,40 @@
+-- Background jobs, e.g. large transaction imports, processed by a worker
+CREATE TYPE job_status AS ENUM ('queued', 'running', 'completed', 'failed');
+
+CREATE TABLE jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    kind VARCHAR(50) NOT NULL,
+    status job_status NOT NULL DEFAULT 'queued',
+    atomic BOOLEAN NOT NULL DEFAULT false,
+    total_items INTEGER NOT NULL,
+    processed_items INTEGER NOT NULL DEFAULT 0,
+    succeeded_items INTEGER NOT NULL DEFAULT 0,
+    failed_items INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- Who submitted the job, so audit entries written by the worker point back at them
+    api_key_id UUID,
+    request_id VARCHAR(255),
+    heartbeat_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMP WITH TIME ZONE,
+    finished_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX idx_jobs_account_id ON jobs(account_id, created_at);
+CREATE INDEX idx_jobs_pending ON jobs(created_at) WHERE status IN ('queued', 'running');
+
+CREATE TABLE job_items (
+    job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    item_index INTEGER NOT NULL,
+    request JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    idempotency_key VARCHAR(255),
+    transaction_id UUID,
+    error_code INTEGER,
+    error TEXT,
+    PRIMARY KEY (job_id, item_index)
+);
+
+CREATE INDEX idx_job_items_pending ON job_items(job_id, item_index) WHERE status = 'pending';
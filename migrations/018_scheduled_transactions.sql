@@ -0,0 +1,56 @@
+-- Transactions executed by the scheduler at a future time, once or on a recurring rule
+CREATE TYPE schedule_frequency AS ENUM ('once', 'daily', 'weekly', 'monthly', 'cron');
+CREATE TYPE schedule_status AS ENUM ('active', 'paused', 'completed', 'cancelled', 'failed');
+
+CREATE TABLE scheduled_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    type VARCHAR(20) NOT NULL,
+    amount BIGINT NOT NULL,
+    description TEXT,
+    counterparty_account_id UUID REFERENCES accounts(id),
+    frequency schedule_frequency NOT NULL,
+    -- Every N days, weeks or months
+    every INTEGER NOT NULL DEFAULT 1,
+    cron_expression VARCHAR(255),
+    start_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    end_at TIMESTAMP WITH TIME ZONE,
+    max_occurrences INTEGER,
+    status schedule_status NOT NULL DEFAULT 'active',
+    -- The occurrence currently due or next due; NULL once the schedule has ended
+    next_run_at TIMESTAMP WITH TIME ZONE,
+    -- Set while an occurrence that failed for insufficient funds waits to be retried
+    retry_at TIMESTAMP WITH TIME ZONE,
+    retry_attempt INTEGER NOT NULL DEFAULT 0,
+    max_retries INTEGER NOT NULL,
+    retry_interval_minutes INTEGER NOT NULL,
+    occurrences INTEGER NOT NULL DEFAULT 0,
+    last_run_at TIMESTAMP WITH TIME ZONE,
+    -- Who created the schedule, so executions are attributed to them in the audit log
+    api_key_id UUID,
+    request_id VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_scheduled_transactions_account_id ON scheduled_transactions(account_id, created_at);
+CREATE INDEX idx_scheduled_transactions_due ON scheduled_transactions((COALESCE(retry_at, next_run_at)))
+    WHERE status = 'active';
+
+-- One row per execution attempt or skipped occurrence
+CREATE TABLE scheduled_transaction_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    schedule_id UUID NOT NULL REFERENCES scheduled_transactions(id) ON DELETE CASCADE,
+    scheduled_for TIMESTAMP WITH TIME ZONE NOT NULL,
+    attempt INTEGER NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    transaction_id UUID REFERENCES transactions(id),
+    error_code INTEGER,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_scheduled_transaction_runs_schedule_id ON scheduled_transaction_runs(schedule_id, created_at);
+
+-- Events not tied to a transaction's own lifecycle carry their body with the delivery
+ALTER TABLE webhook_deliveries ADD COLUMN data JSONB;
@@ -0,0 +1,27 @@
+-- Split transfers: a parent 'split' transaction whose legs are ordinary transfers
+ALTER TABLE transactions DROP CONSTRAINT transactions_type_check;
+ALTER TABLE transactions ADD CONSTRAINT transactions_type_check
+    CHECK (type IN ('credit', 'debit', 'transfer', 'split'));
+
+ALTER TABLE transactions ADD COLUMN parent_transaction_id UUID REFERENCES transactions(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_transactions_parent_transaction_id ON transactions(parent_transaction_id)
+    WHERE parent_transaction_id IS NOT NULL;
+
+-- The legs carry a split's balance effect, so the parent is left out
+CREATE OR REPLACE VIEW account_ledger AS
+SELECT
+    account_id,
+    id AS transaction_id,
+    CASE WHEN type = 'credit' THEN amount ELSE -amount END AS delta,
+    created_at
+FROM transactions
+WHERE status = 'completed' AND type <> 'split'
+UNION ALL
+SELECT
+    counterparty_account_id,
+    id,
+    amount,
+    created_at
+FROM transactions
+WHERE status = 'completed' AND type = 'transfer' AND counterparty_account_id IS NOT NULL;
@@ -0,0 +1,37 @@
+-- What an account is charged per transaction type; fees are booked to the platform revenue account
+CREATE TABLE fee_schedules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    transaction_type VARCHAR(20) NOT NULL CHECK (transaction_type IN ('credit', 'debit', 'transfer')),
+    fixed_fee BIGINT NOT NULL DEFAULT 0 CHECK (fixed_fee >= 0),
+    -- Hundredths of a percent of the transaction amount
+    percentage_bps INTEGER NOT NULL DEFAULT 0 CHECK (percentage_bps BETWEEN 0 AND 10000),
+    min_fee BIGINT CHECK (min_fee >= 0),
+    max_fee BIGINT CHECK (max_fee >= 0),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (account_id, transaction_type),
+    CHECK (min_fee IS NULL OR max_fee IS NULL OR min_fee <= max_fee)
+);
+
+-- A fee is a child of the transaction it was charged on, paid to the revenue account
+ALTER TABLE transactions DROP CONSTRAINT transactions_type_check;
+ALTER TABLE transactions ADD CONSTRAINT transactions_type_check
+    CHECK (type IN ('credit', 'debit', 'transfer', 'split', 'fee'));
+
+CREATE OR REPLACE VIEW account_ledger AS
+SELECT
+    account_id,
+    id AS transaction_id,
+    CASE WHEN type = 'credit' THEN amount ELSE -amount END AS delta,
+    created_at
+FROM transactions
+WHERE status = 'completed' AND type <> 'split'
+UNION ALL
+SELECT
+    counterparty_account_id,
+    id,
+    amount,
+    created_at
+FROM transactions
+WHERE status = 'completed' AND type IN ('transfer', 'fee') AND counterparty_account_id IS NOT NULL;
@@ -0,0 +1,12 @@
+-- Per-account transaction limits and velocity controls; NULL means no limit
+CREATE TABLE account_limits (
+    account_id UUID PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+    max_transaction_amount BIGINT CHECK (max_transaction_amount > 0),
+    -- Money leaving the account per UTC calendar day and month
+    daily_debit_limit BIGINT CHECK (daily_debit_limit > 0),
+    monthly_debit_limit BIGINT CHECK (monthly_debit_limit > 0),
+    -- Transfers and splits in any rolling hour
+    hourly_transfer_limit INTEGER CHECK (hourly_transfer_limit > 0),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,61 @@
+-- Chain audit entries as their transaction commits rather than as they are
+-- written. Taking the chain lock on insert held it from a transaction's first
+-- audited write until it ended, serializing every audited transaction.
+--
+-- Entries are now inserted pending, with a negative placeholder id and no
+-- hash, and a deferred trigger chains them at commit: only then is the lock
+-- taken and the real id allocated, so ids still follow commit order. Pending
+-- entries are never visible outside their own transaction.
+CREATE SEQUENCE audit_log_pending_id_seq;
+
+ALTER TABLE audit_log ALTER COLUMN hash DROP NOT NULL;
+
+CREATE OR REPLACE FUNCTION audit_log_chain() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.id := -nextval('audit_log_pending_id_seq');
+    NEW.created_at := NOW();
+    NEW.prev_hash := NULL;
+    NEW.hash := NULL;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION audit_log_chain_at_commit() RETURNS TRIGGER AS $$
+DECLARE
+    chained_id BIGINT;
+    previous_hash TEXT;
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('audit_log'));
+
+    chained_id := nextval('audit_log_id_seq');
+    previous_hash := (SELECT hash FROM audit_log WHERE id > 0 ORDER BY id DESC LIMIT 1);
+
+    UPDATE audit_log
+    SET id = chained_id,
+        prev_hash = previous_hash,
+        hash = audit_entry_hash(
+            previous_hash, NEW.created_at, NEW.actor, NEW.action, NEW.resource_type,
+            NEW.resource_id, NEW.before, NEW.after, NEW.request_id, NEW.ip
+        )
+    WHERE id = NEW.id;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER audit_log_chain_at_commit
+    AFTER INSERT ON audit_log
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION audit_log_chain_at_commit();
+
+-- The one update allowed is the commit trigger chaining a pending entry.
+CREATE OR REPLACE FUNCTION audit_log_immutable() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' AND pg_trigger_depth() > 1 THEN
+        IF OLD.hash IS NULL THEN
+            RETURN NEW;
+        END IF;
+    END IF;
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
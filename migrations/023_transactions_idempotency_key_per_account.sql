@@ -0,0 +1,8 @@
+-- Idempotency keys are chosen by each account, so they only need to be
+-- unique per account. A global constraint let one account's key collide
+-- with, or probe for, another's.
+ALTER TABLE transactions DROP CONSTRAINT transactions_idempotency_key_key;
+DROP INDEX idx_transactions_idempotency_key;
+
+ALTER TABLE transactions
+    ADD CONSTRAINT transactions_account_id_idempotency_key_key UNIQUE (account_id, idempotency_key);
@@ -0,0 +1,2 @@
+-- balance.low is sent when a transaction takes the balance below this; 0 disables it
+ALTER TABLE accounts ADD COLUMN low_balance_threshold BIGINT NOT NULL DEFAULT 0 CHECK (low_balance_threshold >= 0);
@@ -1,40 +1,185 @@
 use axum::{
-    extract::{Path, State},
-    response::Json,
+    body::StreamBody,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
 };
+use chrono::SecondsFormat;
+use futures::{stream, StreamExt};
 use uuid::Uuid;
 
 use crate::{
-    error::Result,
-    models::{AccountResponse, BalanceResponse, CreateAccountRequest, CreateAccountResponse},
-    services::{AccountService, TransactionService, WebhookService},
+    csv,
+    error::{AppError, Result},
+    models::{
+        AccountChangesResponse, AccountResponse, AuditContext, BalanceHistoryQuery,
+        BalanceHistoryResponse, BalanceQuery, BalanceResponse, ChangeAccountStatusRequest,
+        CreateAccountRequest, CreateAccountResponse, ExportFormat, StatementLine,
+        StatementQuery, UpdateAccountRequest,
+    },
+    services::{AccountService, EventService, TransactionService, WebhookService},
 };
 
 pub async fn create_account(
-    State((account_service, _, _)): State<(AccountService, TransactionService, WebhookService)>,
+    State((account_service, _, _, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    ctx: AuditContext,
     Json(req): Json<CreateAccountRequest>,
 ) -> Result<Json<CreateAccountResponse>> {
-    let response = account_service.create_account(req).await?;
+    if !account_service.self_signup_allowed() {
+        return Err(AppError::SignupDisabled);
+    }
+    let response = account_service.create_account(req, &ctx).await?;
     Ok(Json(response))
 }
 
 pub async fn get_account(
-    State((account_service, _, _)): State<(AccountService, TransactionService, WebhookService)>,
+    State((account_service, _, _, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(caller_id): axum::extract::Extension<Uuid>,
     Path(account_id): Path<Uuid>,
 ) -> Result<Json<AccountResponse>> {
+    ensure_own_account(caller_id, account_id)?;
     let account = account_service.get_account(account_id).await?;
     Ok(Json(AccountResponse { account }))
 }
 
 pub async fn get_balance(
-    State((account_service, _, _)): State<(AccountService, TransactionService, WebhookService)>,
+    State((account_service, _, _, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(caller_id): axum::extract::Extension<Uuid>,
     Path(account_id): Path<Uuid>,
+    Query(query): Query<BalanceQuery>,
 ) -> Result<Json<BalanceResponse>> {
-    let balance = account_service.get_balance(account_id).await?;
+    ensure_own_account(caller_id, account_id)?;
+    let balance = match query.as_of {
+        Some(as_of) => account_service.get_balance_as_of(account_id, as_of).await?,
+        None => account_service.get_balance(account_id).await?,
+    };
     Ok(Json(BalanceResponse {
         account_id,
         balance,
         currency: "USD".to_string(),
+        as_of: query.as_of,
     }))
 }
 
+pub async fn get_balance_history(
+    State((account_service, _, _, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(caller_id): axum::extract::Extension<Uuid>,
+    Path(account_id): Path<Uuid>,
+    Query(query): Query<BalanceHistoryQuery>,
+) -> Result<Json<BalanceHistoryResponse>> {
+    ensure_own_account(caller_id, account_id)?;
+    let days = account_service.get_balance_history(account_id, query).await?;
+    Ok(Json(BalanceHistoryResponse {
+        account_id,
+        currency: "USD".to_string(),
+        days,
+    }))
+}
+
+const STATEMENT_CSV_HEADER: &str =
+    "record_type,timestamp,transaction_id,type,counterparty_account_id,description,amount,balance\n";
+
+pub async fn get_statement(
+    State((account_service, _, _, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(caller_id): axum::extract::Extension<Uuid>,
+    Path(account_id): Path<Uuid>,
+    Query(query): Query<StatementQuery>,
+) -> Result<Response> {
+    ensure_own_account(caller_id, account_id)?;
+    let lines = account_service.statement(account_id, &query).await?;
+
+    let (content_type, extension) = match query.format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Jsonl => ("application/x-ndjson", "jsonl"),
+    };
+    let disposition = format!(
+        "attachment; filename=\"statement-{}-{}-{}.{}\"",
+        account_id, query.from, query.to, extension
+    );
+
+    let format = query.format;
+    let header_line = match format {
+        ExportFormat::Csv => Some(Ok(STATEMENT_CSV_HEADER.to_string())),
+        ExportFormat::Jsonl => None,
+    };
+    let body = stream::iter(header_line).chain(lines.map(move |line| {
+        line.and_then(|line| match format {
+            ExportFormat::Csv => Ok(statement_csv_row(&line)),
+            ExportFormat::Jsonl => {
+                let mut json = serde_json::to_string(&line).map_err(|e| AppError::Internal(e.into()))?;
+                json.push('\n');
+                Ok(json)
+            }
+        })
+    }));
+
+    Ok((
+        [(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        StreamBody::new(body),
+    )
+        .into_response())
+}
+
+fn statement_csv_row(line: &StatementLine) -> String {
+    let fields = [
+        line.record_type.to_string(),
+        line.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+        line.transaction_id.map(|id| id.to_string()).unwrap_or_default(),
+        line.r#type.clone().unwrap_or_default(),
+        line.counterparty_account_id.map(|id| id.to_string()).unwrap_or_default(),
+        csv::escape(line.description.as_deref().unwrap_or_default()),
+        line.amount.to_string(),
+        line.balance.to_string(),
+    ];
+    let mut row = fields.join(",");
+    row.push('\n');
+    row
+}
+
+pub async fn update_account(
+    State((account_service, _, _, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(caller_id): axum::extract::Extension<Uuid>,
+    Path(account_id): Path<Uuid>,
+    ctx: AuditContext,
+    Json(req): Json<UpdateAccountRequest>,
+) -> Result<Json<AccountResponse>> {
+    ensure_own_account(caller_id, account_id)?;
+    let account = account_service
+        .update_account(account_id, req, &ctx)
+        .await?;
+    Ok(Json(AccountResponse { account }))
+}
+
+pub async fn change_account_status(
+    State((account_service, _, _, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(caller_id): axum::extract::Extension<Uuid>,
+    Path(account_id): Path<Uuid>,
+    ctx: AuditContext,
+    Json(req): Json<ChangeAccountStatusRequest>,
+) -> Result<Json<AccountResponse>> {
+    ensure_own_account(caller_id, account_id)?;
+    let account = account_service
+        .change_own_status(account_id, req, &ctx)
+        .await?;
+    Ok(Json(AccountResponse { account }))
+}
+
+pub async fn list_account_changes(
+    State((account_service, _, _, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(caller_id): axum::extract::Extension<Uuid>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<AccountChangesResponse>> {
+    ensure_own_account(caller_id, account_id)?;
+    let changes = account_service.list_changes(account_id).await?;
+    Ok(Json(AccountChangesResponse { changes }))
+}
+
+/// Accounts may only modify themselves; any other id is reported as not found.
+fn ensure_own_account(caller_id: Uuid, account_id: Uuid) -> Result<()> {
+    if caller_id != account_id {
+        return Err(AppError::AccountNotFound {
+            account_id: account_id.to_string(),
+        });
+    }
+    Ok(())
+}
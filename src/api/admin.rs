@@ -0,0 +1,189 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    Extension,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::{
+        AccountChangesResponse, AccountResponse, AccountStatus, AdminIdentity, AdminRole,
+        AdminTokensResponse, AuditContext, AuditVerificationResponse, BalanceHistoryQuery,
+        BalanceHistoryResponse, BalanceQuery, BalanceResponse, ChangeAccountStatusRequest, CreateAccountRequest,
+        CreateAccountResponse, CreateAdminTokenRequest, CreateAdminTokenResponse,
+        ListAccountsQuery, ListAccountsResponse, ListAuditLogQuery, ListAuditLogResponse,
+        ReconciliationRunResponse, ReconciliationRunsResponse,
+    },
+    services::{
+        reconciliation::TRIGGER_MANUAL, AccountService, AdminService, AuditService, ReconciliationService,
+    },
+};
+
+pub async fn list_accounts(
+    State((account_service, _, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Query(query): Query<ListAccountsQuery>,
+) -> Result<Json<ListAccountsResponse>> {
+    let response = account_service.list_accounts(query).await?;
+    Ok(Json(response))
+}
+
+pub async fn create_account(
+    State((account_service, _, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Extension(admin): Extension<AdminIdentity>,
+    ctx: AuditContext,
+    Json(req): Json<CreateAccountRequest>,
+) -> Result<Json<CreateAccountResponse>> {
+    ensure_role(&admin, &[AdminRole::Support])?;
+    let response = account_service.create_account(req, &ctx).await?;
+    tracing::info!(account_id = %response.account.id, admin = %admin.subject, "Account created by admin");
+    Ok(Json(response))
+}
+
+pub async fn get_account(
+    State((account_service, _, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<AccountResponse>> {
+    let account = account_service.get_account(account_id).await?;
+    Ok(Json(AccountResponse { account }))
+}
+
+/// Support and finance may freeze and unfreeze; closing moves money
+/// questions into scope, so it is limited to finance.
+pub async fn change_account_status(
+    State((account_service, _, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Extension(admin): Extension<AdminIdentity>,
+    Path(account_id): Path<Uuid>,
+    ctx: AuditContext,
+    Json(req): Json<ChangeAccountStatusRequest>,
+) -> Result<Json<AccountResponse>> {
+    match req.status {
+        AccountStatus::Closed => ensure_role(&admin, &[AdminRole::Finance])?,
+        _ => ensure_role(&admin, &[AdminRole::Support, AdminRole::Finance])?,
+    }
+    let account = account_service
+        .change_status(account_id, req, &ctx)
+        .await?;
+    Ok(Json(AccountResponse { account }))
+}
+
+pub async fn get_balance(
+    State((account_service, _, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Path(account_id): Path<Uuid>,
+    Query(query): Query<BalanceQuery>,
+) -> Result<Json<BalanceResponse>> {
+    let balance = match query.as_of {
+        Some(as_of) => account_service.get_balance_as_of(account_id, as_of).await?,
+        None => account_service.get_balance(account_id).await?,
+    };
+    Ok(Json(BalanceResponse {
+        account_id,
+        balance,
+        currency: "USD".to_string(),
+        as_of: query.as_of,
+    }))
+}
+
+pub async fn get_balance_history(
+    State((account_service, _, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Path(account_id): Path<Uuid>,
+    Query(query): Query<BalanceHistoryQuery>,
+) -> Result<Json<BalanceHistoryResponse>> {
+    let days = account_service.get_balance_history(account_id, query).await?;
+    Ok(Json(BalanceHistoryResponse {
+        account_id,
+        currency: "USD".to_string(),
+        days,
+    }))
+}
+
+pub async fn list_account_changes(
+    State((account_service, _, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<AccountChangesResponse>> {
+    account_service.get_account(account_id).await?;
+    let changes = account_service.list_changes(account_id).await?;
+    Ok(Json(AccountChangesResponse { changes }))
+}
+
+pub async fn create_admin_token(
+    State((_, admin_service, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Extension(admin): Extension<AdminIdentity>,
+    ctx: AuditContext,
+    Json(req): Json<CreateAdminTokenRequest>,
+) -> Result<Json<CreateAdminTokenResponse>> {
+    ensure_role(&admin, &[AdminRole::Superuser])?;
+    let response = admin_service.create_token(req, &ctx).await?;
+    Ok(Json(response))
+}
+
+pub async fn list_admin_tokens(
+    State((_, admin_service, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Extension(admin): Extension<AdminIdentity>,
+) -> Result<Json<AdminTokensResponse>> {
+    ensure_role(&admin, &[AdminRole::Superuser])?;
+    let tokens = admin_service.list_tokens().await?;
+    Ok(Json(AdminTokensResponse { tokens }))
+}
+
+pub async fn revoke_admin_token(
+    State((_, admin_service, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Extension(admin): Extension<AdminIdentity>,
+    Path(token_id): Path<Uuid>,
+    ctx: AuditContext,
+) -> Result<Json<serde_json::Value>> {
+    ensure_role(&admin, &[AdminRole::Superuser])?;
+    admin_service.revoke_token(token_id, &ctx).await?;
+    Ok(Json(serde_json::json!({
+        "message": "Admin token revoked successfully"
+    })))
+}
+
+pub async fn list_audit_log(
+    State((_, _, audit_service, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Query(query): Query<ListAuditLogQuery>,
+) -> Result<Json<ListAuditLogResponse>> {
+    let response = audit_service.list_entries(query).await?;
+    Ok(Json(response))
+}
+
+pub async fn verify_audit_log(
+    State((_, _, audit_service, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+) -> Result<Json<AuditVerificationResponse>> {
+    let response = audit_service.verify_chain().await?;
+    Ok(Json(response))
+}
+
+pub async fn list_reconciliation_runs(
+    State((_, _, _, reconciliation_service)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+) -> Result<Json<ReconciliationRunsResponse>> {
+    let runs = reconciliation_service.list_runs().await?;
+    Ok(Json(ReconciliationRunsResponse { runs }))
+}
+
+pub async fn start_reconciliation_run(
+    State((_, _, _, reconciliation_service)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Extension(admin): Extension<AdminIdentity>,
+) -> Result<Json<ReconciliationRunResponse>> {
+    ensure_role(&admin, &[AdminRole::Finance])?;
+    let response = reconciliation_service.run(TRIGGER_MANUAL).await?;
+    Ok(Json(response))
+}
+
+pub async fn get_reconciliation_run(
+    State((_, _, _, reconciliation_service)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<ReconciliationRunResponse>> {
+    let response = reconciliation_service.get_run(run_id).await?;
+    Ok(Json(response))
+}
+
+pub(crate) fn ensure_role(admin: &AdminIdentity, roles: &[AdminRole]) -> Result<()> {
+    if admin.has_role(roles) {
+        Ok(())
+    } else {
+        Err(AppError::AdminForbidden {
+            role: admin.role.to_string(),
+        })
+    }
+}
@@ -5,13 +5,18 @@ use axum::{
     response::Response,
 };
 
-use crate::services::{AccountService, TransactionService, WebhookService};
+use crate::models::Actor;
+use crate::services::{
+    AccountService, AdminService, AuditService, EventService, ReconciliationService, TransactionService,
+    WebhookService,
+};
 
 pub async fn auth_middleware(
-    State((account_service, _transaction_service, _webhook_service)): State<(
+    State((account_service, _transaction_service, _webhook_service, _)): State<(
         AccountService,
         TransactionService,
         WebhookService,
+        EventService,
     )>,
     mut request: axum::http::Request<axum::body::Body>,
     next: Next<axum::body::Body>,
@@ -26,12 +31,37 @@ pub async fn auth_middleware(
         .strip_prefix("Bearer ")
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    let account_id = account_service
+    let (account_id, key_id) = account_service
         .validate_api_key(api_key)
         .await
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
 
     request.extensions_mut().insert(account_id);
+    request.extensions_mut().insert(Actor::ApiKey(key_id));
+
+    Ok(next.run(request).await)
+}
+/// Authenticates platform operators via `X-Admin-Token`, or via the client
+/// certificate subject forwarded by the TLS proxy when mTLS identities are
+/// configured. Merchant API keys are never accepted here.
+pub async fn admin_auth_middleware(
+    State((_, admin_service, _, _)): State<(AccountService, AdminService, AuditService, ReconciliationService)>,
+    mut request: axum::http::Request<axum::body::Body>,
+    next: Next<axum::body::Body>,
+) -> Result<Response, StatusCode> {
+    let header = |name: &str| request.headers().get(name).and_then(|h| h.to_str().ok());
+
+    let admin = if let Some(token) = header("X-Admin-Token") {
+        admin_service.validate_admin_token(token).await
+    } else if let Some(subject) = admin_service.mtls_identity_header().and_then(header) {
+        admin_service.validate_mtls_identity(subject)
+    } else {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    request.extensions_mut().insert(admin.actor());
+    request.extensions_mut().insert(admin);
 
     Ok(next.run(request).await)
-}
\ No newline at end of file
+}
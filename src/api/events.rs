@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+};
+use futures::{Stream, StreamExt};
+use std::convert::Infallible;
+use uuid::Uuid;
+
+use crate::{
+    error::Result,
+    models::{ListEventsQuery, ListEventsResponse, StreamEventsQuery},
+    services::{AccountService, EventService, TransactionService, WebhookService},
+};
+
+pub async fn list_events(
+    State((_, _, _, event_service)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Query(query): Query<ListEventsQuery>,
+) -> Result<Json<ListEventsResponse>> {
+    let response = event_service.list_events(account_id, query).await?;
+    Ok(Json(response))
+}
+
+/// Server-sent events for the account. Resumes after `Last-Event-ID` (or `?after=`)
+/// when given, otherwise starts with the next new event.
+pub async fn stream_events(
+    State((_, _, _, event_service)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    headers: HeaderMap,
+    Query(query): Query<StreamEventsQuery>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    let last_event_id = headers
+        .get("Last-Event-ID")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .or(query.after);
+
+    let after = match last_event_id {
+        Some(id) => id,
+        None => event_service.latest_event_id(account_id).await?,
+    };
+
+    let stream = event_service.stream_events(account_id, after).map(|event| {
+        let name = event.payload["event"].as_str().unwrap_or("message").to_string();
+        Ok(Event::default()
+            .id(event.id.to_string())
+            .event(name)
+            .json_data(&event)
+            .unwrap_or_else(|_| Event::default().comment("unserializable event")))
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
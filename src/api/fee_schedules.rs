@@ -0,0 +1,64 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    Extension,
+};
+use uuid::Uuid;
+
+use crate::{
+    api::admin::ensure_role,
+    error::Result,
+    models::{
+        AdminIdentity, AdminRole, AuditContext, FeeScheduleResponse, FeeSchedulesResponse,
+        UpdateFeeScheduleRequest,
+    },
+    services::{transaction::parse_transaction_type, FeeService},
+};
+
+/// The authenticated account's own fee schedules.
+pub async fn list_fee_schedules(
+    State(fee_service): State<FeeService>,
+    Extension(account_id): Extension<Uuid>,
+) -> Result<Json<FeeSchedulesResponse>> {
+    let fee_schedules = fee_service.list_schedules(account_id).await?;
+    Ok(Json(FeeSchedulesResponse { fee_schedules }))
+}
+
+pub async fn admin_list_fee_schedules(
+    State(fee_service): State<FeeService>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<FeeSchedulesResponse>> {
+    let fee_schedules = fee_service.list_schedules(account_id).await?;
+    Ok(Json(FeeSchedulesResponse { fee_schedules }))
+}
+
+pub async fn admin_set_fee_schedule(
+    State(fee_service): State<FeeService>,
+    Extension(admin): Extension<AdminIdentity>,
+    Path((account_id, transaction_type)): Path<(Uuid, String)>,
+    ctx: AuditContext,
+    Json(req): Json<UpdateFeeScheduleRequest>,
+) -> Result<Json<FeeScheduleResponse>> {
+    ensure_role(&admin, &[AdminRole::Finance])?;
+    let transaction_type = parse_transaction_type(&transaction_type)?;
+    let fee_schedule = fee_service
+        .set_schedule(account_id, transaction_type, req, &ctx)
+        .await?;
+    Ok(Json(FeeScheduleResponse { fee_schedule }))
+}
+
+pub async fn admin_delete_fee_schedule(
+    State(fee_service): State<FeeService>,
+    Extension(admin): Extension<AdminIdentity>,
+    Path((account_id, transaction_type)): Path<(Uuid, String)>,
+    ctx: AuditContext,
+) -> Result<Json<serde_json::Value>> {
+    ensure_role(&admin, &[AdminRole::Finance])?;
+    let transaction_type = parse_transaction_type(&transaction_type)?;
+    fee_service
+        .delete_schedule(account_id, transaction_type, &ctx)
+        .await?;
+    Ok(Json(serde_json::json!({
+        "message": "Fee schedule deleted successfully"
+    })))
+}
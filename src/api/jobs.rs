@@ -0,0 +1,110 @@
+use axum::{
+    body::{Bytes, StreamBody},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use futures::{stream, StreamExt};
+use uuid::Uuid;
+
+use crate::{
+    api::transactions::parse_batch_body,
+    csv,
+    error::{AppError, Result},
+    models::{AuditContext, BatchTransactionQuery, ExportFormat, Job, JobItemResult, JobResponse, JobResultsQuery},
+    services::JobService,
+};
+
+/// Failed items included in `GET /jobs/:id`; the result file has all of them.
+const ERRORS_IN_RESPONSE: i64 = 100;
+
+const RESULTS_CSV_HEADER: &str = "index,status,idempotency_key,transaction_id,error_code,error\n";
+
+/// Takes the same body as `POST /transactions/batch` and returns straight away;
+/// the transactions are created by the job worker.
+pub async fn create_job(
+    State(job_service): State<JobService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Query(query): Query<BatchTransactionQuery>,
+    ctx: AuditContext,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<JobResponse>)> {
+    let (items, atomic) = parse_batch_body(&headers, &body, &query)?;
+    let job = job_service
+        .create_transaction_import(account_id, items, atomic, &ctx)
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(job_response(job, Vec::new()))))
+}
+
+pub async fn get_job(
+    State(job_service): State<JobService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<JobResponse>> {
+    let job = job_service.get_job(account_id, job_id).await?;
+    let errors = job_service.list_failed_items(job.id, ERRORS_IN_RESPONSE).await?;
+    Ok(Json(job_response(job, errors)))
+}
+
+pub async fn get_job_results(
+    State(job_service): State<JobService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(job_id): Path<Uuid>,
+    Query(query): Query<JobResultsQuery>,
+) -> Result<Response> {
+    let job = job_service.get_job(account_id, job_id).await?;
+    let items = job_service.item_results(job.id);
+
+    let (content_type, extension) = match query.format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Jsonl => ("application/x-ndjson", "jsonl"),
+    };
+    let disposition = format!("attachment; filename=\"job-{}-results.{}\"", job.id, extension);
+
+    let format = query.format;
+    let header_line = match format {
+        ExportFormat::Csv => Some(Ok(RESULTS_CSV_HEADER.to_string())),
+        ExportFormat::Jsonl => None,
+    };
+    let body = stream::iter(header_line).chain(items.map(move |item| {
+        item.and_then(|item| match format {
+            ExportFormat::Csv => Ok(result_csv_row(&item)),
+            ExportFormat::Jsonl => {
+                let mut json = serde_json::to_string(&item).map_err(|e| AppError::Internal(e.into()))?;
+                json.push('\n');
+                Ok(json)
+            }
+        })
+    }));
+
+    Ok((
+        [(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        StreamBody::new(body),
+    )
+        .into_response())
+}
+
+fn job_response(job: Job, errors: Vec<JobItemResult>) -> JobResponse {
+    let result_url = format!("/api/v1/jobs/{}/results", job.id);
+    JobResponse {
+        job,
+        errors,
+        result_url,
+    }
+}
+
+fn result_csv_row(item: &JobItemResult) -> String {
+    let fields = [
+        item.index.to_string(),
+        item.status.clone(),
+        csv::escape(item.idempotency_key.as_deref().unwrap_or_default()),
+        item.transaction_id.map(|id| id.to_string()).unwrap_or_default(),
+        item.error_code.map(|code| code.to_string()).unwrap_or_default(),
+        csv::escape(item.error.as_deref().unwrap_or_default()),
+    ];
+    let mut row = fields.join(",");
+    row.push('\n');
+    row
+}
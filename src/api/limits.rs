@@ -0,0 +1,42 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    Extension,
+};
+use uuid::Uuid;
+
+use crate::{
+    api::admin::ensure_role,
+    error::Result,
+    models::{AccountLimitsResponse, AdminIdentity, AdminRole, AuditContext, UpdateAccountLimitsRequest},
+    services::LimitService,
+};
+
+/// The authenticated account's own limits.
+pub async fn get_limits(
+    State(limit_service): State<LimitService>,
+    Extension(account_id): Extension<Uuid>,
+) -> Result<Json<AccountLimitsResponse>> {
+    let limits = limit_service.get_limits(account_id).await?;
+    Ok(Json(AccountLimitsResponse { limits }))
+}
+
+pub async fn admin_get_limits(
+    State(limit_service): State<LimitService>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<AccountLimitsResponse>> {
+    let limits = limit_service.get_limits(account_id).await?;
+    Ok(Json(AccountLimitsResponse { limits }))
+}
+
+pub async fn admin_set_limits(
+    State(limit_service): State<LimitService>,
+    Extension(admin): Extension<AdminIdentity>,
+    Path(account_id): Path<Uuid>,
+    ctx: AuditContext,
+    Json(req): Json<UpdateAccountLimitsRequest>,
+) -> Result<Json<AccountLimitsResponse>> {
+    ensure_role(&admin, &[AdminRole::Finance])?;
+    let limits = limit_service.set_limits(account_id, req, &ctx).await?;
+    Ok(Json(AccountLimitsResponse { limits }))
+}
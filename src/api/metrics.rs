@@ -19,6 +19,21 @@ pub async fn metrics_handler() -> impl IntoResponse {
         .map(|c| c.load(std::sync::atomic::Ordering::Relaxed))
         .unwrap_or(0);
 
+    let reconciliation_runs = crate::metrics::RECONCILIATION_RUNS
+        .get()
+        .map(|c| c.load(std::sync::atomic::Ordering::Relaxed))
+        .unwrap_or(0);
+
+    let balance_discrepancies = crate::metrics::BALANCE_DISCREPANCIES
+        .get()
+        .map(|c| c.load(std::sync::atomic::Ordering::Relaxed))
+        .unwrap_or(0);
+
+    let balance_drift = crate::metrics::BALANCE_DRIFT
+        .get()
+        .map(|c| c.load(std::sync::atomic::Ordering::Relaxed))
+        .unwrap_or(0);
+
     let metrics = format!(
         "# HELP accounts_created_total Total number of accounts created\n\
          # TYPE accounts_created_total counter\n\
@@ -30,8 +45,25 @@ pub async fn metrics_handler() -> impl IntoResponse {
          \n\
          # HELP webhooks_delivered_total Total number of webhooks delivered\n\
          # TYPE webhooks_delivered_total counter\n\
-         webhooks_delivered_total {}\n",
-        accounts_created, transactions_created, webhooks_delivered
+         webhooks_delivered_total {}\n\
+         \n\
+         # HELP reconciliation_runs_total Total number of balance reconciliation runs\n\
+         # TYPE reconciliation_runs_total counter\n\
+         reconciliation_runs_total {}\n\
+         \n\
+         # HELP balance_discrepancies Accounts whose balance did not match their transactions in the last reconciliation run\n\
+         # TYPE balance_discrepancies gauge\n\
+         balance_discrepancies {}\n\
+         \n\
+         # HELP balance_drift_abs_cents Sum of absolute balance drift found in the last reconciliation run\n\
+         # TYPE balance_drift_abs_cents gauge\n\
+         balance_drift_abs_cents {}\n",
+        accounts_created,
+        transactions_created,
+        webhooks_delivered,
+        reconciliation_runs,
+        balance_discrepancies,
+        balance_drift
     );
 
     (StatusCode::OK, metrics)
@@ -1,6 +1,13 @@
 pub mod accounts;
+pub mod admin;
 pub mod auth;
+pub mod events;
+pub mod fee_schedules;
 pub mod health;
+pub mod jobs;
+pub mod limits;
 pub mod metrics;
+pub mod request_context;
+pub mod scheduled_transactions;
 pub mod transactions;
 pub mod webhooks;
\ No newline at end of file
@@ -0,0 +1,109 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, State},
+    http::{request::Parts, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+use uuid::Uuid;
+
+use crate::models::{Actor, AuditContext};
+
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Request id and client address, attached to every request.
+#[derive(Debug, Clone)]
+pub struct RequestMeta {
+    pub request_id: String,
+    pub ip: Option<String>,
+}
+
+/// Assigns each request an id, reusing a well-formed `X-Request-Id` from the
+/// client or proxy, and echoes it on the response.
+pub async fn request_context_middleware(
+    State(trust_forwarded_for): State<bool>,
+    mut request: axum::http::Request<axum::body::Body>,
+    next: Next<axum::body::Body>,
+) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 255)
+        .map(String::from)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let forwarded_for = trust_forwarded_for
+        .then(|| request.headers().get("X-Forwarded-For"))
+        .flatten()
+        .and_then(|h| h.to_str().ok())
+        .and_then(forwarded_client_ip);
+    let ip = forwarded_for.or_else(|| {
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+    });
+
+    request.extensions_mut().insert(RequestMeta {
+        request_id: request_id.clone(),
+        ip,
+    });
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// The client address our proxy saw: the rightmost `X-Forwarded-For` entry,
+/// which the proxy appended. Entries to its left come from the client and
+/// can be anything.
+fn forwarded_client_ip(forwarded_for: &str) -> Option<String> {
+    let hop = forwarded_for.rsplit(',').next()?.trim();
+    hop.parse::<IpAddr>().ok().map(|ip| ip.to_string())
+}
+
+/// Built from the actor set by the auth middleware, if any, and the request
+/// metadata.
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuditContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let actor = parts.extensions.get::<Actor>().cloned().unwrap_or(Actor::Anonymous);
+        let meta = parts.extensions.get::<RequestMeta>();
+
+        Ok(AuditContext {
+            actor,
+            request_id: meta.map(|m| m.request_id.clone()),
+            ip: meta.and_then(|m| m.ip.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_the_hop_the_proxy_appended() {
+        assert_eq!(forwarded_client_ip("203.0.113.7").as_deref(), Some("203.0.113.7"));
+        assert_eq!(
+            forwarded_client_ip("10.0.0.1, 198.51.100.2,203.0.113.7 ").as_deref(),
+            Some("203.0.113.7")
+        );
+        assert_eq!(forwarded_client_ip("1.2.3.4, 2001:db8::1").as_deref(), Some("2001:db8::1"));
+    }
+
+    #[test]
+    fn ignores_a_malformed_last_hop() {
+        assert_eq!(forwarded_client_ip(""), None);
+        assert_eq!(forwarded_client_ip("203.0.113.7, unknown"), None);
+    }
+}
@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::Result,
+    models::{
+        AuditContext, CreateScheduledTransactionRequest, ListScheduledTransactionsQuery,
+        ListScheduledTransactionsResponse, ScheduledTransaction, ScheduledTransactionResponse,
+    },
+    services::ScheduledTransactionService,
+};
+
+pub async fn create_scheduled_transaction(
+    State(service): State<ScheduledTransactionService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    ctx: AuditContext,
+    Json(req): Json<CreateScheduledTransactionRequest>,
+) -> Result<(StatusCode, Json<ScheduledTransaction>)> {
+    let schedule = service.create(account_id, req, &ctx).await?;
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+pub async fn list_scheduled_transactions(
+    State(service): State<ScheduledTransactionService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Query(query): Query<ListScheduledTransactionsQuery>,
+) -> Result<Json<ListScheduledTransactionsResponse>> {
+    let response = service.list(account_id, query).await?;
+    Ok(Json(response))
+}
+
+pub async fn get_scheduled_transaction(
+    State(service): State<ScheduledTransactionService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(schedule_id): Path<Uuid>,
+) -> Result<Json<ScheduledTransactionResponse>> {
+    let response = service.get(account_id, schedule_id).await?;
+    Ok(Json(response))
+}
+
+pub async fn pause_scheduled_transaction(
+    State(service): State<ScheduledTransactionService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(schedule_id): Path<Uuid>,
+    ctx: AuditContext,
+) -> Result<Json<ScheduledTransaction>> {
+    let schedule = service.pause(account_id, schedule_id, &ctx).await?;
+    Ok(Json(schedule))
+}
+
+pub async fn resume_scheduled_transaction(
+    State(service): State<ScheduledTransactionService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(schedule_id): Path<Uuid>,
+    ctx: AuditContext,
+) -> Result<Json<ScheduledTransaction>> {
+    let schedule = service.resume(account_id, schedule_id, &ctx).await?;
+    Ok(Json(schedule))
+}
+
+pub async fn skip_scheduled_transaction(
+    State(service): State<ScheduledTransactionService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(schedule_id): Path<Uuid>,
+    ctx: AuditContext,
+) -> Result<Json<ScheduledTransaction>> {
+    let schedule = service.skip(account_id, schedule_id, &ctx).await?;
+    Ok(Json(schedule))
+}
+
+pub async fn cancel_scheduled_transaction(
+    State(service): State<ScheduledTransactionService>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(schedule_id): Path<Uuid>,
+    ctx: AuditContext,
+) -> Result<Json<ScheduledTransaction>> {
+    let schedule = service.cancel(account_id, schedule_id, &ctx).await?;
+    Ok(Json(schedule))
+}
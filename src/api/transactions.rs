@@ -1,41 +1,200 @@
 use axum::{
-    extract::{Path, State},
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
     response::Json,
 };
 use uuid::Uuid;
 
 use crate::{
-    error::Result,
-    models::{CreateTransactionRequest, TransactionResponse},
-    services::{AccountService, TransactionService, WebhookService},
+    csv,
+    error::{AppError, Result},
+    models::{
+        AuditContext, BatchItemStatus, BatchTransactionQuery, BatchTransactionRequest,
+        BatchTransactionResponse, CreateSplitTransactionRequest, CreateTransactionRequest, TransactionResponse,
+    },
+    services::{transaction::publish_transaction, AccountService, EventService, TransactionService, WebhookService},
 };
 
 pub async fn create_transaction(
-    State((_account_service, transaction_service, webhook_service)): State<(
+    State((_account_service, transaction_service, webhook_service, _event_service)): State<(
         AccountService,
         TransactionService,
         WebhookService,
+        EventService,
     )>,
     axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    ctx: AuditContext,
     Json(req): Json<CreateTransactionRequest>,
 ) -> Result<Json<TransactionResponse>> {
     let response = transaction_service
-        .create_transaction(account_id, req)
+        .create_transaction(account_id, req, &ctx)
         .await?;
 
-    let webhook_service_clone = webhook_service.clone();
-    let transaction_clone = response.transaction.clone();
-    tokio::spawn(async move {
-        let _ = webhook_service_clone.deliver_webhook(&transaction_clone).await;
-    });
+    publish_transaction(&webhook_service, &response.transaction).await;
+
+    Ok(Json(response))
+}
+
+pub async fn create_split_transaction(
+    State((_account_service, transaction_service, webhook_service, _event_service)): State<(
+        AccountService,
+        TransactionService,
+        WebhookService,
+        EventService,
+    )>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    ctx: AuditContext,
+    Json(req): Json<CreateSplitTransactionRequest>,
+) -> Result<Json<TransactionResponse>> {
+    let response = transaction_service
+        .create_split(account_id, req, &ctx)
+        .await?;
+
+    publish_transaction(&webhook_service, &response.transaction).await;
+    for leg in &response.legs {
+        publish_transaction(&webhook_service, leg).await;
+    }
+
+    Ok(Json(response))
+}
+
+pub async fn create_transaction_batch(
+    State((_account_service, transaction_service, webhook_service, _event_service)): State<(
+        AccountService,
+        TransactionService,
+        WebhookService,
+        EventService,
+    )>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Query(query): Query<BatchTransactionQuery>,
+    ctx: AuditContext,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<BatchTransactionResponse>> {
+    let (items, atomic) = parse_batch_body(&headers, &body, &query)?;
+
+    let response = transaction_service
+        .create_batch(account_id, items, atomic, &ctx)
+        .await?;
+
+    for result in &response.results {
+        if let (BatchItemStatus::Created, Some(transaction)) = (result.status, &result.transaction) {
+            publish_transaction(&webhook_service, transaction).await;
+        }
+    }
 
     Ok(Json(response))
 }
 
 pub async fn get_transaction(
-    State((_, transaction_service, _)): State<(AccountService, TransactionService, WebhookService)>,
+    State((_, transaction_service, _, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(caller_id): axum::extract::Extension<Uuid>,
     Path(transaction_id): Path<Uuid>,
 ) -> Result<Json<TransactionResponse>> {
-    let transaction = transaction_service.get_transaction(transaction_id).await?;
-    Ok(Json(TransactionResponse { transaction }))
+    let mut transaction = transaction_service.get_transaction(transaction_id).await?;
+
+    if transaction.account_id == caller_id {
+        let response = transaction_service.transaction_response(transaction).await?;
+        return Ok(Json(response));
+    }
+
+    // The receiving side of a transfer sees the transfer itself, but not the
+    // sender's fees or idempotency key.
+    if transaction.r#type == "transfer" && transaction.counterparty_account_id == Some(caller_id) {
+        transaction.idempotency_key = None;
+        return Ok(Json(TransactionResponse {
+            transaction,
+            legs: Vec::new(),
+            fees: Vec::new(),
+        }));
+    }
+
+    Err(AppError::TransactionNotFound {
+        transaction_id: transaction_id.to_string(),
+    })
+}
+
+/// Reads a batch from either a JSON body (`{"atomic": ..., "transactions": [...]}`)
+/// or, with `Content-Type: text/csv`, one transaction per row under a header
+/// row and `atomic` as a query parameter. Shared with job submission.
+pub fn parse_batch_body(
+    headers: &HeaderMap,
+    body: &Bytes,
+    query: &BatchTransactionQuery,
+) -> Result<(Vec<CreateTransactionRequest>, bool)> {
+    let is_csv = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim_start().to_ascii_lowercase().starts_with("text/csv"))
+        .unwrap_or(false);
+
+    if is_csv {
+        let text = std::str::from_utf8(body)
+            .map_err(|_| AppError::InvalidBatch("CSV body is not valid UTF-8".to_string()))?;
+        Ok((parse_csv_batch(text)?, query.atomic))
+    } else {
+        let req: BatchTransactionRequest =
+            serde_json::from_slice(body).map_err(|e| AppError::InvalidBatch(e.to_string()))?;
+        Ok((req.transactions, req.atomic))
+    }
+}
+
+const BATCH_CSV_COLUMNS: [&str; 5] = ["idempotency_key", "type", "amount", "description", "counterparty_account_id"];
+
+/// Maps CSV rows to requests by header name. `type` and `amount` are required
+/// columns; the rest are optional, and empty cells mean "not set".
+fn parse_csv_batch(text: &str) -> Result<Vec<CreateTransactionRequest>> {
+    let mut records = csv::parse(text).map_err(AppError::InvalidBatch)?.into_iter();
+    let (_, header) = records
+        .next()
+        .ok_or_else(|| AppError::InvalidBatch("CSV body has no header row".to_string()))?;
+
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_ascii_lowercase()).collect();
+    if let Some(unknown) = header.iter().find(|h| !BATCH_CSV_COLUMNS.contains(&h.as_str())) {
+        return Err(AppError::InvalidBatch(format!("unknown CSV column '{}'", unknown)));
+    }
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let (Some(type_column), Some(amount_column)) = (column("type"), column("amount")) else {
+        return Err(AppError::InvalidBatch("CSV header must include 'type' and 'amount'".to_string()));
+    };
+    let key_column = column("idempotency_key");
+    let description_column = column("description");
+    let counterparty_column = column("counterparty_account_id");
+
+    records
+        .map(|(line, row)| {
+            if row.len() != header.len() {
+                return Err(AppError::InvalidBatch(format!(
+                    "line {}: expected {} fields, found {}",
+                    line,
+                    header.len(),
+                    row.len()
+                )));
+            }
+            let cell = |index: Option<usize>| {
+                index
+                    .map(|i| row[i].trim())
+                    .filter(|v| !v.is_empty())
+                    .map(String::from)
+            };
+
+            let amount = row[amount_column]
+                .trim()
+                .parse()
+                .map_err(|_| AppError::InvalidBatch(format!("line {}: invalid amount", line)))?;
+            let counterparty_account_id = cell(counterparty_column)
+                .map(|v| v.parse::<Uuid>())
+                .transpose()
+                .map_err(|_| AppError::InvalidBatch(format!("line {}: invalid counterparty_account_id", line)))?;
+
+            Ok(CreateTransactionRequest {
+                idempotency_key: cell(key_column),
+                r#type: row[type_column].trim().to_string(),
+                amount,
+                description: cell(description_column),
+                counterparty_account_id,
+            })
+        })
+        .collect()
 }
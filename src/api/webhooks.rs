@@ -6,42 +6,117 @@ use uuid::Uuid;
 
 use crate::{
     error::Result,
-    models::{CreateWebhookRequest, WebhookResponse},
-    services::{AccountService, TransactionService, WebhookService},
+    events::EVENT_CATALOG,
+    models::{
+        AuditContext, CreateWebhookRequest, EnableWebhookRequest, EnableWebhookResponse, ReplayWebhookRequest, ReplayWebhookResponse,
+        RotateWebhookSecretRequest, WebhookDeliveryResponse, WebhookEventsResponse,
+        WebhookResponse, WebhookSecretResponse,
+    },
+    services::{AccountService, EventService, TransactionService, WebhookService},
 };
 
 pub async fn register_webhook(
-    State((_, _, webhook_service)): State<(AccountService, TransactionService, WebhookService)>,
+    State((_, _, webhook_service, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
     axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    ctx: AuditContext,
     Json(req): Json<CreateWebhookRequest>,
-) -> Result<Json<WebhookResponse>> {
-    let response = webhook_service.create_webhook(account_id, req).await?;
+) -> Result<Json<WebhookSecretResponse>> {
+    let response = webhook_service.create_webhook(account_id, req, &ctx).await?;
     Ok(Json(response))
 }
 
 pub async fn get_webhook(
-    State((_, _, webhook_service)): State<(AccountService, TransactionService, WebhookService)>,
+    State((_, _, webhook_service, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
     Path(webhook_id): Path<Uuid>,
 ) -> Result<Json<WebhookResponse>> {
-    let webhook = webhook_service.get_webhook(webhook_id).await?;
+    let webhook = webhook_service.get_account_webhook(account_id, webhook_id).await?;
     Ok(Json(WebhookResponse { webhook }))
 }
 
 pub async fn update_webhook(
-    State((_, _, webhook_service)): State<(AccountService, TransactionService, WebhookService)>,
+    State((_, _, webhook_service, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
     Path(webhook_id): Path<Uuid>,
+    ctx: AuditContext,
     Json(req): Json<CreateWebhookRequest>,
 ) -> Result<Json<WebhookResponse>> {
-    let response = webhook_service.update_webhook(webhook_id, req).await?;
+    let response = webhook_service.update_webhook(account_id, webhook_id, req, &ctx).await?;
     Ok(Json(response))
 }
 
 pub async fn delete_webhook(
-    State((_, _, webhook_service)): State<(AccountService, TransactionService, WebhookService)>,
+    State((_, _, webhook_service, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
     Path(webhook_id): Path<Uuid>,
+    ctx: AuditContext,
 ) -> Result<Json<serde_json::Value>> {
-    webhook_service.delete_webhook(webhook_id).await?;
+    webhook_service.delete_webhook(account_id, webhook_id, &ctx).await?;
     Ok(Json(serde_json::json!({
         "message": "Webhook deleted successfully"
     })))
-}
\ No newline at end of file
+}
+
+pub async fn redeliver_webhook(
+    State((_, _, webhook_service, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path((webhook_id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<WebhookDeliveryResponse>> {
+    let webhook_delivery = webhook_service
+        .redeliver(account_id, webhook_id, delivery_id)
+        .await?;
+    Ok(Json(WebhookDeliveryResponse { webhook_delivery }))
+}
+
+pub async fn replay_webhook(
+    State((_, _, webhook_service, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(webhook_id): Path<Uuid>,
+    Json(req): Json<ReplayWebhookRequest>,
+) -> Result<Json<ReplayWebhookResponse>> {
+    let response = webhook_service.replay(account_id, webhook_id, req).await?;
+    Ok(Json(response))
+}
+
+pub async fn rotate_webhook_secret(
+    State((_, _, webhook_service, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(webhook_id): Path<Uuid>,
+    ctx: AuditContext,
+    Json(req): Json<RotateWebhookSecretRequest>,
+) -> Result<Json<WebhookSecretResponse>> {
+    let response = webhook_service
+        .rotate_secret(account_id, webhook_id, req, &ctx)
+        .await?;
+    Ok(Json(response))
+}
+
+pub async fn list_webhook_events() -> Json<WebhookEventsResponse> {
+    Json(WebhookEventsResponse {
+        events: EVENT_CATALOG,
+    })
+}
+
+pub async fn test_webhook(
+    State((_, _, webhook_service, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(webhook_id): Path<Uuid>,
+) -> Result<Json<WebhookDeliveryResponse>> {
+    let webhook_delivery = webhook_service
+        .send_test_event(account_id, webhook_id)
+        .await?;
+    Ok(Json(WebhookDeliveryResponse { webhook_delivery }))
+}
+
+pub async fn enable_webhook(
+    State((_, _, webhook_service, _)): State<(AccountService, TransactionService, WebhookService, EventService)>,
+    axum::extract::Extension(account_id): axum::extract::Extension<Uuid>,
+    Path(webhook_id): Path<Uuid>,
+    ctx: AuditContext,
+    Json(req): Json<EnableWebhookRequest>,
+) -> Result<Json<EnableWebhookResponse>> {
+    let response = webhook_service
+        .enable_webhook(account_id, webhook_id, req, &ctx)
+        .await?;
+    Ok(Json(response))
+}
@@ -0,0 +1,37 @@
+use crate::services::AccountService;
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+/// How many past UTC days each run recomputes. The ledger is keyed on when a
+/// transaction was created, so one that commits after midnight still belongs
+/// to the day before; recomputing keeps those days' snapshots correct.
+const RECOMPUTE_DAYS: i64 = 2;
+
+/// Snapshots the last few UTC days' closing balances every hour, oldest day
+/// first so each snapshot builds on an already corrected one.
+pub async fn start_balance_snapshot_scheduler(account_service: Arc<AccountService>) -> anyhow::Result<()> {
+    let sched = JobScheduler::new().await?;
+
+    sched
+        .add(Job::new_async("0 15 * * * *", move |_uuid, _l| {
+            let account_service = account_service.clone();
+            Box::pin(async move {
+                let today = Utc::now().date_naive();
+                for days_ago in (1..=RECOMPUTE_DAYS).rev() {
+                    let date = today - Duration::days(days_ago);
+                    if let Err(e) = account_service.snapshot_balances(date).await {
+                        tracing::error!("Failed to snapshot balances for {}: {}", date, e);
+                        break;
+                    }
+                }
+            })
+        })?)
+        .await?;
+
+    sched.start().await?;
+
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+    }
+}
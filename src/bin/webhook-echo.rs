@@ -0,0 +1,275 @@
+//! Local webhook receiver for exercising the delivery pipeline end to end.
+//!
+//! Accepts deliveries on any path, verifies `X-Webhook-Signature` when
+//! `ECHO_SECRETS` is set, and answers with `ECHO_RESPONSE_STATUS` (default 200)
+//! so failure handling can be tested too. Everything received is kept in memory
+//! and can be inspected with `GET /received` or cleared with `DELETE /received`.
+//!
+//! ```text
+//! ECHO_PORT=4000 ECHO_SECRETS=whsec_... cargo run --bin webhook-echo
+//! ```
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Json},
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+use std::{
+    env,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Clone)]
+struct EchoState {
+    secrets: Arc<Vec<String>>,
+    response_status: StatusCode,
+    received: Arc<Mutex<Vec<ReceivedDelivery>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ReceivedDelivery {
+    path: String,
+    event: Option<String>,
+    signature: Option<String>,
+    verified: Option<bool>,
+    verification_error: Option<String>,
+    body: serde_json::Value,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "webhook_echo=info".into()),
+        )
+        .init();
+
+    let port: u16 = env::var("ECHO_PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(4000);
+    let secrets = env::var("ECHO_SECRETS")
+        .map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    let response_status = env::var("ECHO_RESPONSE_STATUS")
+        .ok()
+        .and_then(|v| v.parse::<u16>().ok())
+        .and_then(|v| StatusCode::from_u16(v).ok())
+        .unwrap_or(StatusCode::OK);
+
+    let state = EchoState {
+        secrets: Arc::new(secrets),
+        response_status,
+        received: Arc::new(Mutex::new(Vec::new())),
+    };
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    tracing::info!("Webhook echo receiver listening on {}", addr);
+
+    axum::Server::bind(&addr)
+        .serve(app(state).into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+fn app(state: EchoState) -> Router {
+    Router::new()
+        .route("/received", get(list_received).delete(clear_received))
+        .fallback(receive)
+        .with_state(state)
+}
+
+async fn receive(
+    State(state): State<EchoState>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    };
+    let signature = header(webhook_signature::SIGNATURE_HEADER);
+
+    let (verified, verification_error) = if state.secrets.is_empty() {
+        (None, None)
+    } else {
+        let secrets: Vec<&str> = state.secrets.iter().map(String::as_str).collect();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        let result = signature
+            .as_deref()
+            .ok_or(webhook_signature::SignatureError::MalformedHeader)
+            .and_then(|signature| {
+                webhook_signature::verify(
+                    signature,
+                    &body,
+                    &secrets,
+                    webhook_signature::DEFAULT_TOLERANCE_SECS,
+                    now,
+                )
+            });
+        match result {
+            Ok(_) => (Some(true), None),
+            Err(e) => (Some(false), Some(e.to_string())),
+        }
+    };
+
+    let delivery = ReceivedDelivery {
+        path: uri.path().to_string(),
+        event: header("X-Webhook-Event"),
+        signature,
+        verified,
+        verification_error,
+        body: serde_json::from_slice(&body)
+            .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(&body).into())),
+    };
+
+    tracing::info!(
+        path = %delivery.path,
+        event = ?delivery.event,
+        verified = ?delivery.verified,
+        "Received webhook delivery"
+    );
+
+    state
+        .received
+        .lock()
+        .expect("received deliveries lock poisoned")
+        .push(delivery.clone());
+
+    let status = if delivery.verified == Some(false) {
+        StatusCode::UNAUTHORIZED
+    } else {
+        state.response_status
+    };
+
+    (status, Json(delivery))
+}
+
+async fn list_received(State(state): State<EchoState>) -> Json<Vec<ReceivedDelivery>> {
+    Json(
+        state
+            .received
+            .lock()
+            .expect("received deliveries lock poisoned")
+            .clone(),
+    )
+}
+
+async fn clear_received(State(state): State<EchoState>) -> StatusCode {
+    state
+        .received
+        .lock()
+        .expect("received deliveries lock poisoned")
+        .clear();
+    StatusCode::NO_CONTENT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    const SECRET: &str = "whsec_test";
+
+    fn state(response_status: StatusCode) -> EchoState {
+        EchoState {
+            secrets: Arc::new(vec![SECRET.to_string()]),
+            response_status,
+            received: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// A `webhook.test` delivery signed and sent the way the service does it.
+    fn webhook_test(secrets: &[&str]) -> Request<Body> {
+        let body = serde_json::to_vec(&json!({
+            "event": "webhook.test",
+            "delivery_id": "7d0e3c1a-2b4f-4c1e-9a7d-1f2e3d4c5b6a",
+            "data": { "message": "This is a test event sent to verify your webhook endpoint" },
+            "timestamp": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+        Request::post("/hooks/orders")
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Event", "webhook.test")
+            .header(
+                webhook_signature::SIGNATURE_HEADER,
+                webhook_signature::signature_header(secrets, timestamp, &body),
+            )
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    async fn send(state: &EchoState, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = app(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn verifies_a_signed_webhook_test() {
+        let state = state(StatusCode::OK);
+
+        let (status, delivery) = send(&state, webhook_test(&[SECRET])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(delivery["verified"], true);
+        assert_eq!(delivery["event"], "webhook.test");
+        assert_eq!(delivery["path"], "/hooks/orders");
+
+        let (_, received) = send(&state, Request::get("/received").body(Body::empty()).unwrap()).await;
+        assert_eq!(received.as_array().unwrap().len(), 1);
+        assert_eq!(received[0]["body"]["event"], "webhook.test");
+    }
+
+    #[tokio::test]
+    async fn accepts_a_signature_from_any_current_secret() {
+        let state = state(StatusCode::OK);
+
+        let (status, delivery) = send(&state, webhook_test(&["whsec_old", SECRET])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(delivery["verified"], true);
+    }
+
+    #[tokio::test]
+    async fn rejects_a_delivery_signed_with_another_secret() {
+        let state = state(StatusCode::OK);
+
+        let (status, delivery) = send(&state, webhook_test(&["whsec_other"])).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(delivery["verified"], false);
+        assert!(delivery["verification_error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn answers_verified_deliveries_with_the_configured_status() {
+        let state = state(StatusCode::SERVICE_UNAVAILABLE);
+
+        let (status, delivery) = send(&state, webhook_test(&[SECRET])).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(delivery["verified"], true);
+    }
+}
@@ -0,0 +1,242 @@
+use anyhow::{anyhow, bail, Result};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, sync::Arc};
+
+pub const KEY_LEN: usize = 32;
+
+const ENCRYPTED_PREFIX: &str = "enc:v2:";
+const LEGACY_ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Encrypts secrets stored at rest (AES-256-GCM, keys from `WEBHOOK_ENCRYPTION_KEY`).
+///
+/// Stored values look like `enc:v2:<key version>:<hex nonce><hex ciphertext>`,
+/// so values written under a retired key can still be read after rotation.
+/// `enc:v1:` values predate key versions and were encrypted with a key derived
+/// from `WEBHOOK_SECRET`; they can only be read when that legacy key is given.
+/// Values without a prefix are legacy plaintext and are returned as-is by
+/// [`SecretCipher::decrypt`].
+#[derive(Clone)]
+pub struct SecretCipher {
+    current_version: u32,
+    keys: Arc<HashMap<u32, LessSafeKey>>,
+    legacy_key: Option<Arc<LessSafeKey>>,
+    rng: SystemRandom,
+}
+
+impl SecretCipher {
+    /// `keys` must include `current_version`, which new values are encrypted
+    /// with; the others are only used to decrypt.
+    pub fn new(current_version: u32, keys: &[(u32, [u8; KEY_LEN])], legacy_master_key: Option<&str>) -> Result<Self> {
+        let mut by_version = HashMap::new();
+        for (version, key_bytes) in keys {
+            if by_version.insert(*version, aead_key(key_bytes)).is_some() {
+                bail!("Duplicate encryption key version {}", version);
+            }
+        }
+        if !by_version.contains_key(&current_version) {
+            bail!("No encryption key for the current version {}", current_version);
+        }
+
+        let legacy_key = legacy_master_key.map(|master_key| Arc::new(aead_key(&Sha256::digest(master_key.as_bytes()).into())));
+
+        Ok(Self {
+            current_version,
+            keys: Arc::new(by_version),
+            legacy_key,
+            rng: SystemRandom::new(),
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce_bytes)
+            .map_err(|_| anyhow!("Failed to generate nonce"))?;
+
+        let mut in_out = plaintext.as_bytes().to_vec();
+        self.keys[&self.current_version]
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce_bytes), Aad::empty(), &mut in_out)
+            .map_err(|_| anyhow!("Failed to encrypt secret"))?;
+
+        Ok(format!(
+            "{}{}:{}{}",
+            ENCRYPTED_PREFIX,
+            self.current_version,
+            hex::encode(nonce_bytes),
+            hex::encode(in_out)
+        ))
+    }
+
+    pub fn decrypt(&self, stored: &str) -> Result<String> {
+        if let Some(versioned) = stored.strip_prefix(ENCRYPTED_PREFIX) {
+            let (version, encoded) = versioned
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Encrypted secret has no key version"))?;
+            let version: u32 = version.parse()?;
+            let key = self
+                .keys
+                .get(&version)
+                .ok_or_else(|| anyhow!("No encryption key for version {}", version))?;
+            return open(key, encoded);
+        }
+
+        if let Some(encoded) = stored.strip_prefix(LEGACY_ENCRYPTED_PREFIX) {
+            let key = self
+                .legacy_key
+                .as_ref()
+                .ok_or_else(|| anyhow!("Secret uses the legacy format; set WEBHOOK_SECRET to read it"))?;
+            return open(key, encoded);
+        }
+
+        Ok(stored.to_string())
+    }
+
+    /// Whether `stored` should be rewritten under the current key: it's
+    /// plaintext, legacy, or encrypted with a retired key.
+    pub fn needs_reencryption(&self, stored: &str) -> bool {
+        !stored.starts_with(&format!("{}{}:", ENCRYPTED_PREFIX, self.current_version))
+    }
+
+    /// Generates a new random webhook signing secret.
+    pub fn generate_secret(&self) -> Result<String> {
+        let mut bytes = [0u8; 32];
+        self.rng
+            .fill(&mut bytes)
+            .map_err(|_| anyhow!("Failed to generate secret"))?;
+        Ok(format!("whsec_{}", hex::encode(bytes)))
+    }
+}
+
+/// Parses a hex-encoded 256-bit key, as generated by `openssl rand -hex 32`.
+pub fn parse_key(encoded: &str) -> Result<[u8; KEY_LEN]> {
+    let bytes = hex::decode(encoded.trim()).map_err(|_| anyhow!("Encryption key must be hex-encoded"))?;
+    <[u8; KEY_LEN]>::try_from(bytes).map_err(|_| anyhow!("Encryption key must be {} bytes", KEY_LEN))
+}
+
+fn aead_key(key_bytes: &[u8; KEY_LEN]) -> LessSafeKey {
+    let key = UnboundKey::new(&AES_256_GCM, key_bytes).expect("a 32-byte key is a valid AES-256 key");
+    LessSafeKey::new(key)
+}
+
+fn open(key: &LessSafeKey, encoded: &str) -> Result<String> {
+    let bytes = hex::decode(encoded)?;
+    if bytes.len() < NONCE_LEN {
+        return Err(anyhow!("Encrypted secret is truncated"));
+    }
+    let (nonce_bytes, ciphertext) = bytes.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce_bytes)
+        .map_err(|_| anyhow!("Invalid nonce"))?;
+
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::empty(), &mut in_out)
+        .map_err(|_| anyhow!("Failed to decrypt secret"))?;
+
+    Ok(String::from_utf8(plaintext.to_vec())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_1: [u8; KEY_LEN] = [1; KEY_LEN];
+    const KEY_2: [u8; KEY_LEN] = [2; KEY_LEN];
+
+    fn legacy_encrypt(master_key: &str, plaintext: &str) -> String {
+        let key = aead_key(&Sha256::digest(master_key.as_bytes()).into());
+        let nonce_bytes = [7u8; NONCE_LEN];
+        let mut in_out = plaintext.as_bytes().to_vec();
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce_bytes), Aad::empty(), &mut in_out)
+            .unwrap();
+        format!("{}{}{}", LEGACY_ENCRYPTED_PREFIX, hex::encode(nonce_bytes), hex::encode(in_out))
+    }
+
+    #[test]
+    fn round_trips() {
+        let cipher = SecretCipher::new(1, &[(1, KEY_1)], None).unwrap();
+
+        for plaintext in ["whsec_abc", "", "ünïcødé"] {
+            let stored = cipher.encrypt(plaintext).unwrap();
+            assert!(stored.starts_with("enc:v2:1:"));
+            assert_eq!(cipher.decrypt(&stored).unwrap(), plaintext);
+        }
+    }
+
+    #[test]
+    fn uses_a_fresh_nonce_per_value() {
+        let cipher = SecretCipher::new(1, &[(1, KEY_1)], None).unwrap();
+        assert_ne!(cipher.encrypt("whsec_abc").unwrap(), cipher.encrypt("whsec_abc").unwrap());
+    }
+
+    #[test]
+    fn decrypts_values_from_retired_keys() {
+        let old = SecretCipher::new(1, &[(1, KEY_1)], None).unwrap();
+        let stored = old.encrypt("whsec_abc").unwrap();
+
+        let rotated = SecretCipher::new(2, &[(2, KEY_2), (1, KEY_1)], None).unwrap();
+        assert!(rotated.needs_reencryption(&stored));
+        assert_eq!(rotated.decrypt(&stored).unwrap(), "whsec_abc");
+
+        let reencrypted = rotated.encrypt("whsec_abc").unwrap();
+        assert!(reencrypted.starts_with("enc:v2:2:"));
+        assert!(!rotated.needs_reencryption(&reencrypted));
+    }
+
+    #[test]
+    fn rejects_unknown_versions_and_wrong_keys() {
+        let stored = SecretCipher::new(1, &[(1, KEY_1)], None).unwrap().encrypt("whsec_abc").unwrap();
+
+        let without_key = SecretCipher::new(2, &[(2, KEY_2)], None).unwrap();
+        assert!(without_key.decrypt(&stored).is_err());
+
+        let wrong_key = SecretCipher::new(1, &[(1, KEY_2)], None).unwrap();
+        assert!(wrong_key.decrypt(&stored).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext() {
+        let cipher = SecretCipher::new(1, &[(1, KEY_1)], None).unwrap();
+        let mut stored = cipher.encrypt("whsec_abc").unwrap();
+        let last = stored.pop().unwrap();
+        stored.push(if last == '0' { '1' } else { '0' });
+
+        assert!(cipher.decrypt(&stored).is_err());
+        assert!(cipher.decrypt("enc:v2:1:00").is_err());
+        assert!(cipher.decrypt("enc:v2:x:00").is_err());
+    }
+
+    #[test]
+    fn reads_legacy_values_only_with_the_legacy_key() {
+        let stored = legacy_encrypt("old-master-key", "whsec_abc");
+
+        let cipher = SecretCipher::new(1, &[(1, KEY_1)], Some("old-master-key")).unwrap();
+        assert!(cipher.needs_reencryption(&stored));
+        assert_eq!(cipher.decrypt(&stored).unwrap(), "whsec_abc");
+
+        assert!(SecretCipher::new(1, &[(1, KEY_1)], None).unwrap().decrypt(&stored).is_err());
+    }
+
+    #[test]
+    fn passes_plaintext_through() {
+        let cipher = SecretCipher::new(1, &[(1, KEY_1)], None).unwrap();
+        assert!(cipher.needs_reencryption("whsec_plain"));
+        assert_eq!(cipher.decrypt("whsec_plain").unwrap(), "whsec_plain");
+    }
+
+    #[test]
+    fn requires_a_key_for_the_current_version() {
+        assert!(SecretCipher::new(2, &[(1, KEY_1)], None).is_err());
+        assert!(SecretCipher::new(1, &[(1, KEY_1), (1, KEY_2)], None).is_err());
+    }
+
+    #[test]
+    fn parses_hex_keys() {
+        assert_eq!(parse_key(&"01".repeat(KEY_LEN)).unwrap(), KEY_1);
+        assert!(parse_key("01").is_err());
+        assert!(parse_key(&"zz".repeat(KEY_LEN)).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Minimal RFC 4180 CSV support for statement exports and batch uploads.
+
+/// Quotes a field when it contains a delimiter, quote or line break.
+///
+/// Fields are free text from API callers, so one starting with `=`, `+`,
+/// `-`, `@`, a tab or a carriage return is prefixed with `'`; spreadsheets
+/// would otherwise evaluate it as a formula when the file is opened.
+pub fn escape(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Splits `input` into records of fields. Quoted fields may contain commas,
+/// doubled quotes and line breaks; blank lines are ignored. Each record is
+/// returned with the line number it starts on, for error messages.
+pub fn parse(input: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_record = false;
+    let mut in_quotes = false;
+    let mut field_quoted = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() && !field_quoted => {
+                in_quotes = true;
+                field_quoted = true;
+                in_record = true;
+            }
+            ',' => {
+                record.push(std::mem::take(&mut field));
+                field_quoted = false;
+                in_record = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                if in_record || !field.is_empty() {
+                    record.push(std::mem::take(&mut field));
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                in_record = false;
+                field_quoted = false;
+                line += 1;
+                record_line = line;
+            }
+            _ if field_quoted => return Err(format!("line {}: unexpected character after quoted field", line)),
+            '"' => return Err(format!("line {}: unexpected quote", line)),
+            _ => {
+                field.push(c);
+                in_record = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("line {}: unterminated quoted field", record_line));
+    }
+    if in_record || !field.is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_fields_that_need_quoting() {
+        assert_eq!(escape("plain text"), "plain text");
+        assert_eq!(escape(""), "");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn neutralizes_formulas() {
+        assert_eq!(escape("=HYPERLINK(\"http://x\")"), "\"'=HYPERLINK(\"\"http://x\"\")\"");
+        assert_eq!(escape("+1"), "'+1");
+        assert_eq!(escape("-5 refund"), "'-5 refund");
+        assert_eq!(escape("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape("\tx"), "'\tx");
+        assert_eq!(escape("a=b"), "a=b");
+    }
+
+    #[test]
+    fn parses_records_with_line_numbers() {
+        let records = parse("type,amount\r\ncredit,10\n\ndebit,5").unwrap();
+        assert_eq!(
+            records,
+            vec![
+                (1, vec!["type".to_string(), "amount".to_string()]),
+                (2, vec!["credit".to_string(), "10".to_string()]),
+                (4, vec!["debit".to_string(), "5".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_quoted_fields() {
+        let records = parse("\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\nnext,\"\",x\n").unwrap();
+        assert_eq!(records[0], (1, vec!["a,b".into(), "say \"hi\"".into(), "two\nlines".into(), String::new()]));
+        assert_eq!(records[1], (3, vec!["next".into(), String::new(), "x".into()]));
+        assert_eq!(records.len(), 2);
+    }
+
+    #[test]
+    fn round_trips_escaped_fields() {
+        let fields = ["a,b", "say \"hi\"", "two\r\nlines", "plain"];
+        let line = fields.map(escape).join(",");
+        assert_eq!(parse(&line).unwrap(), vec![(1, fields.map(String::from).to_vec())]);
+    }
+
+    #[test]
+    fn rejects_malformed_quoting() {
+        assert_eq!(parse("a,\"b").unwrap_err(), "line 1: unterminated quoted field");
+        assert_eq!(parse("a,\"b\"c").unwrap_err(), "line 1: unexpected character after quoted field");
+        assert_eq!(parse("a,b\"c").unwrap_err(), "line 1: unexpected quote");
+        assert_eq!(parse("x\n\"a\nb").unwrap_err(), "line 2: unterminated quoted field");
+    }
+
+    #[test]
+    fn ignores_blank_input() {
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse("\n\r\n").unwrap().is_empty());
+    }
+}
@@ -3,6 +3,7 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde_json::json;
 use thiserror::Error;
 
@@ -17,23 +18,80 @@ pub enum AppError {
      #[error("Account not found: {account_id}")]
      AccountNotFound { account_id: String },
 
+     #[error("Account {account_id} is frozen")]
+     AccountFrozen { account_id: String },
+
+     #[error("Account {account_id} is closed")]
+     AccountClosed { account_id: String },
+
+     #[error("Account cannot change status from {from} to {to}")]
+     InvalidAccountStatusTransition { from: String, to: String },
+
+     #[error("Account {account_id} has a non-zero balance of {balance}")]
+     AccountBalanceNotZero { account_id: String, balance: i64 },
+
+     #[error("Email already in use: {email}")]
+     EmailAlreadyInUse { email: String },
+
      #[error("Insufficient funds: account {account_id} has balance {balance}, required {required}")]
      InsufficientFunds{ account_id: String, balance: i64, required: i64},
 
+     #[error(
+         "Transaction limit exceeded: {limit} of {max}{}",
+         .resets_at.map(|at| format!(", resets at {}", at.to_rfc3339())).unwrap_or_default()
+     )]
+     LimitExceeded { limit: String, max: i64, resets_at: Option<DateTime<Utc>> },
+
      #[error("Transaction not found: {transaction_id}")]
      TransactionNotFound { transaction_id: String},
 
      #[error("Invalid API key")]
      InvalidApiKey,
 
+     #[error("Invalid admin token")]
+     InvalidAdminToken,
+
+     #[error("Admin role '{role}' is not permitted to perform this action")]
+     AdminForbidden { role: String },
+
+     #[error("Self-service signup is disabled; accounts are created by an administrator")]
+     SignupDisabled,
+
+     #[error("Admin token not found: {token_id}")]
+     AdminTokenNotFound { token_id: String },
+
+     #[error("Reconciliation run not found: {run_id}")]
+     ReconciliationRunNotFound { run_id: String },
+
+     #[error("A reconciliation run is already in progress")]
+     ReconciliationInProgress,
+
      #[error("Webhook not found: {webhook_id}")]
      WebhookNotFound {webhook_id: String},
 
-     #[error("Webhook delivery failed: {0}")]
-     WebhookDeliveryFailed(String),
+     #[error("Webhook delivery not found: {delivery_id}")]
+     WebhookDeliveryNotFound {delivery_id: String},
+
+     #[error("Webhook URL not allowed: {0}")]
+     WebhookUrlNotAllowed(String),
 
-     #[error("Idempotency key already used: {Key}")]
-     IdempotencyKeyUsed {Key: String},
+     #[error("Job not found: {job_id}")]
+     JobNotFound { job_id: String },
+
+     #[error("Scheduled transaction not found: {schedule_id}")]
+     ScheduledTransactionNotFound { schedule_id: String },
+
+     #[error("Cannot {action} a scheduled transaction that is {status}")]
+     InvalidScheduleAction { action: String, status: String },
+
+     #[error("No {transaction_type} fee schedule for account {account_id}")]
+     FeeScheduleNotFound { account_id: String, transaction_type: String },
+
+     #[error("Fees are disabled: no platform revenue account is configured")]
+     FeesDisabled,
+
+     #[error("Invalid batch: {0}")]
+     InvalidBatch(String),
 
      #[error("Rate limit exceeded")]
      RateLimitExceeded,
@@ -42,21 +100,47 @@ pub enum AppError {
      Internal(#[from] anyhow::Error),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let(status, error_message) = match self {
+impl AppError {
+    /// HTTP status and client-facing message, shared by error responses and
+    /// per-item results in batch responses.
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
             AppError::AccountNotFound { .. } => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::AccountFrozen { .. } => (StatusCode::FORBIDDEN, self.to_string()),
+            AppError::AccountClosed { .. } => (StatusCode::FORBIDDEN, self.to_string()),
+            AppError::InvalidAccountStatusTransition { .. } => (StatusCode::CONFLICT, self.to_string()),
+            AppError::AccountBalanceNotZero { .. } => (StatusCode::CONFLICT, self.to_string()),
+            AppError::EmailAlreadyInUse { .. } => (StatusCode::CONFLICT, self.to_string()),
             AppError::TransactionNotFound { .. } => (StatusCode::NOT_FOUND, self.to_string()),
             AppError::WebhookNotFound { .. } => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::WebhookDeliveryNotFound { .. } => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::AdminTokenNotFound { .. } => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::ReconciliationRunNotFound { .. } => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::JobNotFound { .. } => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::ScheduledTransactionNotFound { .. } => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::InvalidScheduleAction { .. } => (StatusCode::CONFLICT, self.to_string()),
+            AppError::FeeScheduleNotFound { .. } => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::FeesDisabled => (StatusCode::CONFLICT, self.to_string()),
+            AppError::ReconciliationInProgress => (StatusCode::CONFLICT, self.to_string()),
             AppError::InsufficientFunds { .. } => (StatusCode::BAD_REQUEST, self.to_string()),
+            AppError::LimitExceeded { .. } => (StatusCode::UNPROCESSABLE_ENTITY, self.to_string()),
             AppError::InvalidApiKey => (StatusCode::UNAUTHORIZED, "Invalid API key".to_string()),
+            AppError::InvalidAdminToken => (StatusCode::UNAUTHORIZED, "Invalid admin token".to_string()),
+            AppError::AdminForbidden { .. } => (StatusCode::FORBIDDEN, self.to_string()),
+            AppError::SignupDisabled => (StatusCode::FORBIDDEN, self.to_string()),
             AppError::Validation(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::IdempotencyKeyUsed { .. } => (StatusCode::CONFLICT, self.to_string()),
+            AppError::InvalidBatch(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             AppError::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded".to_string()),
-            AppError::WebhookDeliveryFailed(_) => (StatusCode::BAD_GATEWAY, self.to_string()),
+            AppError::WebhookUrlNotAllowed(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             AppError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string()),
             AppError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string()),
-        };
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = self.status_and_message();
 
         let body = Json(json!({
             "error": error_message,
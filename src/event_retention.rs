@@ -0,0 +1,24 @@
+use crate::services::EventService;
+use std::sync::Arc;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+pub async fn start_event_retention_scheduler(event_service: Arc<EventService>) -> anyhow::Result<()> {
+    let sched = JobScheduler::new().await?;
+
+    sched
+        .add(Job::new_async("0 0 * * * *", move |_uuid, _l| {
+            let event_service = event_service.clone();
+            Box::pin(async move {
+                if let Err(e) = event_service.purge_expired_events().await {
+                    tracing::error!("Failed to purge expired events: {}", e);
+                }
+            })
+        })?)
+        .await?;
+
+    sched.start().await?;
+
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+    }
+}
@@ -0,0 +1,243 @@
+use chrono::Utc;
+use serde::Serialize;
+use validator::ValidationError;
+
+use uuid::Uuid;
+
+use crate::models::{Transaction, TransferDirection, TransferPerspective, WebhookPayload};
+
+pub const TRANSACTION_CREATED: &str = "transaction.created";
+pub const TRANSACTION_COMPLETED: &str = "transaction.completed";
+pub const TRANSACTION_CREDIT: &str = "transaction.credit";
+pub const TRANSACTION_DEBIT: &str = "transaction.debit";
+pub const TRANSACTION_TRANSFER: &str = "transaction.transfer";
+pub const TRANSACTION_SPLIT: &str = "transaction.split";
+pub const TRANSFER_SENT: &str = "transfer.sent";
+pub const TRANSFER_RECEIVED: &str = "transfer.received";
+pub const BALANCE_LOW: &str = "balance.low";
+pub const WEBHOOK_TEST: &str = "webhook.test";
+pub const WEBHOOK_DISABLED: &str = "webhook.disabled";
+pub const SCHEDULED_TRANSACTION_EXECUTED: &str = "scheduled_transaction.executed";
+pub const SCHEDULED_TRANSACTION_FAILED: &str = "scheduled_transaction.failed";
+
+/// Recorded in the account event log and stream only; not available to webhooks.
+pub const BALANCE_UPDATED: &str = "balance.updated";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Every event type a webhook can subscribe to.
+pub const EVENT_CATALOG: &[EventDefinition] = &[
+    EventDefinition {
+        name: TRANSACTION_CREATED,
+        description: "A transaction was recorded",
+    },
+    EventDefinition {
+        name: TRANSACTION_COMPLETED,
+        description: "A transaction completed and balances were updated",
+    },
+    EventDefinition {
+        name: TRANSACTION_CREDIT,
+        description: "A credit transaction completed",
+    },
+    EventDefinition {
+        name: TRANSACTION_DEBIT,
+        description: "A debit transaction completed",
+    },
+    EventDefinition {
+        name: TRANSACTION_TRANSFER,
+        description: "A transfer transaction completed",
+    },
+    EventDefinition {
+        name: TRANSACTION_SPLIT,
+        description: "A split transfer to several accounts completed",
+    },
+    EventDefinition {
+        name: TRANSFER_SENT,
+        description: "A transfer out of the account completed",
+    },
+    EventDefinition {
+        name: TRANSFER_RECEIVED,
+        description: "A transfer into the account from another account completed",
+    },
+    EventDefinition {
+        name: BALANCE_LOW,
+        description: "A transaction took the account balance below its low-balance threshold",
+    },
+    EventDefinition {
+        name: WEBHOOK_TEST,
+        description: "A test event sent on request to verify an endpoint",
+    },
+    EventDefinition {
+        name: WEBHOOK_DISABLED,
+        description: "A webhook endpoint was disabled after repeated delivery failures",
+    },
+    EventDefinition {
+        name: SCHEDULED_TRANSACTION_EXECUTED,
+        description: "A scheduled transaction ran and created its transaction",
+    },
+    EventDefinition {
+        name: SCHEDULED_TRANSACTION_FAILED,
+        description: "A scheduled transaction run failed, possibly to be retried",
+    },
+];
+
+pub fn is_known_event(name: &str) -> bool {
+    EVENT_CATALOG.iter().any(|e| e.name == name)
+}
+
+/// A subscription is `*`, an exact catalog event, or a `<group>.*` wildcard
+/// matching at least one catalog event.
+pub fn is_valid_subscription(subscription: &str) -> bool {
+    if subscription == "*" {
+        return true;
+    }
+
+    match subscription.strip_suffix(".*") {
+        Some(group) => EVENT_CATALOG
+            .iter()
+            .any(|e| e.name.split_once('.').map(|(g, _)| g) == Some(group)),
+        None => is_known_event(subscription),
+    }
+}
+
+pub fn subscription_matches(subscriptions: &[String], event: &str) -> bool {
+    subscriptions.iter().any(|subscription| {
+        if subscription == "*" {
+            return true;
+        }
+        match subscription.strip_suffix(".*") {
+            Some(group) => event
+                .strip_prefix(group)
+                .is_some_and(|rest| rest.starts_with('.')),
+            None => subscription == event,
+        }
+    })
+}
+
+pub fn validate_subscriptions(subscriptions: &[String]) -> Result<(), ValidationError> {
+    if let Some(unknown) = subscriptions.iter().find(|s| !is_valid_subscription(s)) {
+        let mut error = ValidationError::new("unknown_event");
+        error.add_param("event".into(), unknown);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Events emitted to `account_id` for a transaction, in the order they are delivered.
+///
+/// The owning account sees the full transaction lifecycle; the counterparty of
+/// a transfer only sees `transfer.received` once it completes. The legs of a
+/// split and fees are reported to the sender through the parent transaction.
+pub fn events_for_account(transaction: &Transaction, account_id: Uuid) -> Vec<&'static str> {
+    if transaction.account_id == account_id && transaction.parent_transaction_id.is_some() {
+        Vec::new()
+    } else if transaction.account_id == account_id {
+        transaction_events(transaction)
+    } else if transaction.counterparty_account_id == Some(account_id)
+        && transaction.r#type == "transfer"
+        && transaction.status == "completed"
+    {
+        vec![TRANSFER_RECEIVED]
+    } else {
+        Vec::new()
+    }
+}
+
+fn transaction_events(transaction: &Transaction) -> Vec<&'static str> {
+    let mut events = vec![TRANSACTION_CREATED];
+
+    match transaction.r#type.as_str() {
+        "credit" => events.push(TRANSACTION_CREDIT),
+        "debit" => events.push(TRANSACTION_DEBIT),
+        "transfer" => events.push(TRANSACTION_TRANSFER),
+        "split" => events.push(TRANSACTION_SPLIT),
+        _ => {}
+    }
+
+    if transaction.status == "completed" {
+        events.push(TRANSACTION_COMPLETED);
+        if transaction.r#type == "transfer" {
+            events.push(TRANSFER_SENT);
+        }
+    }
+
+    events
+}
+
+/// Whether a balance going from `before` to `after` crosses below
+/// `threshold`, so `balance.low` is sent once per drop rather than for every
+/// transaction while the balance stays low. A threshold of 0 never triggers.
+pub fn crossed_low_balance(threshold: i64, before: i64, after: i64) -> bool {
+    threshold > 0 && before >= threshold && after < threshold
+}
+
+/// Describes a transfer from the side of the account receiving `event`.
+pub fn transfer_perspective(event: &str, transaction: &Transaction) -> Option<TransferPerspective> {
+    let counterparty_account_id = transaction.counterparty_account_id?;
+
+    match event {
+        TRANSFER_SENT => Some(TransferPerspective {
+            direction: TransferDirection::Sent,
+            account_id: transaction.account_id,
+            counterparty_account_id,
+            amount: -transaction.amount,
+        }),
+        TRANSFER_RECEIVED => Some(TransferPerspective {
+            direction: TransferDirection::Received,
+            account_id: counterparty_account_id,
+            counterparty_account_id: transaction.account_id,
+            amount: transaction.amount,
+        }),
+        _ => None,
+    }
+}
+
+/// The payload for a transaction event, shared by webhook deliveries and the event log.
+pub fn transaction_payload(event: &str, transaction: &Transaction) -> WebhookPayload {
+    let mut payload_transaction = transaction.clone();
+    if event == TRANSFER_RECEIVED {
+        // The sender's idempotency key is private to the sender.
+        payload_transaction.idempotency_key = None;
+    }
+
+    WebhookPayload {
+        event: event.to_string(),
+        delivery_id: None,
+        sequence: None,
+        transaction: Some(payload_transaction),
+        transfer: transfer_perspective(event, transaction),
+        data: None,
+        timestamp: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_balance_triggers_when_crossing_the_threshold() {
+        assert!(crossed_low_balance(1_000, 1_500, 999));
+        assert!(crossed_low_balance(1_000, 1_000, 0));
+        assert!(!crossed_low_balance(1_000, 1_500, 1_000));
+    }
+
+    #[test]
+    fn low_balance_triggers_once_per_drop() {
+        // Already below: further debits stay quiet until the balance recovers.
+        assert!(!crossed_low_balance(1_000, 999, 500));
+        // Credits never trigger it.
+        assert!(!crossed_low_balance(1_000, 500, 800));
+        assert!(!crossed_low_balance(1_000, 500, 1_500));
+    }
+
+    #[test]
+    fn zero_threshold_disables_low_balance() {
+        assert!(!crossed_low_balance(0, 100, 0));
+        assert!(!crossed_low_balance(0, 0, 0));
+    }
+}
@@ -1,16 +1,24 @@
 mod api;
+mod balance_snapshots;
 mod config;
+mod crypto;
+mod csv;
 mod database;
 mod error;
+mod event_retention;
+mod events;
 mod metrics;
 mod models;
+mod reconciliation;
+mod scheduled_transactions;
 mod services;
+mod url_policy;
 mod webhooks;
 
 use axum::{
     http::Method,
     middleware,
-    routing::{get, post, delete},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 use std::{net::SocketAddr, sync::Arc};
@@ -19,15 +27,20 @@ use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
-use tracing_subscriber;
 
 use crate::{
     api::{
-        accounts, auth, health, metrics as api_metrics, transactions, webhooks as webhook_routes,
+        accounts, admin, auth, events as event_routes, fee_schedules, health, jobs, limits, metrics as api_metrics,
+        request_context, scheduled_transactions as scheduled_transaction_routes, transactions,
+        webhooks as webhook_routes,
     },
     config::Config,
+    crypto::SecretCipher,
     database::Database,
-    services::{AccountService, TransactionService, WebhookService},
+    services::{
+        AccountService, AdminService, AuditService, EventService, FeeService, JobService, LimitService,
+        ReconciliationService, ScheduledTransactionService, TransactionService, WebhookService,
+    },
 };
 
 #[tokio::main]
@@ -42,46 +55,194 @@ async fn main() -> anyhow::Result<()> {
     database.migrate().await?;
 
     let database = Arc::new(database);
-    let account_service = AccountService::new(database.clone());
-    let transaction_service = TransactionService::new(database.clone());
-    let webhook_service = WebhookService::new(database.clone());
+    let account_service = AccountService::new(database.clone(), &config);
+    if let Some(account_id) = config.platform_revenue_account_id {
+        if let Err(e) = account_service.get_account(account_id).await {
+            anyhow::bail!("PLATFORM_REVENUE_ACCOUNT_ID {} can't be used: {}", account_id, e);
+        }
+    }
+    let transaction_service = TransactionService::new(database.clone(), &config);
+    let cipher = SecretCipher::new(
+        config.webhook_encryption_key_version,
+        &config.webhook_encryption_keys,
+        config.webhook_legacy_secret.as_deref(),
+    )?;
+    let webhook_service = WebhookService::new(database.clone(), &config, cipher);
+    webhook_service.reencrypt_secrets().await?;
+    let event_service = EventService::new(database.clone(), &config);
+    let admin_service = AdminService::new(database.clone(), &config);
+    if let Some(token) = &config.admin_bootstrap_token {
+        admin_service.ensure_bootstrap_token(token).await?;
+    }
+
+    tokio::spawn(crate::webhooks::start_webhook_retry_scheduler(Arc::new(
+        webhook_service.clone(),
+    )));
+    tokio::spawn(crate::balance_snapshots::start_balance_snapshot_scheduler(Arc::new(
+        account_service.clone(),
+    )));
+    tokio::spawn(crate::event_retention::start_event_retention_scheduler(Arc::new(
+        event_service.clone(),
+    )));
+    tokio::spawn(event_service.clone().listen_for_events());
+
+    let job_service = JobService::new(
+        database.clone(),
+        transaction_service.clone(),
+        webhook_service.clone(),
+        &config,
+    );
+    tokio::spawn(job_service.clone().run_worker());
+
+    let job_routes = Router::new()
+        .route("/", post(jobs::create_job))
+        .route("/:job_id", get(jobs::get_job))
+        .route("/:job_id/results", get(jobs::get_job_results))
+        .with_state(job_service);
+
+    let scheduled_transaction_service = ScheduledTransactionService::new(
+        database.clone(),
+        transaction_service.clone(),
+        webhook_service.clone(),
+    );
+    tokio::spawn(crate::scheduled_transactions::start_scheduled_transaction_runner(Arc::new(
+        scheduled_transaction_service.clone(),
+    )));
+
+    let scheduled_transaction_routes = Router::new()
+        .route("/", post(scheduled_transaction_routes::create_scheduled_transaction))
+        .route("/", get(scheduled_transaction_routes::list_scheduled_transactions))
+        .route("/:schedule_id", get(scheduled_transaction_routes::get_scheduled_transaction))
+        .route("/:schedule_id/pause", post(scheduled_transaction_routes::pause_scheduled_transaction))
+        .route("/:schedule_id/resume", post(scheduled_transaction_routes::resume_scheduled_transaction))
+        .route("/:schedule_id/skip", post(scheduled_transaction_routes::skip_scheduled_transaction))
+        .route("/:schedule_id/cancel", post(scheduled_transaction_routes::cancel_scheduled_transaction))
+        .with_state(scheduled_transaction_service);
+
+    let audit_service = AuditService::new(database.clone());
+    let reconciliation_service =
+        ReconciliationService::new(database.clone(), account_service.clone(), &config);
+
+    tokio::spawn(crate::reconciliation::start_reconciliation_scheduler(
+        Arc::new(reconciliation_service.clone()),
+        config.reconciliation_schedule.clone(),
+    ));
+
+    let fee_service = FeeService::new(database.clone(), &config);
+    let fee_schedule_routes = Router::new()
+        .route("/", get(fee_schedules::list_fee_schedules))
+        .with_state(fee_service.clone());
+    let admin_fee_schedule_routes = Router::new()
+        .route("/", get(fee_schedules::admin_list_fee_schedules))
+        .route("/:transaction_type", put(fee_schedules::admin_set_fee_schedule))
+        .route("/:transaction_type", delete(fee_schedules::admin_delete_fee_schedule))
+        .with_state(fee_service);
+
+    let limit_service = LimitService::new(database.clone());
+    let limit_routes = Router::new()
+        .route("/", get(limits::get_limits))
+        .with_state(limit_service.clone());
+    let admin_limit_routes = Router::new()
+        .route("/", get(limits::admin_get_limits))
+        .route("/", put(limits::admin_set_limits))
+        .with_state(limit_service);
+
+    let admin_state = (account_service.clone(), admin_service, audit_service, reconciliation_service);
+    let admin_routes = Router::new()
+        .route("/accounts", get(admin::list_accounts))
+        .route("/accounts", post(admin::create_account))
+        .route("/accounts/:account_id", get(admin::get_account))
+        .route("/accounts/:account_id/status", post(admin::change_account_status))
+        .route("/accounts/:account_id/changes", get(admin::list_account_changes))
+        .route("/accounts/:account_id/balance", get(admin::get_balance))
+        .route("/accounts/:account_id/balance-history", get(admin::get_balance_history))
+        .nest("/accounts/:account_id/fee-schedules", admin_fee_schedule_routes)
+        .nest("/accounts/:account_id/limits", admin_limit_routes)
+        .route("/tokens", get(admin::list_admin_tokens))
+        .route("/tokens", post(admin::create_admin_token))
+        .route("/tokens/:token_id", delete(admin::revoke_admin_token))
+        .route("/audit-log", get(admin::list_audit_log))
+        .route("/audit-log/verify", get(admin::verify_audit_log))
+        .route("/reconciliation/runs", get(admin::list_reconciliation_runs))
+        .route("/reconciliation/runs", post(admin::start_reconciliation_run))
+        .route("/reconciliation/runs/:run_id", get(admin::get_reconciliation_run))
+        .layer(middleware::from_fn_with_state(
+            admin_state.clone(),
+            auth::admin_auth_middleware,
+        ))
+        .with_state(admin_state);
 
     let app = Router::new()
         .route("/health", get(health::health_check))
         .route("/metrics", get(api_metrics::metrics_handler))
         .route("/api/v1/accounts", post(accounts::create_account))
+        .nest("/admin", admin_routes)
         .nest("/api/v1", 
             Router::new()
                 .route("/accounts/:account_id", get(accounts::get_account))
+                .route("/accounts/:account_id", patch(accounts::update_account))
+                .route("/accounts/:account_id/status", post(accounts::change_account_status))
+                .route("/accounts/:account_id/changes", get(accounts::list_account_changes))
                 .route("/accounts/:account_id/balance", get(accounts::get_balance))
+                .route("/accounts/:account_id/balance-history", get(accounts::get_balance_history))
+                .route("/accounts/:account_id/statements", get(accounts::get_statement))
                 .route("/transactions", post(transactions::create_transaction))
+                .route("/transactions/batch", post(transactions::create_transaction_batch))
+                .route("/transactions/split", post(transactions::create_split_transaction))
                 .route("/transactions/:transaction_id", get(transactions::get_transaction))
+                .nest("/jobs", job_routes)
+                .nest("/scheduled-transactions", scheduled_transaction_routes)
+                .nest("/fee-schedules", fee_schedule_routes)
+                .nest("/limits", limit_routes)
+                .route("/events", get(event_routes::list_events))
+                .route("/events/stream", get(event_routes::stream_events))
                 .route("/webhooks", post(webhook_routes::register_webhook))
+                .route("/webhook-events", get(webhook_routes::list_webhook_events))
                 .route("/webhooks/:webhook_id", get(webhook_routes::get_webhook))
                 .route("/webhooks/:webhook_id", post(webhook_routes::update_webhook))
                 .route("/webhooks/:webhook_id", delete(webhook_routes::delete_webhook))
+                .route(
+                    "/webhooks/:webhook_id/deliveries/:delivery_id/redeliver",
+                    post(webhook_routes::redeliver_webhook),
+                )
+                .route("/webhooks/:webhook_id/replay", post(webhook_routes::replay_webhook))
+                .route("/webhooks/:webhook_id/test", post(webhook_routes::test_webhook))
+                .route("/webhooks/:webhook_id/enable", post(webhook_routes::enable_webhook))
+                .route(
+                    "/webhooks/:webhook_id/rotate-secret",
+                    post(webhook_routes::rotate_webhook_secret),
+                )
                 .layer(middleware::from_fn_with_state(
-                    (account_service.clone(), transaction_service.clone(), webhook_service.clone()),
+                    (
+                        account_service.clone(),
+                        transaction_service.clone(),
+                        webhook_service.clone(),
+                        event_service.clone(),
+                    ),
                     auth::auth_middleware,
                 ))
         )
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
+                .layer(middleware::from_fn_with_state(
+                    config.trust_x_forwarded_for,
+                    request_context::request_context_middleware,
+                ))
                 .layer(
                     CorsLayer::new()
                         .allow_origin(Any)
-                        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+                        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
                         .allow_headers(Any),
                 ),
         )
-        .with_state((account_service, transaction_service, webhook_service));
+        .with_state((account_service, transaction_service, webhook_service, event_service));
 
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
     tracing::info!("Server starting on {}", addr);
     
     axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await?;
 
     Ok(())
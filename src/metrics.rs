@@ -4,11 +4,17 @@ use std::sync::OnceLock;
 pub static ACCOUNTS_CREATED: OnceLock<AtomicU64> = OnceLock::new();
 pub static TRANSACTIONS_CREATED: OnceLock<AtomicU64> = OnceLock::new();
 pub static WEBHOOKS_DELIVERED: OnceLock<AtomicU64> = OnceLock::new();
+pub static RECONCILIATION_RUNS: OnceLock<AtomicU64> = OnceLock::new();
+pub static BALANCE_DISCREPANCIES: OnceLock<AtomicU64> = OnceLock::new();
+pub static BALANCE_DRIFT: OnceLock<AtomicU64> = OnceLock::new();
 
 pub fn init_metrics() -> anyhow::Result<()> {
     ACCOUNTS_CREATED.set(AtomicU64::new(0)).map_err(|_| anyhow::anyhow!("Failed to set accounts_created counter"))?;
     TRANSACTIONS_CREATED.set(AtomicU64::new(0)).map_err(|_| anyhow::anyhow!("Failed to set transactions_created counter"))?;
     WEBHOOKS_DELIVERED.set(AtomicU64::new(0)).map_err(|_| anyhow::anyhow!("Failed to set webhooks_delivered counter"))?;
+    RECONCILIATION_RUNS.set(AtomicU64::new(0)).map_err(|_| anyhow::anyhow!("Failed to set reconciliation_runs counter"))?;
+    BALANCE_DISCREPANCIES.set(AtomicU64::new(0)).map_err(|_| anyhow::anyhow!("Failed to set balance_discrepancies gauge"))?;
+    BALANCE_DRIFT.set(AtomicU64::new(0)).map_err(|_| anyhow::anyhow!("Failed to set balance_drift gauge"))?;
 
     tracing::info!("Metrics initialized");
     Ok(())
@@ -51,4 +57,21 @@ pub fn record_webhook_delivered(success: bool, duration_seconds: f64) {
         duration_seconds = duration_seconds,
         "Webhook delivery metrics recorded"
     );
-}
\ No newline at end of file
+}
+/// Records the outcome of a reconciliation run; the gauges reflect the latest run.
+pub fn record_reconciliation(discrepancies: u64, total_abs_drift: u64) {
+    if let Some(counter) = RECONCILIATION_RUNS.get() {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+    if let Some(gauge) = BALANCE_DISCREPANCIES.get() {
+        gauge.store(discrepancies, Ordering::Relaxed);
+    }
+    if let Some(gauge) = BALANCE_DRIFT.get() {
+        gauge.store(total_abs_drift, Ordering::Relaxed);
+    }
+    tracing::info!(
+        discrepancies = discrepancies,
+        total_abs_drift = total_abs_drift,
+        "Reconciliation metrics recorded"
+    );
+}
@@ -1,7 +1,9 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use crate::events::EventDefinition;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Account {
@@ -9,19 +11,85 @@ pub struct Account {
     pub business_name: String,
     pub email: String,
     pub balance: i64,
+    pub status: AccountStatus,
+    pub low_balance_threshold: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Frozen accounts can receive money but not send it; closed accounts are
+/// permanently inactive and must have a zero balance.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "account_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AccountStatus {
+    Active,
+    Frozen,
+    Closed,
+}
+
+impl std::fmt::Display for AccountStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountStatus::Active => write!(f, "active"),
+            AccountStatus::Frozen => write!(f, "frozen"),
+            AccountStatus::Closed => write!(f, "closed"),
+        }
+    }
+}
+
+/// A recorded change to an account field, with who made it and why.
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
-pub struct ApiKey {
+pub struct AccountChange {
     pub id: Uuid,
     pub account_id: Uuid,
-    pub key_hash: String,
-    pub name: String,
-    pub is_active: bool,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub reason: Option<String>,
+    pub actor: String,
     pub created_at: DateTime<Utc>,
-    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Who performed a change, as recorded in the account history and audit log.
+#[derive(Debug, Clone)]
+pub enum Actor {
+    /// A merchant request, identified by the API key it used.
+    ApiKey(Uuid),
+    Admin(String),
+    /// An unauthenticated request, i.e. self-service signup.
+    Anonymous,
+    /// The service itself, e.g. the webhook circuit breaker.
+    System,
+}
+
+impl std::fmt::Display for Actor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Actor::ApiKey(key_id) => write!(f, "api_key:{}", key_id),
+            Actor::Admin(subject) => write!(f, "admin:{}", subject),
+            Actor::Anonymous => write!(f, "anonymous"),
+            Actor::System => write!(f, "system"),
+        }
+    }
+}
+
+/// The actor and request behind a mutation, recorded in the audit log.
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub actor: Actor,
+    pub request_id: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl AuditContext {
+    pub fn system() -> Self {
+        Self {
+            actor: Actor::System,
+            request_id: None,
+            ip: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -34,6 +102,9 @@ pub struct Transaction {
     pub description: Option<String>,
     pub status: String,
     pub idempotency_key: Option<String>,
+    /// Set on the legs of a split transfer and on fees, pointing at the
+    /// transaction they belong to.
+    pub parent_transaction_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -55,33 +126,27 @@ impl std::fmt::Display for TransactionType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum TransactionStatus {
-    Pending,
-    Completed,
-    Failed,
-    Cancelled,
-}
-
-impl std::fmt::Display for TransactionStatus {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            TransactionStatus::Pending => write!(f, "pending"),
-            TransactionStatus::Completed => write!(f, "completed"),
-            TransactionStatus::Failed => write!(f, "failed"),
-            TransactionStatus::Cancelled => write!(f, "cancelled"),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Webhook {
     pub id: Uuid,
     pub account_id: Uuid,
     pub url: String,
     pub events: Vec<String>,
+    #[serde(skip_serializing)]
     pub secret: String,
+    #[serde(skip_serializing)]
+    pub previous_secret: Option<String>,
+    pub previous_secret_expires_at: Option<DateTime<Utc>>,
     pub is_active: bool,
+    /// Deliver events strictly in sequence, holding later events while one is retried.
+    pub ordered: bool,
+    /// Events per batched request; 1 means every event is sent on its own.
+    pub batch_max_size: i32,
+    /// How long a partial batch waits for more events before it is sent.
+    pub batch_max_linger_ms: i32,
+    pub consecutive_failures: i32,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub disabled_reason: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -90,7 +155,13 @@ pub struct Webhook {
 pub struct WebhookDelivery {
     pub id: Uuid,
     pub webhook_id: Uuid,
-    pub transaction_id: Uuid,
+    pub transaction_id: Option<Uuid>,
+    pub event: String,
+    /// Event-specific body for events that carry one, sent as the payload's `data`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    pub sequence: Option<i64>,
+    pub batch_id: Option<Uuid>,
     pub status: WebhookDeliveryStatus,
     pub response_status: Option<i32>,
     pub response_body: Option<String>,
@@ -130,14 +201,272 @@ pub struct AccountResponse {
     pub account: Account,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateAccountRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub business_name: Option<String>,
+    #[validate(email)]
+    pub email: Option<String>,
+    /// 0 turns `balance.low` off.
+    #[validate(range(min = 0))]
+    pub low_balance_threshold: Option<i64>,
+    #[validate(length(max = 1000))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangeAccountStatusRequest {
+    pub status: AccountStatus,
+    #[validate(length(min = 1, max = 1000))]
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountChangesResponse {
+    pub changes: Vec<AccountChange>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountSort {
+    #[default]
+    CreatedAtDesc,
+    CreatedAtAsc,
+    BalanceDesc,
+    BalanceAsc,
+}
+
+impl AccountSort {
+    pub fn order_by(self) -> &'static str {
+        match self {
+            AccountSort::CreatedAtDesc => "created_at DESC, id",
+            AccountSort::CreatedAtAsc => "created_at ASC, id",
+            AccountSort::BalanceDesc => "balance DESC, id",
+            AccountSort::BalanceAsc => "balance ASC, id",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ListAccountsQuery {
+    /// Case-insensitive substring match on business name or email.
+    #[validate(length(min = 1, max = 255))]
+    pub q: Option<String>,
+    pub status: Option<AccountStatus>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub sort: AccountSort,
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListAccountsResponse {
+    pub accounts: Vec<Account>,
+    /// Number of accounts matching the filters, across all pages.
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// What an admin credential may do. Superusers may do everything.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "admin_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRole {
+    Support,
+    Finance,
+    Superuser,
+}
+
+impl AdminRole {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "support" => Some(AdminRole::Support),
+            "finance" => Some(AdminRole::Finance),
+            "superuser" => Some(AdminRole::Superuser),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for AdminRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdminRole::Support => write!(f, "support"),
+            AdminRole::Finance => write!(f, "finance"),
+            AdminRole::Superuser => write!(f, "superuser"),
+        }
+    }
+}
+
+/// An authenticated platform operator.
+#[derive(Debug, Clone)]
+pub struct AdminIdentity {
+    /// Identifies the credential in audit records, e.g. `token:<id>` or
+    /// `mtls:<certificate subject>`.
+    pub subject: String,
+    pub role: AdminRole,
+}
+
+impl AdminIdentity {
+    pub fn has_role(&self, roles: &[AdminRole]) -> bool {
+        self.role == AdminRole::Superuser || roles.contains(&self.role)
+    }
+
+    pub fn actor(&self) -> Actor {
+        Actor::Admin(self.subject.clone())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AdminToken {
+    pub id: Uuid,
+    pub name: String,
+    pub role: AdminRole,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateAdminTokenRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub role: AdminRole,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateAdminTokenResponse {
+    pub admin_token: AdminToken,
+    /// Only returned once; store it securely.
+    pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminTokensResponse {
+    pub tokens: Vec<AdminToken>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct BalanceResponse {
     pub account_id: Uuid,
     pub balance: i64,
     pub currency: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceQuery {
+    /// Balance after all transactions created at or before this time.
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+pub const MAX_BALANCE_HISTORY_DAYS: i64 = 366;
+
+/// Inclusive range of UTC days.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_balance_history_range"))]
+pub struct BalanceHistoryQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+fn validate_balance_history_range(query: &BalanceHistoryQuery) -> Result<(), ValidationError> {
+    if query.from > query.to {
+        return Err(ValidationError::new("from_must_not_be_after_to"));
+    }
+    if (query.to - query.from).num_days() >= MAX_BALANCE_HISTORY_DAYS {
+        return Err(ValidationError::new("range_too_long"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DailyBalance {
+    pub date: NaiveDate,
+    pub opening_balance: i64,
+    pub closing_balance: i64,
+    pub transaction_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BalanceHistoryResponse {
+    pub account_id: Uuid,
+    pub currency: String,
+    pub days: Vec<DailyBalance>,
+}
+
+/// Download format for statements and job results.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
 }
 
+pub const MAX_STATEMENT_DAYS: i64 = 366;
+
+/// Inclusive range of UTC days. Statements are streamed, but each one still
+/// scans the account's ledger for the whole range, so the range is capped.
 #[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_statement_range"))]
+pub struct StatementQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+fn validate_statement_range(query: &StatementQuery) -> Result<(), ValidationError> {
+    if query.from > query.to {
+        return Err(ValidationError::new("from_must_not_be_after_to"));
+    }
+    if (query.to - query.from).num_days() >= MAX_STATEMENT_DAYS {
+        return Err(ValidationError::new("range_too_long"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementRecordType {
+    Opening,
+    Transaction,
+    Closing,
+}
+
+impl std::fmt::Display for StatementRecordType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatementRecordType::Opening => write!(f, "opening"),
+            StatementRecordType::Transaction => write!(f, "transaction"),
+            StatementRecordType::Closing => write!(f, "closing"),
+        }
+    }
+}
+
+/// One statement row. Every format emits the same fields in the same order;
+/// opening and closing rows leave the transaction fields empty, and the closing
+/// row's `amount` is the net change over the period.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementLine {
+    pub record_type: StatementRecordType,
+    pub timestamp: DateTime<Utc>,
+    pub transaction_id: Option<Uuid>,
+    pub r#type: Option<String>,
+    pub counterparty_account_id: Option<Uuid>,
+    pub description: Option<String>,
+    pub amount: i64,
+    pub balance: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct CreateTransactionRequest {
     #[validate(length(min = 1, max = 255))]
     pub idempotency_key: Option<String>,
@@ -153,14 +482,434 @@ pub struct CreateTransactionRequest {
 #[derive(Debug, Serialize)]
 pub struct TransactionResponse {
     pub transaction: Transaction,
+    /// The transfers making up a split.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub legs: Vec<Transaction>,
+    /// Fees charged on the transaction, paid to the platform revenue account.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fees: Vec<Transaction>,
+}
+
+/// Moves money from the account to several recipients in one step. Legs give
+/// either amounts or percentages of `amount`, not a mix.
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateSplitTransactionRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub idempotency_key: Option<String>,
+    /// Required with percentage legs; otherwise, if given, must equal the sum of the legs.
+    #[validate(range(min = 1))]
+    pub amount: Option<i64>,
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    #[validate]
+    pub legs: Vec<SplitLegRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_split_leg"))]
+pub struct SplitLegRequest {
+    pub counterparty_account_id: Uuid,
+    #[validate(range(min = 1))]
+    pub amount: Option<i64>,
+    /// Up to two decimal places, e.g. `12.5`.
+    #[validate(range(min = 0.01, max = 100.0))]
+    pub percentage: Option<f64>,
+    /// Defaults to the split's description.
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+}
+
+fn validate_split_leg(leg: &SplitLegRequest) -> Result<(), ValidationError> {
+    match (leg.amount, leg.percentage) {
+        (Some(_), Some(_)) | (None, None) => Err(ValidationError::new("exactly_one_of_amount_or_percentage")),
+        (None, Some(percentage)) if ((percentage * 100.0).round() - percentage * 100.0).abs() > 1e-6 => {
+            Err(ValidationError::new("at_most_two_decimal_places"))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchTransactionRequest {
+    #[serde(default)]
+    pub atomic: bool,
+    pub transactions: Vec<CreateTransactionRequest>,
+}
+
+/// For CSV uploads, where the body only carries the rows.
+#[derive(Debug, Deserialize)]
+pub struct BatchTransactionQuery {
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Created,
+    /// The idempotency key matched an earlier transaction, which is returned as is.
+    Existing,
+    Failed,
+    /// Atomic batches only: the item succeeded but a later item failed.
+    RolledBack,
+    /// Atomic batches only: not attempted because an earlier item failed.
+    Skipped,
+}
+
+impl std::fmt::Display for BatchItemStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BatchItemStatus::Created => write!(f, "created"),
+            BatchItemStatus::Existing => write!(f, "existing"),
+            BatchItemStatus::Failed => write!(f, "failed"),
+            BatchItemStatus::RolledBack => write!(f, "rolled_back"),
+            BatchItemStatus::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchItemError {
+    pub code: u16,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub status: BatchItemStatus,
+    pub idempotency_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<Transaction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BatchItemError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchTransactionResponse {
+    pub atomic: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    /// Every item has a result; individual items may still have failed.
+    Completed,
+    /// The job itself could not be processed, see `error`.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Job {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub kind: String,
+    pub status: JobStatus,
+    pub atomic: bool,
+    pub total_items: i32,
+    pub processed_items: i32,
+    pub succeeded_items: i32,
+    pub failed_items: i32,
+    pub error: Option<String>,
+    #[serde(skip_serializing)]
+    pub attempts: i32,
+    #[serde(skip_serializing)]
+    pub api_key_id: Option<Uuid>,
+    #[serde(skip_serializing)]
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// The outcome of one job item; `status` is `pending` until it has been
+/// processed, then one of the batch item statuses.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct JobItemResult {
+    #[sqlx(rename = "item_index")]
+    pub index: i32,
+    pub status: String,
+    pub idempotency_key: Option<String>,
+    pub transaction_id: Option<Uuid>,
+    pub error_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobResponse {
+    pub job: Job,
+    /// The first failed items, in order; the result file has all of them.
+    pub errors: Vec<JobItemResult>,
+    pub result_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobResultsQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "schedule_frequency", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleFrequency {
+    #[default]
+    Once,
+    Daily,
+    Weekly,
+    Monthly,
+    /// Runs whenever `cron_expression` matches, in UTC.
+    Cron,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "schedule_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleStatus {
+    Active,
+    Paused,
+    /// No occurrences are left.
+    Completed,
+    Cancelled,
+    /// A one-off schedule whose execution failed.
+    Failed,
+}
+
+impl std::fmt::Display for ScheduleStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleStatus::Active => write!(f, "active"),
+            ScheduleStatus::Paused => write!(f, "paused"),
+            ScheduleStatus::Completed => write!(f, "completed"),
+            ScheduleStatus::Cancelled => write!(f, "cancelled"),
+            ScheduleStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ScheduledTransaction {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub r#type: String,
+    pub amount: i64,
+    pub description: Option<String>,
+    pub counterparty_account_id: Option<Uuid>,
+    pub frequency: ScheduleFrequency,
+    pub every: i32,
+    pub cron_expression: Option<String>,
+    pub start_at: DateTime<Utc>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub max_occurrences: Option<i32>,
+    pub status: ScheduleStatus,
+    /// The occurrence currently or next due; `None` once the schedule has ended.
+    pub next_run_at: Option<DateTime<Utc>>,
+    /// When an occurrence that failed for insufficient funds will be retried.
+    pub retry_at: Option<DateTime<Utc>>,
+    pub retry_attempt: i32,
+    pub max_retries: i32,
+    pub retry_interval_minutes: i32,
+    /// Occurrences executed, failed or skipped so far.
+    pub occurrences: i32,
+    pub last_run_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing)]
+    pub api_key_id: Option<Uuid>,
+    #[serde(skip_serializing)]
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One execution attempt or skipped occurrence. `status` is `executed`,
+/// `retrying`, `failed` or `skipped`.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ScheduledTransactionRun {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+    pub scheduled_for: DateTime<Utc>,
+    pub attempt: i32,
+    pub status: String,
+    pub transaction_id: Option<Uuid>,
+    pub error_code: Option<i32>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_schedule_rule"))]
+pub struct CreateScheduledTransactionRequest {
+    #[validate(length(min = 1, max = 20))]
+    pub r#type: String,
+    #[validate(range(min = 1))]
+    pub amount: i64,
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    pub counterparty_account_id: Option<Uuid>,
+    #[serde(default)]
+    pub frequency: ScheduleFrequency,
+    /// Every N days, weeks or months; defaults to 1.
+    #[validate(range(min = 1, max = 366))]
+    pub every: Option<i32>,
+    /// Six-field (`sec min hour day month weekday`) expression, for `cron` only.
+    #[validate(length(min = 1, max = 255))]
+    pub cron_expression: Option<String>,
+    /// The first occurrence, and the only one for `once`. Cron schedules run
+    /// at the first match at or after it, defaulting to now.
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    #[validate(range(min = 1))]
+    pub max_occurrences: Option<i32>,
+    /// Retries of an occurrence that failed for insufficient funds; defaults to 3.
+    #[validate(range(min = 0, max = 10))]
+    pub max_retries: Option<i32>,
+    /// Defaults to 60.
+    #[validate(range(min = 1, max = 1440))]
+    pub retry_interval_minutes: Option<i32>,
+}
+
+fn validate_schedule_rule(req: &CreateScheduledTransactionRequest) -> Result<(), ValidationError> {
+    match (req.frequency, &req.cron_expression) {
+        (ScheduleFrequency::Cron, None) => return Err(ValidationError::new("cron_expression_required")),
+        (ScheduleFrequency::Cron, Some(_)) => {}
+        (_, Some(_)) => return Err(ValidationError::new("cron_expression_only_for_cron")),
+        (_, None) if req.start_at.is_none() => return Err(ValidationError::new("start_at_required")),
+        (_, None) => {}
+    }
+    if let (Some(start_at), Some(end_at)) = (req.start_at, req.end_at) {
+        if end_at <= start_at {
+            return Err(ValidationError::new("end_at_must_be_after_start_at"));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ListScheduledTransactionsQuery {
+    pub status: Option<ScheduleStatus>,
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListScheduledTransactionsResponse {
+    pub scheduled_transactions: Vec<ScheduledTransaction>,
+    /// Number of schedules matching the filters, across all pages.
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduledTransactionResponse {
+    pub scheduled_transaction: ScheduledTransaction,
+    /// The most recent runs, newest first.
+    pub runs: Vec<ScheduledTransactionRun>,
+}
+
+/// What an account is charged on transactions of one type: `fixed_fee` plus
+/// `percentage_bps` hundredths of a percent of the amount, then held within
+/// `min_fee` and `max_fee`.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FeeSchedule {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub transaction_type: String,
+    pub fixed_fee: i64,
+    pub percentage_bps: i32,
+    pub min_fee: Option<i64>,
+    pub max_fee: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_fee_caps"))]
+pub struct UpdateFeeScheduleRequest {
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub fixed_fee: i64,
+    /// e.g. `290` for 2.9%.
+    #[serde(default)]
+    #[validate(range(min = 0, max = 10000))]
+    pub percentage_bps: i32,
+    #[validate(range(min = 0))]
+    pub min_fee: Option<i64>,
+    #[validate(range(min = 0))]
+    pub max_fee: Option<i64>,
+}
+
+fn validate_fee_caps(req: &UpdateFeeScheduleRequest) -> Result<(), ValidationError> {
+    match (req.min_fee, req.max_fee) {
+        (Some(min_fee), Some(max_fee)) if min_fee > max_fee => Err(ValidationError::new("min_fee_exceeds_max_fee")),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeeScheduleResponse {
+    pub fee_schedule: FeeSchedule,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeeSchedulesResponse {
+    pub fee_schedules: Vec<FeeSchedule>,
+}
+
+/// Limits on what an account may move. `None` means no limit.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AccountLimits {
+    pub account_id: Uuid,
+    /// Largest amount of any single transaction.
+    pub max_transaction_amount: Option<i64>,
+    /// Total debits, transfers and splits per UTC calendar day.
+    pub daily_debit_limit: Option<i64>,
+    /// Total debits, transfers and splits per UTC calendar month.
+    pub monthly_debit_limit: Option<i64>,
+    /// Transfers and splits in any rolling hour; a split counts once.
+    pub hourly_transfer_limit: Option<i32>,
+    /// `None` until limits are first set.
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Replaces all of an account's limits; omitted limits are removed.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateAccountLimitsRequest {
+    #[validate(range(min = 1))]
+    pub max_transaction_amount: Option<i64>,
+    #[validate(range(min = 1))]
+    pub daily_debit_limit: Option<i64>,
+    #[validate(range(min = 1))]
+    pub monthly_debit_limit: Option<i64>,
+    #[validate(range(min = 1))]
+    pub hourly_transfer_limit: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountLimitsResponse {
+    pub limits: AccountLimits,
 }
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct CreateWebhookRequest {
     #[validate(url)]
     pub url: String,
-    #[validate(length(min = 1))]
+    #[validate(length(min = 1), custom = "crate::events::validate_subscriptions")]
     pub events: Vec<String>,
+    #[serde(default)]
+    pub ordered: Option<bool>,
+    #[validate(range(min = 1, max = 100))]
+    pub batch_max_size: Option<i32>,
+    #[validate(range(min = 0, max = 60000))]
+    pub batch_max_linger_ms: Option<i32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -168,15 +917,227 @@ pub struct WebhookResponse {
     pub webhook: Webhook,
 }
 
+/// Returned only when a secret is created or rotated; the secret is never shown again.
+#[derive(Debug, Serialize)]
+pub struct WebhookSecretResponse {
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RotateWebhookSecretRequest {
+    /// How long the previous secret keeps signing deliveries. Defaults to 24 hours.
+    #[validate(range(min = 0, max = 604800))]
+    pub overlap_seconds: Option<i64>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct WebhookDeliveryResponse {
     pub webhook_delivery: WebhookDelivery,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct EnableWebhookRequest {
+    /// Replay events that occurred while the webhook was disabled.
+    #[serde(default)]
+    pub replay_backlog: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnableWebhookResponse {
+    pub webhook: Webhook,
+    pub deliveries_enqueued: usize,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_replay_window"))]
+pub struct ReplayWebhookRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    #[validate(custom = "crate::events::validate_subscriptions")]
+    pub events: Option<Vec<String>>,
+    /// Continues a truncated replay: skips transactions at `from` up to and
+    /// including this id. Taken from `next_after` in the previous response.
+    pub after: Option<Uuid>,
+}
+
+fn validate_replay_window(req: &ReplayWebhookRequest) -> Result<(), ValidationError> {
+    if req.from >= req.to {
+        return Err(ValidationError::new("from_must_be_before_to"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplayWebhookResponse {
+    pub webhook_id: Uuid,
+    pub deliveries_enqueued: usize,
+    /// Set when the range held more transactions than one replay covers;
+    /// replay again with these as `from` and `after` to continue.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_from: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_after: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookEventsResponse {
+    pub events: &'static [EventDefinition],
+}
+
 #[derive(Debug, Serialize)]
 pub struct WebhookPayload {
     pub event: String,
-    pub transaction: Transaction,
+    /// Identifies this delivery; batched receivers use it to report per-event failures.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivery_id: Option<Uuid>,
+    /// Per-webhook, monotonically increasing; lets receivers detect gaps and duplicates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<Transaction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer: Option<TransferPerspective>,
+    /// Event-specific body for events not tied to a transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
     pub timestamp: DateTime<Utc>,
-    pub signature: String,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ListEventsQuery {
+    /// Return events recorded after this cursor (an event `id`).
+    pub after: Option<i64>,
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    /// Seconds to wait for new events before returning an empty page.
+    pub wait: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamEventsQuery {
+    /// Resume after this event id; the `Last-Event-ID` header takes precedence.
+    pub after: Option<i64>,
+}
+
+/// An entry in the account event log: the webhook payload plus its cursor.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AccountEvent {
+    pub id: i64,
+    #[serde(flatten)]
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListEventsResponse {
+    pub events: Vec<AccountEvent>,
+    /// Pass as `after` on the next request; unchanged when no events were returned.
+    pub next_cursor: i64,
+}
+
+/// Body of a batched delivery: several events in one signed request.
+#[derive(Debug, Serialize)]
+pub struct WebhookBatchPayload {
+    pub batch_id: Uuid,
+    pub events: Vec<WebhookPayload>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Sent,
+    Received,
+}
+
+/// A transfer as seen by one of its two parties; `amount` is signed from that side.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferPerspective {
+    pub direction: TransferDirection,
+    pub account_id: Uuid,
+    pub counterparty_account_id: Uuid,
+    pub amount: i64,
+}
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor: String,
+    pub action: String,
+    pub resource_type: String,
+    pub resource_id: Uuid,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+    pub ip: Option<String>,
+    pub prev_hash: Option<String>,
+    pub hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ListAuditLogQuery {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    /// Only entries with a greater id (a previous `next_cursor`).
+    pub after: Option<i64>,
+    #[validate(range(min = 1, max = 500))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListAuditLogResponse {
+    pub entries: Vec<AuditEntry>,
+    /// Pass as `after` on the next request; unchanged when no entries were returned.
+    pub next_cursor: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditVerificationResponse {
+    pub valid: bool,
+    pub entries_checked: i64,
+    /// Hash of the newest entry. Record it elsewhere to detect truncation of
+    /// the log's tail, which the chain alone cannot.
+    pub head_hash: Option<String>,
+    pub first_invalid_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReconciliationRun {
+    pub id: Uuid,
+    /// `scheduled` or `manual`.
+    pub trigger: String,
+    pub accounts_checked: i64,
+    pub discrepancies: i64,
+    /// Sum of the absolute drift across all discrepancies, in cents.
+    pub total_abs_drift: i64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BalanceDiscrepancy {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub account_id: Uuid,
+    pub stored_balance: i64,
+    /// Balance implied by the account's completed transactions.
+    pub expected_balance: i64,
+    /// `stored_balance - expected_balance`.
+    pub drift: i64,
+    pub account_frozen: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReconciliationRunResponse {
+    pub run: ReconciliationRun,
+    pub discrepancies: Vec<BalanceDiscrepancy>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReconciliationRunsResponse {
+    pub runs: Vec<ReconciliationRun>,
+}
@@ -0,0 +1,27 @@
+use crate::services::{reconciliation::TRIGGER_SCHEDULED, ReconciliationService};
+use std::sync::Arc;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+pub async fn start_reconciliation_scheduler(
+    reconciliation_service: Arc<ReconciliationService>,
+    schedule: String,
+) -> anyhow::Result<()> {
+    let sched = JobScheduler::new().await?;
+
+    sched
+        .add(Job::new_async(schedule.as_str(), move |_uuid, _l| {
+            let reconciliation_service = reconciliation_service.clone();
+            Box::pin(async move {
+                if let Err(e) = reconciliation_service.run(TRIGGER_SCHEDULED).await {
+                    tracing::error!("Balance reconciliation failed: {}", e);
+                }
+            })
+        })?)
+        .await?;
+
+    sched.start().await?;
+
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+    }
+}
@@ -0,0 +1,30 @@
+use crate::services::ScheduledTransactionService;
+use std::sync::Arc;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+/// How often due scheduled transactions are looked for, so they run within
+/// a few seconds of their time.
+const POLL_SCHEDULE: &str = "*/5 * * * * *";
+
+pub async fn start_scheduled_transaction_runner(
+    scheduled_transaction_service: Arc<ScheduledTransactionService>,
+) -> anyhow::Result<()> {
+    let sched = JobScheduler::new().await?;
+
+    sched
+        .add(Job::new_async(POLL_SCHEDULE, move |_uuid, _l| {
+            let scheduled_transaction_service = scheduled_transaction_service.clone();
+            Box::pin(async move {
+                if let Err(e) = scheduled_transaction_service.run_due().await {
+                    tracing::error!("Failed to run scheduled transactions: {}", e);
+                }
+            })
+        })?)
+        .await?;
+
+    sched.start().await?;
+
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+    }
+}
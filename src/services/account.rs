@@ -1,23 +1,69 @@
 use crate::{
+    config::{Config, SignupPolicy},
     database::Database,
     error::{AppError, Result},
-    models::{Account, CreateAccountRequest, CreateAccountResponse},
+    services::{
+        audit::{self, AuditRecord},
+        transaction::validation_error,
+    },
+    models::{
+        Account, AccountChange, AccountStatus, Actor, AuditContext, BalanceHistoryQuery,
+        ChangeAccountStatusRequest, DailyBalance, CreateAccountRequest,
+        CreateAccountResponse, ListAccountsQuery, ListAccountsResponse, StatementLine,
+        StatementQuery, StatementRecordType, UpdateAccountRequest,
+    },
 };
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
 use sha2::{Digest, Sha256};
+use sqlx::{Postgres, Transaction};
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
+
+const STATEMENT_PAGE_SIZE: i64 = 500;
+
+enum StatementPhase {
+    Rows { after: Option<(DateTime<Utc>, Uuid, i64)> },
+    Closing,
+    Done,
+}
+
+#[derive(sqlx::FromRow)]
+struct StatementRow {
+    transaction_id: Uuid,
+    delta: i64,
+    created_at: DateTime<Utc>,
+    r#type: String,
+    account_id: Uuid,
+    counterparty_account_id: Option<Uuid>,
+    description: Option<String>,
+}
 
 #[derive(Clone)]
 pub struct AccountService {
     database: Arc<Database>,
+    signup_policy: SignupPolicy,
 }
 
 impl AccountService {
-    pub fn new(database: Arc<Database>) -> Self {
-        Self { database }
+    pub fn new(database: Arc<Database>, config: &Config) -> Self {
+        Self {
+            database,
+            signup_policy: config.signup_policy,
+        }
     }
 
-    pub async fn create_account(&self, req: CreateAccountRequest) -> Result<CreateAccountResponse> {
+    /// Whether merchants may create accounts themselves, without an admin.
+    pub fn self_signup_allowed(&self) -> bool {
+        self.signup_policy == SignupPolicy::Open
+    }
+
+    pub async fn create_account(
+        &self,
+        req: CreateAccountRequest,
+        ctx: &AuditContext,
+    ) -> Result<CreateAccountResponse> {
         let span = tracing::info_span!(
             "create_account",
             business_name = %req.business_name,
@@ -33,7 +79,7 @@ impl AccountService {
             r#"
             INSERT INTO accounts (business_name, email)
             VALUES ($1, $2)
-            RETURNING id, business_name, email, balance, created_at, updated_at
+            RETURNING id, business_name, email, balance, status, low_balance_threshold, created_at, updated_at
             "#,
         )
         .bind(&req.business_name)
@@ -56,6 +102,19 @@ impl AccountService {
         .execute(&mut *tx)
         .await?;
 
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::ACCOUNT_CREATED,
+                resource_type: "account",
+                resource_id: account.id,
+                before: None,
+                after: audit::snapshot(&account),
+            },
+        )
+        .await?;
+
         tx.commit().await?;
 
         tracing::info!(
@@ -75,13 +134,276 @@ impl AccountService {
     pub async fn get_account(&self, account_id: Uuid) -> Result<Account> {
         let account = sqlx::query_as::<_, Account>(
             r#"
-            SELECT id, business_name, email, balance, created_at, updated_at
+            SELECT id, business_name, email, balance, status, low_balance_threshold, created_at, updated_at
+            FROM accounts
+            WHERE id = $1
+            "#,
+        )
+        .bind(account_id)
+        .fetch_optional(self.database.pool())
+        .await?
+        .ok_or_else(|| AppError::AccountNotFound {
+            account_id: account_id.to_string(),
+        })?;
+
+        Ok(account)
+    }
+
+    pub async fn update_account(
+        &self,
+        account_id: Uuid,
+        req: UpdateAccountRequest,
+        ctx: &AuditContext,
+    ) -> Result<Account> {
+        req.validate()?;
+
+        let mut tx = self.database.begin_transaction().await?;
+        let existing = Self::lock_account(&mut tx, account_id).await?;
+
+        let account = sqlx::query_as::<_, Account>(
+            r#"
+            UPDATE accounts
+            SET business_name = COALESCE($1, business_name),
+                email = COALESCE($2, email),
+                low_balance_threshold = COALESCE($3, low_balance_threshold)
+            WHERE id = $4
+            RETURNING id, business_name, email, balance, status, low_balance_threshold, created_at, updated_at
+            "#,
+        )
+        .bind(&req.business_name)
+        .bind(&req.email)
+        .bind(req.low_balance_threshold)
+        .bind(account_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            // Checked by the constraint itself, so two concurrent updates to the
+            // same address can't both pass.
+            sqlx::Error::Database(ref db) if db.constraint() == Some("accounts_email_key") => {
+                AppError::EmailAlreadyInUse {
+                    email: req.email.clone().unwrap_or_default(),
+                }
+            }
+            e => e.into(),
+        })?;
+
+        let changes = [
+            ("business_name", existing.business_name.clone(), account.business_name.clone()),
+            ("email", existing.email.clone(), account.email.clone()),
+            (
+                "low_balance_threshold",
+                existing.low_balance_threshold.to_string(),
+                account.low_balance_threshold.to_string(),
+            ),
+        ];
+        for (field, old, new) in changes {
+            if old != new {
+                Self::record_change(&mut tx, account_id, field, Some(&old), Some(&new), req.reason.as_deref(), &ctx.actor)
+                    .await?;
+            }
+        }
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::ACCOUNT_UPDATED,
+                resource_type: "account",
+                resource_id: account_id,
+                before: audit::snapshot(&existing),
+                after: audit::snapshot(&account),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(account_id = %account_id, actor = %ctx.actor, "Account updated");
+
+        Ok(account)
+    }
+
+    /// Moves an account between `active`, `frozen` and `closed`, on behalf
+    /// of an administrator.
+    ///
+    /// Closing is permanent and only allowed once the balance is zero.
+    pub async fn change_status(
+        &self,
+        account_id: Uuid,
+        req: ChangeAccountStatusRequest,
+        ctx: &AuditContext,
+    ) -> Result<Account> {
+        self.apply_status_change(account_id, req, ctx, admin_transition_allowed)
+            .await
+    }
+
+    /// Like [`change_status`](Self::change_status), for the account itself:
+    /// a merchant can freeze or close an active account, but a frozen
+    /// account stays frozen until an administrator lifts it.
+    pub async fn change_own_status(
+        &self,
+        account_id: Uuid,
+        req: ChangeAccountStatusRequest,
+        ctx: &AuditContext,
+    ) -> Result<Account> {
+        self.apply_status_change(account_id, req, ctx, merchant_transition_allowed)
+            .await
+    }
+
+    async fn apply_status_change(
+        &self,
+        account_id: Uuid,
+        req: ChangeAccountStatusRequest,
+        ctx: &AuditContext,
+        allowed: fn(AccountStatus, AccountStatus) -> bool,
+    ) -> Result<Account> {
+        req.validate()?;
+
+        let mut tx = self.database.begin_transaction().await?;
+        let existing = Self::lock_account(&mut tx, account_id).await?;
+
+        if !allowed(existing.status, req.status) {
+            return Err(AppError::InvalidAccountStatusTransition {
+                from: existing.status.to_string(),
+                to: req.status.to_string(),
+            });
+        }
+
+        if req.status == AccountStatus::Closed && existing.balance != 0 {
+            return Err(AppError::AccountBalanceNotZero {
+                account_id: account_id.to_string(),
+                balance: existing.balance,
+            });
+        }
+
+        let account = sqlx::query_as::<_, Account>(
+            r#"
+            UPDATE accounts
+            SET status = $1
+            WHERE id = $2
+            RETURNING id, business_name, email, balance, status, low_balance_threshold, created_at, updated_at
+            "#,
+        )
+        .bind(req.status)
+        .bind(account_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Self::record_change(
+            &mut tx,
+            account_id,
+            "status",
+            Some(&existing.status.to_string()),
+            Some(&account.status.to_string()),
+            Some(&req.reason),
+            &ctx.actor,
+        )
+        .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::ACCOUNT_STATUS_CHANGED,
+                resource_type: "account",
+                resource_id: account_id,
+                before: audit::snapshot(&existing),
+                after: audit::snapshot(&account),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(
+            account_id = %account_id,
+            from = %existing.status,
+            to = %account.status,
+            actor = %ctx.actor,
+            "Account status changed"
+        );
+
+        Ok(account)
+    }
+
+    /// Searches all accounts; for platform operators only.
+    pub async fn list_accounts(&self, query: ListAccountsQuery) -> Result<ListAccountsResponse> {
+        query.validate()?;
+
+        let limit = query.limit.unwrap_or(50);
+        let offset = query.offset.unwrap_or(0);
+        let pattern = query.q.as_deref().map(|q| format!("%{}%", escape_like(q)));
+
+        let filters = r#"
+            WHERE ($1::text IS NULL OR business_name ILIKE $1 OR email ILIKE $1)
+            AND ($2::account_status IS NULL OR status = $2)
+            AND ($3::timestamptz IS NULL OR created_at >= $3)
+            AND ($4::timestamptz IS NULL OR created_at < $4)
+        "#;
+
+        let accounts = sqlx::query_as::<_, Account>(&format!(
+            r#"
+            SELECT id, business_name, email, balance, status, low_balance_threshold, created_at, updated_at
+            FROM accounts
+            {}
+            ORDER BY {}
+            LIMIT $5 OFFSET $6
+            "#,
+            filters,
+            query.sort.order_by()
+        ))
+        .bind(&pattern)
+        .bind(query.status)
+        .bind(query.created_after)
+        .bind(query.created_before)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM accounts {}", filters))
+            .bind(&pattern)
+            .bind(query.status)
+            .bind(query.created_after)
+            .bind(query.created_before)
+            .fetch_one(self.database.pool())
+            .await?;
+
+        Ok(ListAccountsResponse {
+            accounts,
+            total,
+            limit,
+            offset,
+        })
+    }
+
+    pub async fn list_changes(&self, account_id: Uuid) -> Result<Vec<AccountChange>> {
+        let changes = sqlx::query_as::<_, AccountChange>(
+            r#"
+            SELECT id, account_id, field, old_value, new_value, reason, actor, created_at
+            FROM account_changes
+            WHERE account_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(account_id)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        Ok(changes)
+    }
+
+    async fn lock_account(tx: &mut Transaction<'_, Postgres>, account_id: Uuid) -> Result<Account> {
+        let account = sqlx::query_as::<_, Account>(
+            r#"
+            SELECT id, business_name, email, balance, status, low_balance_threshold, created_at, updated_at
             FROM accounts
             WHERE id = $1
+            FOR UPDATE
             "#,
         )
         .bind(account_id)
-        .fetch_optional(&*self.database.pool())
+        .fetch_optional(&mut **tx)
         .await?
         .ok_or_else(|| AppError::AccountNotFound {
             account_id: account_id.to_string(),
@@ -90,6 +412,33 @@ impl AccountService {
         Ok(account)
     }
 
+    async fn record_change(
+        tx: &mut Transaction<'_, Postgres>,
+        account_id: Uuid,
+        field: &str,
+        old_value: Option<&str>,
+        new_value: Option<&str>,
+        reason: Option<&str>,
+        actor: &Actor,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO account_changes (account_id, field, old_value, new_value, reason, actor)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(account_id)
+        .bind(field)
+        .bind(old_value)
+        .bind(new_value)
+        .bind(reason)
+        .bind(actor.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn get_balance(&self, account_id: Uuid) -> Result<i64> {
         let balance = sqlx::query_scalar::<_, i64>(
             r#"
@@ -99,7 +448,7 @@ impl AccountService {
             "#,
         )
         .bind(account_id)
-        .fetch_optional(&*self.database.pool())
+        .fetch_optional(self.database.pool())
         .await?
         .ok_or_else(|| AppError::AccountNotFound {
             account_id: account_id.to_string(),
@@ -108,40 +457,278 @@ impl AccountService {
         Ok(balance)
     }
 
-    pub async fn update_balance(&self, account_id: Uuid, new_balance: i64) -> Result<()> {
-        let result = sqlx::query(
+    /// Balance after every completed transaction created at or before `as_of`,
+    /// starting from the latest end-of-day snapshot that precedes it.
+    pub async fn get_balance_as_of(&self, account_id: Uuid, as_of: DateTime<Utc>) -> Result<i64> {
+        self.get_account(account_id).await?;
+
+        let balance = sqlx::query_scalar::<_, i64>(
             r#"
-            UPDATE accounts
-            SET balance = $1
-            WHERE id = $2
+            WITH snapshot AS (
+                SELECT closing_balance, (snapshot_date + 1)::timestamp AT TIME ZONE 'UTC' AS covers_until
+                FROM balance_snapshots
+                WHERE account_id = $1 AND (snapshot_date + 1)::timestamp AT TIME ZONE 'UTC' <= $2
+                ORDER BY snapshot_date DESC
+                LIMIT 1
+            )
+            SELECT (
+                COALESCE((SELECT closing_balance FROM snapshot), 0)
+                + COALESCE((
+                    SELECT SUM(delta)
+                    FROM account_ledger
+                    WHERE account_id = $1
+                    AND created_at >= COALESCE((SELECT covers_until FROM snapshot), '-infinity')
+                    AND created_at <= $2
+                ), 0)
+            )::BIGINT
             "#,
         )
-        .bind(new_balance)
         .bind(account_id)
-        .execute(&*self.database.pool())
+        .bind(as_of)
+        .fetch_one(self.database.pool())
         .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(AppError::AccountNotFound {
-                account_id: account_id.to_string(),
-            });
-        }
+        Ok(balance)
+    }
 
-        Ok(())
+    /// Opening and closing balance for each UTC day in the range.
+    pub async fn get_balance_history(
+        &self,
+        account_id: Uuid,
+        query: BalanceHistoryQuery,
+    ) -> Result<Vec<DailyBalance>> {
+        query.validate()?;
+
+        let (range_start, _) = day_range(query.from, query.to)?;
+        let mut balance = self.get_balance_as_of(account_id, just_before(range_start)?).await?;
+
+        let daily = sqlx::query_as::<_, (NaiveDate, i64, i64)>(
+            r#"
+            WITH days AS (
+                SELECT d::date AS day
+                FROM generate_series($2::date, $3::date, INTERVAL '1 day') d
+            ),
+            daily AS (
+                SELECT (created_at AT TIME ZONE 'UTC')::date AS day, SUM(delta) AS net, COUNT(*) AS count
+                FROM account_ledger
+                WHERE account_id = $1
+                AND created_at >= $2::timestamp AT TIME ZONE 'UTC'
+                AND created_at < ($3 + 1)::timestamp AT TIME ZONE 'UTC'
+                GROUP BY 1
+            )
+            SELECT days.day, COALESCE(daily.net, 0)::BIGINT, COALESCE(daily.count, 0)
+            FROM days
+            LEFT JOIN daily ON daily.day = days.day
+            ORDER BY days.day
+            "#,
+        )
+        .bind(account_id)
+        .bind(query.from)
+        .bind(query.to)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let days = daily
+            .into_iter()
+            .map(|(date, net, transaction_count)| {
+                let opening_balance = balance;
+                balance += net;
+                DailyBalance {
+                    date,
+                    opening_balance,
+                    closing_balance: balance,
+                    transaction_count,
+                }
+            })
+            .collect();
+
+        Ok(days)
+    }
+
+    /// Streams a statement for an inclusive range of UTC days: an opening row,
+    /// one row per completed transaction with the running balance, then a
+    /// closing row. Transactions are read a page at a time so memory stays flat
+    /// however long the range is.
+    pub async fn statement(
+        &self,
+        account_id: Uuid,
+        query: &StatementQuery,
+    ) -> Result<impl Stream<Item = Result<StatementLine>> + Send + 'static> {
+        query.validate()?;
+
+        let (range_start, range_end) = day_range(query.from, query.to)?;
+        let opening_balance = self.get_balance_as_of(account_id, just_before(range_start)?).await?;
+
+        let opening = StatementLine {
+            record_type: StatementRecordType::Opening,
+            timestamp: range_start,
+            transaction_id: None,
+            r#type: None,
+            counterparty_account_id: None,
+            description: None,
+            amount: 0,
+            balance: opening_balance,
+        };
+
+        let database = self.database.clone();
+        let pages = stream::unfold(
+            (StatementPhase::Rows { after: None }, opening_balance),
+            move |(phase, balance)| {
+                let database = database.clone();
+                async move {
+                    match phase {
+                        StatementPhase::Rows { after } => {
+                            let page = match Self::statement_page(&database, account_id, range_start, range_end, after).await {
+                                Ok(page) => page,
+                                Err(e) => return Some((Err(e), (StatementPhase::Done, balance))),
+                            };
+
+                            let next = match page.last() {
+                                Some(last) if page.len() as i64 == STATEMENT_PAGE_SIZE => StatementPhase::Rows {
+                                    after: Some((last.created_at, last.transaction_id, last.delta)),
+                                },
+                                _ => StatementPhase::Closing,
+                            };
+
+                            let mut balance = balance;
+                            let lines = page
+                                .into_iter()
+                                .map(|row| {
+                                    balance += row.delta;
+                                    let counterparty_account_id = if row.account_id == account_id {
+                                        row.counterparty_account_id
+                                    } else {
+                                        Some(row.account_id)
+                                    };
+                                    StatementLine {
+                                        record_type: StatementRecordType::Transaction,
+                                        timestamp: row.created_at,
+                                        transaction_id: Some(row.transaction_id),
+                                        r#type: Some(row.r#type),
+                                        counterparty_account_id,
+                                        description: row.description,
+                                        amount: row.delta,
+                                        balance,
+                                    }
+                                })
+                                .collect::<Vec<_>>();
+
+                            Some((Ok(lines), (next, balance)))
+                        }
+                        StatementPhase::Closing => {
+                            let closing = StatementLine {
+                                record_type: StatementRecordType::Closing,
+                                timestamp: range_end,
+                                transaction_id: None,
+                                r#type: None,
+                                counterparty_account_id: None,
+                                description: None,
+                                amount: balance - opening_balance,
+                                balance,
+                            };
+                            Some((Ok(vec![closing]), (StatementPhase::Done, balance)))
+                        }
+                        StatementPhase::Done => None,
+                    }
+                }
+            },
+        );
+
+        Ok(stream::once(async move { Ok(vec![opening]) })
+            .chain(pages)
+            .map_ok(|lines| stream::iter(lines.into_iter().map(Ok)))
+            .try_flatten())
+    }
+
+    async fn statement_page(
+        database: &Database,
+        account_id: Uuid,
+        range_start: DateTime<Utc>,
+        range_end: DateTime<Utc>,
+        after: Option<(DateTime<Utc>, Uuid, i64)>,
+    ) -> Result<Vec<StatementRow>> {
+        // Keyset on (created_at, transaction_id, delta): a transfer to oneself
+        // has two ledger entries under the same transaction id.
+        let rows = sqlx::query_as::<_, StatementRow>(
+            r#"
+            SELECT l.transaction_id, l.delta, l.created_at, t.type, t.account_id, t.counterparty_account_id, t.description
+            FROM account_ledger l
+            JOIN transactions t ON t.id = l.transaction_id
+            WHERE l.account_id = $1
+            AND l.created_at >= $2
+            AND l.created_at < $3
+            AND ($4::timestamptz IS NULL OR (l.created_at, l.transaction_id, l.delta) > ($4, $5, $6))
+            ORDER BY l.created_at, l.transaction_id, l.delta
+            LIMIT $7
+            "#,
+        )
+        .bind(account_id)
+        .bind(range_start)
+        .bind(range_end)
+        .bind(after.map(|(created_at, _, _)| created_at))
+        .bind(after.map(|(_, transaction_id, _)| transaction_id))
+        .bind(after.map(|(_, _, delta)| delta))
+        .bind(STATEMENT_PAGE_SIZE)
+        .fetch_all(database.pool())
+        .await?;
+
+        Ok(rows)
     }
 
-    pub async fn validate_api_key(&self, api_key: &str) -> Result<Uuid> {
+    /// Records every account's closing balance for `date` (UTC), built on the
+    /// closest earlier snapshot. Re-running it recomputes the day, so a
+    /// transaction created before midnight that committed after the first run
+    /// is picked up; snapshots that didn't change are left alone.
+    pub async fn snapshot_balances(&self, date: NaiveDate) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO balance_snapshots (account_id, snapshot_date, closing_balance)
+            SELECT
+                a.id,
+                $1,
+                COALESCE(prev.closing_balance, 0) + COALESCE((
+                    SELECT SUM(l.delta)
+                    FROM account_ledger l
+                    WHERE l.account_id = a.id
+                    AND l.created_at >= COALESCE((prev.snapshot_date + 1)::timestamp AT TIME ZONE 'UTC', '-infinity')
+                    AND l.created_at < ($1 + 1)::timestamp AT TIME ZONE 'UTC'
+                ), 0)
+            FROM accounts a
+            LEFT JOIN LATERAL (
+                SELECT snapshot_date, closing_balance
+                FROM balance_snapshots s
+                WHERE s.account_id = a.id AND s.snapshot_date < $1
+                ORDER BY s.snapshot_date DESC
+                LIMIT 1
+            ) prev ON true
+            WHERE a.created_at < ($1 + 1)::timestamp AT TIME ZONE 'UTC'
+            ON CONFLICT (account_id, snapshot_date) DO UPDATE
+            SET closing_balance = EXCLUDED.closing_balance
+            WHERE balance_snapshots.closing_balance <> EXCLUDED.closing_balance
+            "#,
+        )
+        .bind(date)
+        .execute(self.database.pool())
+        .await?;
+
+        tracing::info!(date = %date, snapshots = result.rows_affected(), "Balance snapshots recorded");
+
+        Ok(result.rows_affected())
+    }
+
+    /// Returns the account the key belongs to and the key's own id.
+    pub async fn validate_api_key(&self, api_key: &str) -> Result<(Uuid, Uuid)> {
         let key_hash = format!("{:x}", Sha256::digest(api_key.as_bytes()));
 
-        let account_id = sqlx::query_scalar::<_, Uuid>(
+        let (account_id, key_id) = sqlx::query_as::<_, (Uuid, Uuid)>(
             r#"
-            SELECT account_id
+            SELECT account_id, id
             FROM api_keys
             WHERE key_hash = $1 AND is_active = true
             "#,
         )
         .bind(&key_hash)
-        .fetch_optional(&*self.database.pool())
+        .fetch_optional(self.database.pool())
         .await?
         .ok_or(AppError::InvalidApiKey)?;
 
@@ -153,9 +740,93 @@ impl AccountService {
             "#,
         )
         .bind(&key_hash)
-        .execute(&*self.database.pool())
+        .execute(self.database.pool())
         .await?;
 
-        Ok(account_id)
+        Ok((account_id, key_id))
     }
-}
\ No newline at end of file
+}
+
+/// The start of UTC day `from` and the end of UTC day `to`, which is
+/// exclusive. Dates at the edge of the calendar are rejected rather than
+/// overflowing.
+fn day_range(from: NaiveDate, to: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let out_of_range = || validation_error("to", "date_out_of_range");
+    let start = Utc.from_utc_datetime(&from.and_time(NaiveTime::MIN));
+    let end = to
+        .checked_add_signed(chrono::Duration::days(1))
+        .ok_or_else(out_of_range)?;
+    Ok((start, Utc.from_utc_datetime(&end.and_time(NaiveTime::MIN))))
+}
+
+/// The last instant before a range starting at `start`: balances "as of"
+/// it leave out everything in the range.
+fn just_before(start: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    start
+        .checked_sub_signed(chrono::Duration::microseconds(1))
+        .ok_or_else(|| validation_error("from", "date_out_of_range"))
+}
+
+/// Escapes `LIKE` wildcards so user input is matched literally.
+fn escape_like(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn admin_transition_allowed(from: AccountStatus, to: AccountStatus) -> bool {
+    matches!(
+        (from, to),
+        (AccountStatus::Active, AccountStatus::Frozen)
+            | (AccountStatus::Frozen, AccountStatus::Active)
+            | (AccountStatus::Active, AccountStatus::Closed)
+            | (AccountStatus::Frozen, AccountStatus::Closed)
+    )
+}
+
+fn merchant_transition_allowed(from: AccountStatus, to: AccountStatus) -> bool {
+    matches!(
+        (from, to),
+        (AccountStatus::Active, AccountStatus::Frozen) | (AccountStatus::Active, AccountStatus::Closed)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AccountStatus::{Active, Closed, Frozen};
+
+    #[test]
+    fn only_administrators_unfreeze() {
+        assert!(admin_transition_allowed(Frozen, Active));
+        assert!(!merchant_transition_allowed(Frozen, Active));
+        assert!(!merchant_transition_allowed(Frozen, Closed));
+
+        for allowed in [admin_transition_allowed, merchant_transition_allowed] {
+            assert!(allowed(Active, Frozen));
+            assert!(allowed(Active, Closed));
+            assert!(!allowed(Closed, Active));
+            assert!(!allowed(Closed, Frozen));
+            assert!(!allowed(Active, Active));
+        }
+    }
+
+    #[test]
+    fn day_ranges_cover_whole_utc_days() {
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let (start, end) = day_range(from, to).unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-02-01T00:00:00+00:00");
+        assert_eq!(just_before(start).unwrap().to_rfc3339(), "2023-12-31T23:59:59.999999+00:00");
+    }
+
+    #[test]
+    fn day_ranges_reject_the_edges_of_the_calendar() {
+        assert!(day_range(NaiveDate::MIN, NaiveDate::MAX).is_err());
+
+        let (start, _) = day_range(NaiveDate::MIN, NaiveDate::MIN).unwrap();
+        assert!(just_before(start).is_err());
+    }
+}
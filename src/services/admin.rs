@@ -0,0 +1,199 @@
+use crate::{
+    config::Config,
+    database::Database,
+    error::{AppError, Result},
+    models::{
+        AdminIdentity, AdminRole, AdminToken, AuditContext, CreateAdminTokenRequest,
+        CreateAdminTokenResponse,
+    },
+    services::audit::{self, AuditRecord},
+};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Clone)]
+pub struct AdminService {
+    database: Arc<Database>,
+    mtls_identity_header: Option<String>,
+    mtls_identities: Arc<Vec<(AdminRole, String)>>,
+}
+
+impl AdminService {
+    pub fn new(database: Arc<Database>, config: &Config) -> Self {
+        Self {
+            database,
+            mtls_identity_header: config.admin_mtls_identity_header.clone(),
+            mtls_identities: Arc::new(config.admin_mtls_identities.clone()),
+        }
+    }
+
+    /// Makes sure the token from `ADMIN_BOOTSTRAP_TOKEN` exists as a superuser,
+    /// so the first operator can authenticate on a fresh deployment.
+    pub async fn ensure_bootstrap_token(&self, token: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO admin_tokens (name, token_hash, role)
+            VALUES ('bootstrap', $1, 'superuser')
+            ON CONFLICT (token_hash) DO NOTHING
+            "#,
+        )
+        .bind(hash_token(token))
+        .execute(self.database.pool())
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn validate_admin_token(&self, token: &str) -> Result<AdminIdentity> {
+        let (id, role) = sqlx::query_as::<_, (Uuid, AdminRole)>(
+            r#"
+            UPDATE admin_tokens
+            SET last_used_at = NOW()
+            WHERE token_hash = $1 AND is_active = true
+            RETURNING id, role
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(self.database.pool())
+        .await?
+        .ok_or(AppError::InvalidAdminToken)?;
+
+        Ok(AdminIdentity {
+            subject: format!("token:{}", id),
+            role,
+        })
+    }
+
+    /// Header carrying the client certificate subject, set by the TLS-terminating
+    /// proxy. `None` unless mTLS identities are configured.
+    pub fn mtls_identity_header(&self) -> Option<&str> {
+        self.mtls_identity_header.as_deref()
+    }
+
+    pub fn validate_mtls_identity(&self, subject: &str) -> Result<AdminIdentity> {
+        let (role, subject) = self
+            .mtls_identities
+            .iter()
+            .find(|(_, allowed)| allowed == subject)
+            .ok_or(AppError::InvalidAdminToken)?;
+
+        Ok(AdminIdentity {
+            subject: format!("mtls:{}", subject),
+            role: *role,
+        })
+    }
+
+    pub async fn create_token(
+        &self,
+        req: CreateAdminTokenRequest,
+        ctx: &AuditContext,
+    ) -> Result<CreateAdminTokenResponse> {
+        req.validate()?;
+
+        let token = format!("adm_{}", Uuid::new_v4().simple());
+
+        let mut tx = self.database.begin_transaction().await?;
+
+        let admin_token = sqlx::query_as::<_, AdminToken>(
+            r#"
+            INSERT INTO admin_tokens (name, token_hash, role)
+            VALUES ($1, $2, $3)
+            RETURNING id, name, role, is_active, created_at, last_used_at
+            "#,
+        )
+        .bind(&req.name)
+        .bind(hash_token(&token))
+        .bind(req.role)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::ADMIN_TOKEN_CREATED,
+                resource_type: "admin_token",
+                resource_id: admin_token.id,
+                before: None,
+                after: audit::snapshot(&admin_token),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(admin_token_id = %admin_token.id, role = %admin_token.role, "Admin token created");
+
+        Ok(CreateAdminTokenResponse { admin_token, token })
+    }
+
+    pub async fn list_tokens(&self) -> Result<Vec<AdminToken>> {
+        let tokens = sqlx::query_as::<_, AdminToken>(
+            r#"
+            SELECT id, name, role, is_active, created_at, last_used_at
+            FROM admin_tokens
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(self.database.pool())
+        .await?;
+
+        Ok(tokens)
+    }
+
+    pub async fn revoke_token(&self, token_id: Uuid, ctx: &AuditContext) -> Result<()> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        let existing = sqlx::query_as::<_, AdminToken>(
+            r#"
+            SELECT id, name, role, is_active, created_at, last_used_at
+            FROM admin_tokens
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(token_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::AdminTokenNotFound {
+            token_id: token_id.to_string(),
+        })?;
+
+        let revoked = sqlx::query_as::<_, AdminToken>(
+            r#"
+            UPDATE admin_tokens
+            SET is_active = false
+            WHERE id = $1
+            RETURNING id, name, role, is_active, created_at, last_used_at
+            "#,
+        )
+        .bind(token_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::ADMIN_TOKEN_REVOKED,
+                resource_type: "admin_token",
+                resource_id: token_id,
+                before: audit::snapshot(&existing),
+                after: audit::snapshot(&revoked),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(admin_token_id = %token_id, "Admin token revoked");
+
+        Ok(())
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,161 @@
+use crate::{
+    database::Database,
+    error::Result,
+    models::{AuditContext, AuditEntry, AuditVerificationResponse, ListAuditLogQuery, ListAuditLogResponse},
+};
+use serde::Serialize;
+use sqlx::{Postgres, Transaction};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+pub const ACCOUNT_CREATED: &str = "account.created";
+pub const ACCOUNT_UPDATED: &str = "account.updated";
+pub const ACCOUNT_STATUS_CHANGED: &str = "account.status_changed";
+pub const ACCOUNT_BALANCE_CHANGED: &str = "account.balance_changed";
+pub const ACCOUNT_LIMITS_UPDATED: &str = "account.limits_updated";
+pub const TRANSACTION_CREATED: &str = "transaction.created";
+pub const WEBHOOK_CREATED: &str = "webhook.created";
+pub const WEBHOOK_UPDATED: &str = "webhook.updated";
+pub const WEBHOOK_DELETED: &str = "webhook.deleted";
+pub const WEBHOOK_SECRET_ROTATED: &str = "webhook.secret_rotated";
+pub const WEBHOOK_ENABLED: &str = "webhook.enabled";
+pub const WEBHOOK_DISABLED: &str = "webhook.disabled";
+pub const ADMIN_TOKEN_CREATED: &str = "admin_token.created";
+pub const ADMIN_TOKEN_REVOKED: &str = "admin_token.revoked";
+pub const JOB_CREATED: &str = "job.created";
+pub const SCHEDULED_TRANSACTION_CREATED: &str = "scheduled_transaction.created";
+pub const SCHEDULED_TRANSACTION_PAUSED: &str = "scheduled_transaction.paused";
+pub const SCHEDULED_TRANSACTION_RESUMED: &str = "scheduled_transaction.resumed";
+pub const SCHEDULED_TRANSACTION_SKIPPED: &str = "scheduled_transaction.skipped";
+pub const SCHEDULED_TRANSACTION_CANCELLED: &str = "scheduled_transaction.cancelled";
+pub const FEE_SCHEDULE_UPDATED: &str = "fee_schedule.updated";
+pub const FEE_SCHEDULE_DELETED: &str = "fee_schedule.deleted";
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+
+/// A mutation to append to the audit log.
+pub struct AuditRecord<'a> {
+    pub action: &'a str,
+    pub resource_type: &'a str,
+    pub resource_id: Uuid,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// Appends to the audit log inside the caller's transaction, so the entry
+/// exists exactly when the change it describes does. The entry is chained
+/// (`id`, `prev_hash`, `hash`) by the `audit_log_chain_at_commit` trigger when
+/// the caller commits, so the chain lock is only held for the commit.
+pub async fn record(
+    tx: &mut Transaction<'_, Postgres>,
+    ctx: &AuditContext,
+    record: AuditRecord<'_>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (actor, action, resource_type, resource_id, before, after, request_id, ip)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(ctx.actor.to_string())
+    .bind(record.action)
+    .bind(record.resource_type)
+    .bind(record.resource_id)
+    .bind(record.before)
+    .bind(record.after)
+    .bind(&ctx.request_id)
+    .bind(&ctx.ip)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Snapshot of a resource for `before`/`after`. Uses the resource's API
+/// representation, so secrets marked `skip_serializing` are never logged.
+pub fn snapshot<T: Serialize>(value: &T) -> Option<serde_json::Value> {
+    serde_json::to_value(value).ok()
+}
+
+#[derive(Clone)]
+pub struct AuditService {
+    database: Arc<Database>,
+}
+
+impl AuditService {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn list_entries(&self, query: ListAuditLogQuery) -> Result<ListAuditLogResponse> {
+        query.validate()?;
+
+        let after = query.after.unwrap_or(0);
+
+        let entries = sqlx::query_as::<_, AuditEntry>(
+            r#"
+            SELECT id, actor, action, resource_type, resource_id, before, after, request_id, ip, prev_hash, hash, created_at
+            FROM audit_log
+            WHERE id > $1
+            AND ($2::text IS NULL OR actor = $2)
+            AND ($3::text IS NULL OR action = $3)
+            AND ($4::text IS NULL OR resource_type = $4)
+            AND ($5::uuid IS NULL OR resource_id = $5)
+            AND ($6::text IS NULL OR request_id = $6)
+            ORDER BY id
+            LIMIT $7
+            "#,
+        )
+        .bind(after)
+        .bind(&query.actor)
+        .bind(&query.action)
+        .bind(&query.resource_type)
+        .bind(query.resource_id)
+        .bind(&query.request_id)
+        .bind(query.limit.unwrap_or(DEFAULT_PAGE_SIZE))
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let next_cursor = entries.last().map(|e| e.id).unwrap_or(after);
+
+        Ok(ListAuditLogResponse { entries, next_cursor })
+    }
+
+    /// Recomputes every entry's hash and checks each links to its predecessor.
+    pub async fn verify_chain(&self) -> Result<AuditVerificationResponse> {
+        let (entries_checked, first_invalid_id, head_hash) = sqlx::query_as::<_, (i64, Option<i64>, Option<String>)>(
+            r#"
+            WITH chain AS (
+                SELECT
+                    id,
+                    hash,
+                    prev_hash IS DISTINCT FROM LAG(hash) OVER (ORDER BY id)
+                        OR hash <> audit_entry_hash(
+                            prev_hash, created_at, actor, action, resource_type,
+                            resource_id, before, after, request_id, ip
+                        ) AS invalid
+                FROM audit_log
+            )
+            SELECT
+                COUNT(*),
+                MIN(id) FILTER (WHERE invalid),
+                (SELECT hash FROM chain ORDER BY id DESC LIMIT 1)
+            FROM chain
+            "#,
+        )
+        .fetch_one(self.database.pool())
+        .await?;
+
+        if let Some(id) = first_invalid_id {
+            tracing::error!(first_invalid_id = id, "Audit log hash chain is broken");
+        }
+
+        Ok(AuditVerificationResponse {
+            valid: first_invalid_id.is_none(),
+            entries_checked,
+            head_hash,
+            first_invalid_id,
+        })
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{
+    config::Config,
+    database::Database,
+    error::Result,
+    events,
+    models::{AccountEvent, ListEventsQuery, ListEventsResponse, Transaction, WebhookPayload},
+};
+use chrono::Utc;
+use futures::Stream;
+use serde_json::json;
+use sqlx::{postgres::PgListener, Postgres};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+use validator::Validate;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Postgres channel notified with the account id whenever events are recorded.
+const EVENTS_CHANNEL: &str = "account_events";
+
+/// How often waiting readers re-check the database in case a notification was missed.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Persisted per-account event log, for clients that poll instead of receiving webhooks.
+#[derive(Clone)]
+pub struct EventService {
+    database: Arc<Database>,
+    /// Account ids with new events, fanned out from `LISTEN account_events`.
+    new_events: broadcast::Sender<Uuid>,
+    retention_days: i32,
+    max_wait: Duration,
+}
+
+impl EventService {
+    pub fn new(database: Arc<Database>, config: &Config) -> Self {
+        Self {
+            database,
+            new_events: broadcast::channel(1024).0,
+            retention_days: config.event_retention_days,
+            max_wait: Duration::from_secs(config.events_max_wait_seconds),
+        }
+    }
+
+    /// Forwards `account_events` notifications from Postgres to waiting readers,
+    /// so events recorded by any instance reach every instance's streams. Runs
+    /// for the life of the process: if the listener can't be set up it is
+    /// retried, and readers fall back to polling meanwhile.
+    pub async fn listen_for_events(self) {
+        loop {
+            if let Err(e) = self.forward_notifications().await {
+                tracing::error!("Event listener failed, restarting: {}", e);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn forward_notifications(&self) -> anyhow::Result<()> {
+        let mut listener = PgListener::connect_with(self.database.pool()).await?;
+        listener.listen(EVENTS_CHANNEL).await?;
+
+        loop {
+            match listener.recv().await {
+                Ok(notification) => {
+                    if let Ok(account_id) = notification.payload().parse::<Uuid>() {
+                        // No receivers just means nobody is waiting.
+                        let _ = self.new_events.send(account_id);
+                    }
+                }
+                Err(e) => {
+                    // The listener reconnects on the next recv; readers catch up on their next poll.
+                    tracing::warn!("Event listener error: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    /// Events recorded after the `after` cursor, oldest first.
+    ///
+    /// With `wait`, holds the request open until an event arrives or the wait
+    /// (capped at `EVENTS_MAX_WAIT_SECONDS`) elapses.
+    pub async fn list_events(&self, account_id: Uuid, query: ListEventsQuery) -> Result<ListEventsResponse> {
+        query.validate()?;
+
+        let after = query.after.unwrap_or(0);
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        let wait = Duration::from_secs(query.wait.unwrap_or(0)).min(self.max_wait);
+        let deadline = Instant::now() + wait;
+
+        // Subscribe before querying so an event recorded in between isn't missed.
+        let mut receiver = self.new_events.subscribe();
+
+        loop {
+            let events = self.fetch_events(account_id, after, limit).await?;
+
+            let now = Instant::now();
+            if !events.is_empty() || now >= deadline {
+                let next_cursor = events.last().map_or(after, |e| e.id);
+                return Ok(ListEventsResponse { events, next_cursor });
+            }
+
+            let wait = (deadline - now).min(POLL_INTERVAL);
+            let _ = tokio::time::timeout(wait, wait_for_account(&mut receiver, account_id)).await;
+        }
+    }
+
+    /// Cursor of the account's most recent event, or 0 if it has none.
+    pub async fn latest_event_id(&self, account_id: Uuid) -> Result<i64> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COALESCE(MAX(id), 0)
+            FROM account_events
+            WHERE account_id = $1
+            "#,
+        )
+        .bind(account_id)
+        .fetch_one(self.database.pool())
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Events recorded after the `after` cursor, followed by new events as they
+    /// are recorded. Ends if the database becomes unavailable; clients resume
+    /// from the last event they saw.
+    pub fn stream_events(&self, account_id: Uuid, after: i64) -> impl Stream<Item = AccountEvent> {
+        let receiver = self.new_events.subscribe();
+
+        futures::stream::unfold(
+            (self.clone(), receiver, after, VecDeque::new()),
+            move |(service, mut receiver, mut cursor, mut buffered)| async move {
+                loop {
+                    if let Some(event) = buffered.pop_front() {
+                        return Some((event, (service, receiver, cursor, buffered)));
+                    }
+
+                    match service.fetch_events(account_id, cursor, DEFAULT_PAGE_SIZE).await {
+                        Ok(events) if !events.is_empty() => {
+                            cursor = events.last().map_or(cursor, |e| e.id);
+                            buffered.extend(events);
+                        }
+                        Ok(_) => {
+                            let _ = tokio::time::timeout(
+                                POLL_INTERVAL,
+                                wait_for_account(&mut receiver, account_id),
+                            )
+                            .await;
+                        }
+                        Err(e) => {
+                            tracing::error!(account_id = %account_id, "Failed to read event stream: {}", e);
+                            return None;
+                        }
+                    }
+                }
+            },
+        )
+    }
+
+    async fn fetch_events(&self, account_id: Uuid, after: i64, limit: i64) -> Result<Vec<AccountEvent>> {
+        let events = sqlx::query_as::<_, AccountEvent>(
+            r#"
+            SELECT id, payload
+            FROM account_events
+            WHERE account_id = $1 AND id > $2
+            ORDER BY id
+            LIMIT $3
+            "#,
+        )
+        .bind(account_id)
+        .bind(after)
+        .bind(limit)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Deletes events older than the retention period.
+    pub async fn purge_expired_events(&self) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM account_events
+            WHERE created_at < NOW() - make_interval(days => $1)
+            "#,
+        )
+        .bind(self.retention_days)
+        .execute(self.database.pool())
+        .await?;
+
+        if result.rows_affected() > 0 {
+            tracing::info!(count = result.rows_affected(), "Purged expired account events");
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves once events are recorded for `account_id`, or when notifications
+/// were dropped and the caller should re-check.
+async fn wait_for_account(receiver: &mut broadcast::Receiver<Uuid>, account_id: Uuid) {
+    loop {
+        match receiver.recv().await {
+            Ok(id) if id == account_id => return,
+            Ok(_) => continue,
+            Err(RecvError::Lagged(_)) => return,
+            Err(RecvError::Closed) => std::future::pending::<()>().await,
+        }
+    }
+}
+
+/// Records the events a transaction produces for its account and, for
+/// transfers, the counterparty account, within `tx`, the database transaction
+/// that writes it, so the events exist exactly when the transaction does.
+/// `balances_after` holds the balances the transaction left each account
+/// with, for `balance.updated`. Waiting readers are notified when `tx` commits.
+pub async fn record_transaction_events(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    transaction: &Transaction,
+    balances_after: &HashMap<Uuid, i64>,
+) -> Result<()> {
+    let mut account_ids = vec![transaction.account_id];
+    if let Some(counterparty_id) = transaction.counterparty_account_id {
+        if counterparty_id != transaction.account_id {
+            account_ids.push(counterparty_id);
+        }
+    }
+    // Lock in a fixed order so two transfers between the same accounts can't deadlock.
+    account_ids.sort();
+
+    // Event ids are the polling cursor, so per account they must become
+    // visible in id order; hold the account lock until commit.
+    for account_id in &account_ids {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1::text))")
+            .bind(account_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    for &account_id in &account_ids {
+        let account_events = events::events_for_account(transaction, account_id);
+        // e.g. the sender of a split leg, which is reported through the parent
+        if account_events.is_empty() {
+            continue;
+        }
+
+        for event in account_events {
+            let payload = events::transaction_payload(event, transaction);
+
+            sqlx::query(
+                r#"
+                INSERT INTO account_events (account_id, event, transaction_id, payload)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(account_id)
+            .bind(event)
+            .bind(transaction.id)
+            .bind(sqlx::types::Json(&payload))
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        if let Some(&balance) = balances_after.get(&account_id).filter(|_| transaction.status == "completed") {
+            let payload = WebhookPayload {
+                event: events::BALANCE_UPDATED.to_string(),
+                delivery_id: None,
+                sequence: None,
+                transaction: None,
+                transfer: None,
+                data: Some(json!({
+                    "account_id": account_id,
+                    "balance": balance,
+                    "transaction_id": transaction.id,
+                })),
+                timestamp: Utc::now(),
+            };
+
+            sqlx::query(
+                r#"
+                INSERT INTO account_events (account_id, event, transaction_id, payload)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(account_id)
+            .bind(events::BALANCE_UPDATED)
+            .bind(transaction.id)
+            .bind(sqlx::types::Json(&payload))
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        // Delivered to listeners when the transaction commits.
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(EVENTS_CHANNEL)
+            .bind(account_id.to_string())
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Records `event` for `account_id` as part of `transaction`, within `tx`,
+/// for events that depend on more than the transaction itself, such as
+/// `balance.low`. `data` goes in the payload next to the transaction.
+pub async fn record_transaction_event(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    account_id: Uuid,
+    event: &str,
+    transaction: &Transaction,
+    data: &serde_json::Value,
+) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1::text))")
+        .bind(account_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let mut payload = events::transaction_payload(event, transaction);
+    payload.data = Some(data.clone());
+
+    sqlx::query(
+        r#"
+        INSERT INTO account_events (account_id, event, transaction_id, payload)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(account_id)
+    .bind(event)
+    .bind(transaction.id)
+    .bind(sqlx::types::Json(&payload))
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(EVENTS_CHANNEL)
+        .bind(account_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
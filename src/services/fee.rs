@@ -0,0 +1,258 @@
+use crate::{
+    config::Config,
+    database::Database,
+    error::{AppError, Result},
+    models::{AuditContext, FeeSchedule, TransactionType, UpdateFeeScheduleRequest},
+    services::audit::{self, AuditRecord},
+};
+use sqlx::Postgres;
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Clone)]
+pub struct FeeService {
+    database: Arc<Database>,
+    revenue_account_id: Option<Uuid>,
+}
+
+impl FeeService {
+    pub fn new(database: Arc<Database>, config: &Config) -> Self {
+        Self {
+            database,
+            revenue_account_id: config.platform_revenue_account_id,
+        }
+    }
+
+    pub async fn list_schedules(&self, account_id: Uuid) -> Result<Vec<FeeSchedule>> {
+        let schedules = sqlx::query_as::<_, FeeSchedule>(
+            r#"
+            SELECT id, account_id, transaction_type, fixed_fee, percentage_bps, min_fee, max_fee, created_at, updated_at
+            FROM fee_schedules
+            WHERE account_id = $1
+            ORDER BY transaction_type
+            "#,
+        )
+        .bind(account_id)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        Ok(schedules)
+    }
+
+    /// Creates or replaces the account's schedule for `transaction_type`.
+    /// Refused while no revenue account is configured, since the fees would
+    /// have nowhere to go.
+    pub async fn set_schedule(
+        &self,
+        account_id: Uuid,
+        transaction_type: TransactionType,
+        req: UpdateFeeScheduleRequest,
+        ctx: &AuditContext,
+    ) -> Result<FeeSchedule> {
+        req.validate()?;
+        if self.revenue_account_id.is_none() {
+            return Err(AppError::FeesDisabled);
+        }
+
+        let mut tx = self.database.begin_transaction().await?;
+
+        sqlx::query_scalar::<_, Uuid>("SELECT id FROM accounts WHERE id = $1")
+            .bind(account_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::AccountNotFound {
+                account_id: account_id.to_string(),
+            })?;
+
+        let existing = Self::find_schedule(&mut tx, account_id, &transaction_type).await?;
+
+        let schedule = sqlx::query_as::<_, FeeSchedule>(
+            r#"
+            INSERT INTO fee_schedules (account_id, transaction_type, fixed_fee, percentage_bps, min_fee, max_fee)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (account_id, transaction_type) DO UPDATE
+            SET fixed_fee = EXCLUDED.fixed_fee,
+                percentage_bps = EXCLUDED.percentage_bps,
+                min_fee = EXCLUDED.min_fee,
+                max_fee = EXCLUDED.max_fee,
+                updated_at = NOW()
+            RETURNING id, account_id, transaction_type, fixed_fee, percentage_bps, min_fee, max_fee, created_at, updated_at
+            "#,
+        )
+        .bind(account_id)
+        .bind(transaction_type.to_string())
+        .bind(req.fixed_fee)
+        .bind(req.percentage_bps)
+        .bind(req.min_fee)
+        .bind(req.max_fee)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::FEE_SCHEDULE_UPDATED,
+                resource_type: "fee_schedule",
+                resource_id: schedule.id,
+                before: existing.as_ref().and_then(audit::snapshot),
+                after: audit::snapshot(&schedule),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(
+            account_id = %account_id,
+            transaction_type = %schedule.transaction_type,
+            "Fee schedule updated"
+        );
+
+        Ok(schedule)
+    }
+
+    pub async fn delete_schedule(
+        &self,
+        account_id: Uuid,
+        transaction_type: TransactionType,
+        ctx: &AuditContext,
+    ) -> Result<()> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        let existing = Self::find_schedule(&mut tx, account_id, &transaction_type)
+            .await?
+            .ok_or_else(|| AppError::FeeScheduleNotFound {
+                account_id: account_id.to_string(),
+                transaction_type: transaction_type.to_string(),
+            })?;
+
+        sqlx::query("DELETE FROM fee_schedules WHERE id = $1")
+            .bind(existing.id)
+            .execute(&mut *tx)
+            .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::FEE_SCHEDULE_DELETED,
+                resource_type: "fee_schedule",
+                resource_id: existing.id,
+                before: audit::snapshot(&existing),
+                after: None,
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(account_id = %account_id, transaction_type = %transaction_type, "Fee schedule deleted");
+
+        Ok(())
+    }
+
+    async fn find_schedule(
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        account_id: Uuid,
+        transaction_type: &TransactionType,
+    ) -> Result<Option<FeeSchedule>> {
+        let schedule = sqlx::query_as::<_, FeeSchedule>(
+            r#"
+            SELECT id, account_id, transaction_type, fixed_fee, percentage_bps, min_fee, max_fee, created_at, updated_at
+            FROM fee_schedules
+            WHERE account_id = $1 AND transaction_type = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(account_id)
+        .bind(transaction_type.to_string())
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        Ok(schedule)
+    }
+}
+
+/// The fee `account_id` owes on a transaction of `transaction_type` and
+/// `amount`, read within `tx`. `None` when it has no schedule for the type;
+/// a schedule can still work out to a fee of zero.
+pub async fn fee_for(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    account_id: Uuid,
+    transaction_type: &TransactionType,
+    amount: i64,
+) -> Result<Option<i64>> {
+    let schedule = FeeService::find_schedule(tx, account_id, transaction_type).await?;
+    Ok(schedule.map(|schedule| fee_amount(&schedule, amount)))
+}
+
+/// The percentage part is rounded half up, to the nearest unit.
+fn fee_amount(schedule: &FeeSchedule, amount: i64) -> i64 {
+    let percentage = (i128::from(amount) * i128::from(schedule.percentage_bps) + 5_000) / 10_000;
+    let mut fee = i128::from(schedule.fixed_fee) + percentage;
+    if let Some(min_fee) = schedule.min_fee {
+        fee = fee.max(i128::from(min_fee));
+    }
+    if let Some(max_fee) = schedule.max_fee {
+        fee = fee.min(i128::from(max_fee));
+    }
+    i64::try_from(fee).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn schedule(fixed_fee: i64, percentage_bps: i32, min_fee: Option<i64>, max_fee: Option<i64>) -> FeeSchedule {
+        FeeSchedule {
+            id: Uuid::nil(),
+            account_id: Uuid::nil(),
+            transaction_type: "transfer".to_string(),
+            fixed_fee,
+            percentage_bps,
+            min_fee,
+            max_fee,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn adds_the_fixed_and_percentage_parts() {
+        assert_eq!(fee_amount(&schedule(30, 0, None, None), 10_000), 30);
+        assert_eq!(fee_amount(&schedule(0, 290, None, None), 10_000), 290);
+        assert_eq!(fee_amount(&schedule(30, 290, None, None), 10_000), 320);
+        assert_eq!(fee_amount(&schedule(0, 0, None, None), 10_000), 0);
+    }
+
+    #[test]
+    fn rounds_the_percentage_half_up() {
+        // 1% of 149 is 1.49, of 150 is 1.5
+        assert_eq!(fee_amount(&schedule(0, 100, None, None), 149), 1);
+        assert_eq!(fee_amount(&schedule(0, 100, None, None), 150), 2);
+        assert_eq!(fee_amount(&schedule(0, 100, None, None), 49), 0);
+        assert_eq!(fee_amount(&schedule(0, 100, None, None), 50), 1);
+        // 0.01% of 1 unit rounds to nothing
+        assert_eq!(fee_amount(&schedule(0, 1, None, None), 1), 0);
+    }
+
+    #[test]
+    fn applies_min_and_max() {
+        let capped = schedule(5, 100, Some(10), Some(50));
+        assert_eq!(fee_amount(&capped, 100), 10);
+        assert_eq!(fee_amount(&capped, 1_000), 15);
+        assert_eq!(fee_amount(&capped, 4_500), 50);
+        assert_eq!(fee_amount(&capped, 1_000_000), 50);
+        // A minimum applies even when the schedule would charge nothing.
+        assert_eq!(fee_amount(&schedule(0, 0, Some(25), None), 1), 25);
+    }
+
+    #[test]
+    fn saturates_instead_of_overflowing() {
+        assert_eq!(fee_amount(&schedule(i64::MAX, 10_000, None, None), i64::MAX), i64::MAX);
+        assert_eq!(fee_amount(&schedule(0, 10_000, None, None), i64::MAX), i64::MAX);
+    }
+}
@@ -0,0 +1,480 @@
+use crate::{
+    config::Config,
+    database::Database,
+    error::{AppError, Result},
+    models::{
+        Actor, AuditContext, BatchItemResult, BatchItemStatus, CreateTransactionRequest, Job,
+        JobItemResult, JobStatus,
+    },
+    services::{
+        audit::{self, AuditRecord},
+        transaction::publish_transaction,
+        TransactionService, WebhookService,
+    },
+};
+use futures::{stream, Stream, TryStreamExt};
+use sqlx::{postgres::PgListener, types::Json};
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+
+pub const KIND_TRANSACTION_IMPORT: &str = "transaction_import";
+
+const JOBS_CHANNEL: &str = "jobs";
+/// Items per `run_batch` call for best-effort jobs; progress is saved after each.
+const CHUNK_SIZE: i64 = 100;
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// A running job whose worker hasn't sent a heartbeat for this long is
+/// assumed dead (e.g. the process restarted) and is picked up again.
+const STALE_AFTER_SECONDS: f64 = 60.0;
+/// Also how often the worker looks for stale jobs when no new ones arrive.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_ATTEMPTS: i32 = 5;
+const RESULTS_PAGE_SIZE: i64 = 1000;
+
+#[derive(Clone)]
+pub struct JobService {
+    database: Arc<Database>,
+    transaction_service: TransactionService,
+    webhook_service: WebhookService,
+    max_items: usize,
+}
+
+#[derive(sqlx::FromRow)]
+struct PendingItem {
+    item_index: i32,
+    request: Json<CreateTransactionRequest>,
+    idempotency_key: Option<String>,
+}
+
+impl JobService {
+    pub fn new(
+        database: Arc<Database>,
+        transaction_service: TransactionService,
+        webhook_service: WebhookService,
+        config: &Config,
+    ) -> Self {
+        Self {
+            database,
+            transaction_service,
+            webhook_service,
+            max_items: config.job_max_items,
+        }
+    }
+
+    /// Queues a transaction import. Items without an idempotency key are given
+    /// `job:<job id>:<index>`, so a job resumed after a restart never creates
+    /// the same transaction twice.
+    pub async fn create_transaction_import(
+        &self,
+        account_id: Uuid,
+        items: Vec<CreateTransactionRequest>,
+        atomic: bool,
+        ctx: &AuditContext,
+    ) -> Result<Job> {
+        if items.is_empty() {
+            return Err(AppError::InvalidBatch("no transactions".to_string()));
+        }
+        if items.len() > self.max_items {
+            return Err(AppError::InvalidBatch(format!(
+                "{} transactions exceeds the limit of {}",
+                items.len(),
+                self.max_items
+            )));
+        }
+
+        let api_key_id = match ctx.actor {
+            Actor::ApiKey(key_id) => Some(key_id),
+            _ => None,
+        };
+
+        let mut tx = self.database.begin_transaction().await?;
+
+        let job = sqlx::query_as::<_, Job>(
+            r#"
+            INSERT INTO jobs (account_id, kind, atomic, total_items, api_key_id, request_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, account_id, kind, status, atomic, total_items, processed_items, succeeded_items,
+                failed_items, error, attempts, api_key_id, request_id, created_at, started_at, finished_at
+            "#,
+        )
+        .bind(account_id)
+        .bind(KIND_TRANSACTION_IMPORT)
+        .bind(atomic)
+        .bind(items.len() as i32)
+        .bind(api_key_id)
+        .bind(&ctx.request_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO job_items (job_id, item_index, request, idempotency_key)
+            SELECT
+                $1,
+                (t.ord - 1)::INTEGER,
+                t.elem,
+                COALESCE(t.elem->>'idempotency_key', 'job:' || $1::text || ':' || (t.ord - 1))
+            FROM jsonb_array_elements($2) WITH ORDINALITY AS t(elem, ord)
+            "#,
+        )
+        .bind(job.id)
+        .bind(Json(&items))
+        .execute(&mut *tx)
+        .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::JOB_CREATED,
+                resource_type: "job",
+                resource_id: job.id,
+                before: None,
+                after: audit::snapshot(&job),
+            },
+        )
+        .await?;
+
+        // Delivered on commit; wakes an idle worker straight away.
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(JOBS_CHANNEL)
+            .bind(job.id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(job_id = %job.id, account_id = %account_id, items = job.total_items, atomic, "Job queued");
+
+        Ok(job)
+    }
+
+    pub async fn get_job(&self, account_id: Uuid, job_id: Uuid) -> Result<Job> {
+        let job = sqlx::query_as::<_, Job>(
+            r#"
+            SELECT id, account_id, kind, status, atomic, total_items, processed_items, succeeded_items,
+                failed_items, error, attempts, api_key_id, request_id, created_at, started_at, finished_at
+            FROM jobs
+            WHERE id = $1 AND account_id = $2
+            "#,
+        )
+        .bind(job_id)
+        .bind(account_id)
+        .fetch_optional(self.database.pool())
+        .await?
+        .ok_or_else(|| AppError::JobNotFound {
+            job_id: job_id.to_string(),
+        })?;
+
+        Ok(job)
+    }
+
+    pub async fn list_failed_items(&self, job_id: Uuid, limit: i64) -> Result<Vec<JobItemResult>> {
+        let items = sqlx::query_as::<_, JobItemResult>(
+            r#"
+            SELECT item_index, status, idempotency_key, transaction_id, error_code, error
+            FROM job_items
+            WHERE job_id = $1 AND status = 'failed'
+            ORDER BY item_index
+            LIMIT $2
+            "#,
+        )
+        .bind(job_id)
+        .bind(limit)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        Ok(items)
+    }
+
+    /// Every item's result in order, read a page at a time.
+    pub fn item_results(&self, job_id: Uuid) -> impl Stream<Item = Result<JobItemResult>> + Send + 'static {
+        let database = self.database.clone();
+
+        stream::unfold(Some(-1), move |after| {
+            let database = database.clone();
+            async move {
+                let after = after?;
+                let page = sqlx::query_as::<_, JobItemResult>(
+                    r#"
+                    SELECT item_index, status, idempotency_key, transaction_id, error_code, error
+                    FROM job_items
+                    WHERE job_id = $1 AND item_index > $2
+                    ORDER BY item_index
+                    LIMIT $3
+                    "#,
+                )
+                .bind(job_id)
+                .bind(after)
+                .bind(RESULTS_PAGE_SIZE)
+                .fetch_all(database.pool())
+                .await;
+
+                match page {
+                    Ok(page) => {
+                        let next = match page.last() {
+                            Some(last) if page.len() as i64 == RESULTS_PAGE_SIZE => Some(last.index),
+                            _ => None,
+                        };
+                        Some((Ok(page), next))
+                    }
+                    Err(e) => Some((Err(AppError::from(e)), None)),
+                }
+            }
+        })
+        .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+        .try_flatten()
+    }
+
+    /// Processes queued jobs one at a time until the process exits. Several
+    /// instances can run this side by side; each job is claimed by one.
+    ///
+    /// If the worker fails to start or panics on a job it is restarted after
+    /// the poll interval; the job it was on is reclaimed once its heartbeat
+    /// goes stale.
+    pub async fn run_worker(self) {
+        loop {
+            match tokio::spawn(self.clone().process_jobs()).await {
+                Ok(Err(e)) => tracing::error!("Job worker failed, restarting: {}", e),
+                Err(e) => tracing::error!("Job worker panicked, restarting: {}", e),
+                Ok(Ok(())) => {}
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn process_jobs(self) -> anyhow::Result<()> {
+        let mut listener = PgListener::connect_with(self.database.pool()).await?;
+        listener.listen(JOBS_CHANNEL).await?;
+
+        loop {
+            loop {
+                match self.claim_next_job().await {
+                    Ok(Some(job)) => self.process_job(job).await,
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::error!("Failed to claim job: {}", e);
+                        break;
+                    }
+                }
+            }
+
+            // Otherwise woken by a notification, or the poll interval elapsed
+            if let Ok(Err(e)) = tokio::time::timeout(POLL_INTERVAL, listener.recv()).await {
+                tracing::warn!("Job listener error: {}", e);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+
+    async fn claim_next_job(&self) -> Result<Option<Job>> {
+        let job = sqlx::query_as::<_, Job>(
+            r#"
+            UPDATE jobs
+            SET status = 'running',
+                attempts = attempts + 1,
+                heartbeat_at = NOW(),
+                started_at = COALESCE(started_at, NOW())
+            WHERE id = (
+                SELECT id
+                FROM jobs
+                WHERE status = 'queued'
+                OR (status = 'running' AND heartbeat_at < NOW() - make_interval(secs => $1))
+                ORDER BY created_at
+                FOR UPDATE SKIP LOCKED
+                LIMIT 1
+            )
+            RETURNING id, account_id, kind, status, atomic, total_items, processed_items, succeeded_items,
+                failed_items, error, attempts, api_key_id, request_id, created_at, started_at, finished_at
+            "#,
+        )
+        .bind(STALE_AFTER_SECONDS)
+        .fetch_optional(self.database.pool())
+        .await?;
+
+        Ok(job)
+    }
+
+    async fn process_job(&self, job: Job) {
+        tracing::info!(job_id = %job.id, attempt = job.attempts, "Processing job");
+
+        let heartbeat = tokio::spawn(Self::heartbeat(self.database.clone(), job.id));
+        let outcome = self.process_items(&job).await;
+        heartbeat.abort();
+
+        let result = match outcome {
+            Ok(()) => self.finish_job(job.id, JobStatus::Completed, None).await,
+            Err(e) if job.attempts >= MAX_ATTEMPTS => {
+                tracing::error!(job_id = %job.id, "Job failed, giving up: {}", e);
+                let (_, message) = e.status_and_message();
+                self.finish_job(job.id, JobStatus::Failed, Some(message)).await
+            }
+            Err(e) => {
+                tracing::warn!(job_id = %job.id, attempt = job.attempts, "Job failed, will retry: {}", e);
+                self.requeue_job(job.id).await
+            }
+        };
+
+        if let Err(e) = result {
+            // Left as running; it is reclaimed once its heartbeat goes stale.
+            tracing::error!(job_id = %job.id, "Failed to update job status: {}", e);
+        }
+    }
+
+    async fn heartbeat(database: Arc<Database>, job_id: Uuid) {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = sqlx::query("UPDATE jobs SET heartbeat_at = NOW() WHERE id = $1")
+                .bind(job_id)
+                .execute(database.pool())
+                .await
+            {
+                tracing::warn!(job_id = %job_id, "Failed to record job heartbeat: {}", e);
+            }
+        }
+    }
+
+    /// Runs the items that don't have a result yet. Atomic jobs go through
+    /// `run_batch` in one piece; otherwise items are run in chunks and each
+    /// chunk's results are saved before the next starts.
+    async fn process_items(&self, job: &Job) -> Result<()> {
+        let ctx = AuditContext {
+            actor: job.api_key_id.map(Actor::ApiKey).unwrap_or(Actor::System),
+            request_id: job.request_id.clone(),
+            ip: None,
+        };
+        let chunk_size = if job.atomic { job.total_items as i64 } else { CHUNK_SIZE };
+
+        loop {
+            let pending = sqlx::query_as::<_, PendingItem>(
+                r#"
+                SELECT item_index, request, idempotency_key
+                FROM job_items
+                WHERE job_id = $1 AND status = 'pending'
+                ORDER BY item_index
+                LIMIT $2
+                "#,
+            )
+            .bind(job.id)
+            .bind(chunk_size)
+            .fetch_all(self.database.pool())
+            .await?;
+
+            if pending.is_empty() {
+                return Ok(());
+            }
+
+            let indexes: Vec<i32> = pending.iter().map(|item| item.item_index).collect();
+            let requests = pending
+                .into_iter()
+                .map(|item| CreateTransactionRequest {
+                    idempotency_key: item.idempotency_key,
+                    ..item.request.0
+                })
+                .collect();
+
+            let response = self
+                .transaction_service
+                .run_batch(job.account_id, requests, job.atomic, &ctx)
+                .await?;
+
+            self.save_results(job.id, &indexes, &response.results).await?;
+
+            for result in &response.results {
+                if let (BatchItemStatus::Created, Some(transaction)) = (result.status, &result.transaction) {
+                    publish_transaction(&self.webhook_service, transaction).await;
+                }
+            }
+        }
+    }
+
+    async fn save_results(&self, job_id: Uuid, indexes: &[i32], results: &[BatchItemResult]) -> Result<()> {
+        let item_indexes: Vec<i32> = results.iter().map(|r| indexes[r.index]).collect();
+        let statuses: Vec<String> = results.iter().map(|r| r.status.to_string()).collect();
+        let transaction_ids: Vec<Option<Uuid>> =
+            results.iter().map(|r| r.transaction.as_ref().map(|t| t.id)).collect();
+        let error_codes: Vec<Option<i32>> =
+            results.iter().map(|r| r.error.as_ref().map(|e| e.code as i32)).collect();
+        let errors: Vec<Option<String>> =
+            results.iter().map(|r| r.error.as_ref().map(|e| e.error.clone())).collect();
+
+        let succeeded = results
+            .iter()
+            .filter(|r| matches!(r.status, BatchItemStatus::Created | BatchItemStatus::Existing))
+            .count() as i32;
+        let failed = results
+            .iter()
+            .filter(|r| r.status == BatchItemStatus::Failed)
+            .count() as i32;
+
+        let mut tx = self.database.begin_transaction().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE job_items
+            SET status = r.status, transaction_id = r.transaction_id, error_code = r.error_code, error = r.error
+            FROM UNNEST($2::INTEGER[], $3::VARCHAR[], $4::UUID[], $5::INTEGER[], $6::TEXT[])
+                AS r(item_index, status, transaction_id, error_code, error)
+            WHERE job_items.job_id = $1 AND job_items.item_index = r.item_index
+            "#,
+        )
+        .bind(job_id)
+        .bind(&item_indexes)
+        .bind(&statuses)
+        .bind(&transaction_ids)
+        .bind(&error_codes)
+        .bind(&errors)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET processed_items = processed_items + $2,
+                succeeded_items = succeeded_items + $3,
+                failed_items = failed_items + $4,
+                heartbeat_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(results.len() as i32)
+        .bind(succeeded)
+        .bind(failed)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn finish_job(&self, job_id: Uuid, status: JobStatus, error: Option<String>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = $2, error = $3, finished_at = NOW(), heartbeat_at = NULL
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(status)
+        .bind(error)
+        .execute(self.database.pool())
+        .await?;
+
+        tracing::info!(job_id = %job_id, status = ?status, "Job finished");
+
+        Ok(())
+    }
+
+    async fn requeue_job(&self, job_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE jobs SET status = 'queued', heartbeat_at = NULL WHERE id = $1")
+            .bind(job_id)
+            .execute(self.database.pool())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,368 @@
+use crate::{
+    database::Database,
+    error::{AppError, Result},
+    models::{AccountLimits, AuditContext, UpdateAccountLimitsRequest},
+    services::{
+        audit::{self, AuditRecord},
+        transaction::validation_error,
+    },
+};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::Postgres;
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+const MAX_TRANSACTION_AMOUNT: &str = "max_transaction_amount";
+const DAILY_DEBIT_LIMIT: &str = "daily_debit_limit";
+const MONTHLY_DEBIT_LIMIT: &str = "monthly_debit_limit";
+const HOURLY_TRANSFER_LIMIT: &str = "hourly_transfer_limit";
+
+#[derive(Clone)]
+pub struct LimitService {
+    database: Arc<Database>,
+}
+
+impl LimitService {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn get_limits(&self, account_id: Uuid) -> Result<AccountLimits> {
+        let mut tx = self.database.begin_transaction().await?;
+        Self::ensure_account(&mut tx, account_id).await?;
+        let limits = find_limits(&mut tx, account_id).await?;
+        tx.commit().await?;
+
+        Ok(limits.unwrap_or_else(|| no_limits(account_id)))
+    }
+
+    pub async fn set_limits(
+        &self,
+        account_id: Uuid,
+        req: UpdateAccountLimitsRequest,
+        ctx: &AuditContext,
+    ) -> Result<AccountLimits> {
+        req.validate()?;
+
+        let mut tx = self.database.begin_transaction().await?;
+        Self::ensure_account(&mut tx, account_id).await?;
+        let existing = find_limits(&mut tx, account_id).await?;
+
+        let limits = sqlx::query_as::<_, AccountLimits>(
+            r#"
+            INSERT INTO account_limits (account_id, max_transaction_amount, daily_debit_limit, monthly_debit_limit, hourly_transfer_limit)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (account_id) DO UPDATE
+            SET max_transaction_amount = EXCLUDED.max_transaction_amount,
+                daily_debit_limit = EXCLUDED.daily_debit_limit,
+                monthly_debit_limit = EXCLUDED.monthly_debit_limit,
+                hourly_transfer_limit = EXCLUDED.hourly_transfer_limit,
+                updated_at = NOW()
+            RETURNING account_id, max_transaction_amount, daily_debit_limit, monthly_debit_limit, hourly_transfer_limit, updated_at
+            "#,
+        )
+        .bind(account_id)
+        .bind(req.max_transaction_amount)
+        .bind(req.daily_debit_limit)
+        .bind(req.monthly_debit_limit)
+        .bind(req.hourly_transfer_limit)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::ACCOUNT_LIMITS_UPDATED,
+                resource_type: "account",
+                resource_id: account_id,
+                before: audit::snapshot(&existing.unwrap_or_else(|| no_limits(account_id))),
+                after: audit::snapshot(&limits),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(account_id = %account_id, "Account limits updated");
+
+        Ok(limits)
+    }
+
+    async fn ensure_account(tx: &mut sqlx::Transaction<'_, Postgres>, account_id: Uuid) -> Result<()> {
+        sqlx::query_scalar::<_, Uuid>("SELECT id FROM accounts WHERE id = $1")
+            .bind(account_id)
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| AppError::AccountNotFound {
+                account_id: account_id.to_string(),
+            })?;
+        Ok(())
+    }
+}
+
+/// Fails with `LimitExceeded` if a new transaction of `transaction_type` and
+/// `amount` would break one of the account's limits. Must run within `tx`
+/// after the account row is locked, so concurrent transactions on the
+/// account are counted rather than racing past the check. Fees don't count
+/// towards the debit totals.
+pub async fn check(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    account_id: Uuid,
+    transaction_type: &str,
+    amount: i64,
+) -> Result<()> {
+    // A zero or negative amount would slip under every cap.
+    if amount < 1 {
+        return Err(validation_error("amount", "range"));
+    }
+
+    let Some(limits) = find_limits(tx, account_id).await? else {
+        return Ok(());
+    };
+
+    check_transaction_amount(&limits, amount)?;
+
+    if !matches!(transaction_type, "debit" | "transfer" | "split") {
+        return Ok(());
+    }
+
+    if limits.daily_debit_limit.is_some() || limits.monthly_debit_limit.is_some() {
+        // Split legs are left out; their parent carries the total.
+        let (today, this_month, day_end, month_end) = sqlx::query_as::<_, (i64, i64, DateTime<Utc>, DateTime<Utc>)>(
+            r#"
+            SELECT
+                COALESCE(SUM(amount) FILTER (WHERE created_at >= date_trunc('day', NOW(), 'UTC')), 0)::BIGINT,
+                COALESCE(SUM(amount), 0)::BIGINT,
+                date_trunc('day', NOW(), 'UTC') + INTERVAL '1 day',
+                date_trunc('month', NOW(), 'UTC') + INTERVAL '1 month'
+            FROM transactions
+            WHERE account_id = $1
+            AND status = 'completed'
+            AND type IN ('debit', 'transfer', 'split')
+            AND parent_transaction_id IS NULL
+            AND created_at >= date_trunc('month', NOW(), 'UTC')
+            "#,
+        )
+        .bind(account_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        check_debit_totals(
+            &limits,
+            amount,
+            &DebitTotals {
+                today,
+                this_month,
+                day_end,
+                month_end,
+            },
+        )?;
+    }
+
+    if let Some(max) = limits.hourly_transfer_limit.filter(|_| transaction_type != "debit") {
+        let recent = sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            SELECT created_at
+            FROM transactions
+            WHERE account_id = $1
+            AND status = 'completed'
+            AND type IN ('transfer', 'split')
+            AND parent_transaction_id IS NULL
+            AND created_at > NOW() - INTERVAL '1 hour'
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(account_id)
+        .bind(i64::from(max))
+        .fetch_all(&mut **tx)
+        .await?;
+
+        check_hourly_transfers(max, &recent)?;
+    }
+
+    Ok(())
+}
+
+/// Debits, transfers and splits already completed in the current UTC day
+/// and month, and when each period ends.
+struct DebitTotals {
+    today: i64,
+    this_month: i64,
+    day_end: DateTime<Utc>,
+    month_end: DateTime<Utc>,
+}
+
+fn check_transaction_amount(limits: &AccountLimits, amount: i64) -> Result<()> {
+    match limits.max_transaction_amount.filter(|max| amount > *max) {
+        Some(max) => Err(AppError::LimitExceeded {
+            limit: MAX_TRANSACTION_AMOUNT.to_string(),
+            max,
+            resets_at: None,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn check_debit_totals(limits: &AccountLimits, amount: i64, totals: &DebitTotals) -> Result<()> {
+    for (limit, max, total, resets_at) in [
+        (DAILY_DEBIT_LIMIT, limits.daily_debit_limit, totals.today, totals.day_end),
+        (MONTHLY_DEBIT_LIMIT, limits.monthly_debit_limit, totals.this_month, totals.month_end),
+    ] {
+        if let Some(max) = max.filter(|max| total.saturating_add(amount) > *max) {
+            return Err(AppError::LimitExceeded {
+                limit: limit.to_string(),
+                max,
+                resets_at: Some(resets_at),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// `recent` holds the creation times of up to `max` transfers from the past
+/// hour, newest first. The limit is reached if there are `max` of them;
+/// another is allowed once the oldest of those drops out of the hour.
+fn check_hourly_transfers(max: i32, recent: &[DateTime<Utc>]) -> Result<()> {
+    let Some(&oldest) = usize::try_from(max).ok().and_then(|max| recent.get(max.checked_sub(1)?)) else {
+        return Ok(());
+    };
+    Err(AppError::LimitExceeded {
+        limit: HOURLY_TRANSFER_LIMIT.to_string(),
+        max: i64::from(max),
+        resets_at: Some(oldest + Duration::hours(1)),
+    })
+}
+
+async fn find_limits(tx: &mut sqlx::Transaction<'_, Postgres>, account_id: Uuid) -> Result<Option<AccountLimits>> {
+    let limits = sqlx::query_as::<_, AccountLimits>(
+        r#"
+        SELECT account_id, max_transaction_amount, daily_debit_limit, monthly_debit_limit, hourly_transfer_limit, updated_at
+        FROM account_limits
+        WHERE account_id = $1
+        "#,
+    )
+    .bind(account_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    Ok(limits)
+}
+
+fn no_limits(account_id: Uuid) -> AccountLimits {
+    AccountLimits {
+        account_id,
+        max_transaction_amount: None,
+        daily_debit_limit: None,
+        monthly_debit_limit: None,
+        hourly_transfer_limit: None,
+        updated_at: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc)
+    }
+
+    fn limits(max_transaction_amount: Option<i64>, daily: Option<i64>, monthly: Option<i64>) -> AccountLimits {
+        AccountLimits {
+            max_transaction_amount,
+            daily_debit_limit: daily,
+            monthly_debit_limit: monthly,
+            ..no_limits(Uuid::nil())
+        }
+    }
+
+    fn totals(today: i64, this_month: i64) -> DebitTotals {
+        DebitTotals {
+            today,
+            this_month,
+            day_end: at("2024-03-16T00:00:00Z"),
+            month_end: at("2024-04-01T00:00:00Z"),
+        }
+    }
+
+    fn exceeded(result: Result<()>) -> (String, i64, Option<DateTime<Utc>>) {
+        match result {
+            Err(AppError::LimitExceeded { limit, max, resets_at }) => (limit, max, resets_at),
+            other => panic!("expected LimitExceeded, got {:?}", other.map_err(|e| e.to_string())),
+        }
+    }
+
+    #[test]
+    fn caps_single_transactions() {
+        let limits = limits(Some(500), None, None);
+        assert!(check_transaction_amount(&limits, 500).is_ok());
+        assert_eq!(
+            exceeded(check_transaction_amount(&limits, 501)),
+            (MAX_TRANSACTION_AMOUNT.to_string(), 500, None)
+        );
+        assert!(check_transaction_amount(&no_limits(Uuid::nil()), i64::MAX).is_ok());
+    }
+
+    #[test]
+    fn caps_daily_debits() {
+        let limits = limits(None, Some(1_000), None);
+        assert!(check_debit_totals(&limits, 400, &totals(600, 600)).is_ok());
+        assert_eq!(
+            exceeded(check_debit_totals(&limits, 401, &totals(600, 600))),
+            (DAILY_DEBIT_LIMIT.to_string(), 1_000, Some(at("2024-03-16T00:00:00Z")))
+        );
+    }
+
+    #[test]
+    fn caps_monthly_debits() {
+        let limits = limits(None, Some(1_000), Some(5_000));
+        assert!(check_debit_totals(&limits, 1_000, &totals(0, 4_000)).is_ok());
+        assert_eq!(
+            exceeded(check_debit_totals(&limits, 1_000, &totals(0, 4_001))),
+            (MONTHLY_DEBIT_LIMIT.to_string(), 5_000, Some(at("2024-04-01T00:00:00Z")))
+        );
+        // The daily limit is reported first when both are hit.
+        assert_eq!(exceeded(check_debit_totals(&limits, 2_000, &totals(0, 4_500))).0, DAILY_DEBIT_LIMIT);
+    }
+
+    #[test]
+    fn debit_totals_saturate() {
+        let unreachable = limits(None, Some(i64::MAX), None);
+        assert!(check_debit_totals(&unreachable, 1, &totals(i64::MAX - 1, 0)).is_ok());
+        assert!(check_debit_totals(&unreachable, i64::MAX, &totals(i64::MAX, 0)).is_ok());
+        assert!(check_debit_totals(&limits(None, Some(i64::MAX - 1), None), 1, &totals(i64::MAX - 1, 0)).is_err());
+    }
+
+    #[test]
+    fn caps_transfers_per_hour() {
+        let recent = [at("2024-03-15T10:50:00Z"), at("2024-03-15T10:20:00Z"), at("2024-03-15T10:05:00Z")];
+
+        assert!(check_hourly_transfers(3, &[]).is_ok());
+        assert!(check_hourly_transfers(3, &recent[..2]).is_ok());
+        assert_eq!(
+            exceeded(check_hourly_transfers(3, &recent)),
+            (HOURLY_TRANSFER_LIMIT.to_string(), 3, Some(at("2024-03-15T11:05:00Z")))
+        );
+    }
+
+    #[test]
+    fn hourly_limit_resets_when_the_max_th_transfer_leaves_the_hour() {
+        // The query fetches at most `max` rows, so the oldest that counts is
+        // always the last one; with max 1 it's the only one.
+        let recent = [at("2024-03-15T10:50:00Z")];
+        assert_eq!(
+            exceeded(check_hourly_transfers(1, &recent)).2,
+            Some(at("2024-03-15T11:50:00Z"))
+        );
+
+        let recent = [at("2024-03-15T10:50:00Z"), at("2024-03-15T10:20:00Z")];
+        assert_eq!(
+            exceeded(check_hourly_transfers(2, &recent)).2,
+            Some(at("2024-03-15T11:20:00Z"))
+        );
+        assert!(check_hourly_transfers(3, &recent).is_ok());
+    }
+}
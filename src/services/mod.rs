@@ -1,7 +1,23 @@
 pub mod account;
+pub mod admin;
+pub mod audit;
+pub mod event;
+pub mod fee;
+pub mod job;
+pub mod limit;
+pub mod reconciliation;
+pub mod scheduled_transaction;
 pub mod transaction;
 pub mod webhook;
 
 pub use account::AccountService;
+pub use admin::AdminService;
+pub use audit::AuditService;
+pub use event::EventService;
+pub use fee::FeeService;
+pub use job::JobService;
+pub use limit::LimitService;
+pub use reconciliation::ReconciliationService;
+pub use scheduled_transaction::ScheduledTransactionService;
 pub use transaction::TransactionService;
 pub use webhook::WebhookService;
\ No newline at end of file
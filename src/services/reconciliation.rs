@@ -0,0 +1,229 @@
+use crate::{
+    config::Config,
+    database::Database,
+    error::{AppError, Result},
+    models::{
+        AccountStatus, AuditContext, BalanceDiscrepancy, ChangeAccountStatusRequest, ReconciliationRun,
+        ReconciliationRunResponse,
+    },
+    services::AccountService,
+};
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const TRIGGER_SCHEDULED: &str = "scheduled";
+pub const TRIGGER_MANUAL: &str = "manual";
+
+const RUNS_PAGE_SIZE: i64 = 50;
+
+#[derive(Clone)]
+pub struct ReconciliationService {
+    database: Arc<Database>,
+    account_service: AccountService,
+    freeze_on_drift: bool,
+}
+
+impl ReconciliationService {
+    pub fn new(database: Arc<Database>, account_service: AccountService, config: &Config) -> Self {
+        Self {
+            database,
+            account_service,
+            freeze_on_drift: config.reconciliation_freeze_on_drift,
+        }
+    }
+
+    /// Recomputes every account's balance from its completed transactions and
+    /// records the accounts whose stored balance differs.
+    pub async fn run(&self, trigger: &str) -> Result<ReconciliationRunResponse> {
+        let started_at = Utc::now();
+        let mut tx = self.database.begin_transaction().await?;
+
+        // One snapshot for the count and the comparison, so transactions
+        // committing mid-run can't show up as drift.
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+            .execute(&mut *tx)
+            .await?;
+
+        let acquired = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_xact_lock(hashtext('reconciliation'))")
+            .fetch_one(&mut *tx)
+            .await?;
+        if !acquired {
+            return Err(AppError::ReconciliationInProgress);
+        }
+
+        let accounts_checked = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM accounts")
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let mismatches = sqlx::query_as::<_, (Uuid, i64, i64, AccountStatus)>(
+            r#"
+            SELECT a.id, a.balance, COALESCE(SUM(l.delta), 0)::BIGINT AS expected, a.status
+            FROM accounts a
+            LEFT JOIN account_ledger l ON l.account_id = a.id
+            GROUP BY a.id
+            HAVING a.balance <> COALESCE(SUM(l.delta), 0)
+            ORDER BY a.id
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let total_abs_drift: i64 = mismatches
+            .iter()
+            .map(|(_, stored, expected, _)| (stored - expected).abs())
+            .sum();
+
+        let run = sqlx::query_as::<_, ReconciliationRun>(
+            r#"
+            INSERT INTO reconciliation_runs (trigger, accounts_checked, discrepancies, total_abs_drift, started_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, trigger, accounts_checked, discrepancies, total_abs_drift, started_at, finished_at
+            "#,
+        )
+        .bind(trigger)
+        .bind(accounts_checked)
+        .bind(mismatches.len() as i64)
+        .bind(total_abs_drift)
+        .bind(started_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut discrepancies = Vec::with_capacity(mismatches.len());
+        for (account_id, stored_balance, expected_balance, _) in &mismatches {
+            let discrepancy = sqlx::query_as::<_, BalanceDiscrepancy>(
+                r#"
+                INSERT INTO balance_discrepancies (run_id, account_id, stored_balance, expected_balance, drift)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id, run_id, account_id, stored_balance, expected_balance, drift, account_frozen, created_at
+                "#,
+            )
+            .bind(run.id)
+            .bind(account_id)
+            .bind(stored_balance)
+            .bind(expected_balance)
+            .bind(stored_balance - expected_balance)
+            .fetch_one(&mut *tx)
+            .await?;
+            discrepancies.push(discrepancy);
+        }
+
+        tx.commit().await?;
+
+        for discrepancy in &discrepancies {
+            tracing::warn!(
+                run_id = %run.id,
+                account_id = %discrepancy.account_id,
+                stored_balance = discrepancy.stored_balance,
+                expected_balance = discrepancy.expected_balance,
+                drift = discrepancy.drift,
+                "Balance drift detected"
+            );
+        }
+
+        if self.freeze_on_drift {
+            for (discrepancy, (_, _, _, status)) in discrepancies.iter_mut().zip(&mismatches) {
+                if *status == AccountStatus::Active {
+                    discrepancy.account_frozen = self.freeze_account(run.id, discrepancy).await;
+                }
+            }
+        }
+
+        crate::metrics::record_reconciliation(run.discrepancies as u64, run.total_abs_drift as u64);
+
+        tracing::info!(
+            run_id = %run.id,
+            accounts_checked = run.accounts_checked,
+            discrepancies = run.discrepancies,
+            "Reconciliation run completed"
+        );
+
+        Ok(ReconciliationRunResponse { run, discrepancies })
+    }
+
+    /// Freezes an account with drift; returns whether it was frozen. Failures
+    /// are logged rather than failing the run, whose results are already stored.
+    async fn freeze_account(&self, run_id: Uuid, discrepancy: &BalanceDiscrepancy) -> bool {
+        let frozen = self
+            .account_service
+            .change_status(
+                discrepancy.account_id,
+                ChangeAccountStatusRequest {
+                    status: AccountStatus::Frozen,
+                    reason: format!(
+                        "Balance drift of {} detected by reconciliation run {}",
+                        discrepancy.drift, run_id
+                    ),
+                },
+                &AuditContext::system(),
+            )
+            .await;
+
+        if let Err(e) = frozen {
+            tracing::error!(account_id = %discrepancy.account_id, "Failed to freeze account with balance drift: {}", e);
+            return false;
+        }
+
+        if let Err(e) = sqlx::query(
+            r#"
+            UPDATE balance_discrepancies
+            SET account_frozen = true
+            WHERE id = $1
+            "#,
+        )
+        .bind(discrepancy.id)
+        .execute(self.database.pool())
+        .await
+        {
+            tracing::error!(discrepancy_id = %discrepancy.id, "Failed to record account freeze: {}", e);
+        }
+
+        true
+    }
+
+    pub async fn list_runs(&self) -> Result<Vec<ReconciliationRun>> {
+        let runs = sqlx::query_as::<_, ReconciliationRun>(
+            r#"
+            SELECT id, trigger, accounts_checked, discrepancies, total_abs_drift, started_at, finished_at
+            FROM reconciliation_runs
+            ORDER BY started_at DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(RUNS_PAGE_SIZE)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        Ok(runs)
+    }
+
+    pub async fn get_run(&self, run_id: Uuid) -> Result<ReconciliationRunResponse> {
+        let run = sqlx::query_as::<_, ReconciliationRun>(
+            r#"
+            SELECT id, trigger, accounts_checked, discrepancies, total_abs_drift, started_at, finished_at
+            FROM reconciliation_runs
+            WHERE id = $1
+            "#,
+        )
+        .bind(run_id)
+        .fetch_optional(self.database.pool())
+        .await?
+        .ok_or_else(|| AppError::ReconciliationRunNotFound {
+            run_id: run_id.to_string(),
+        })?;
+
+        let discrepancies = sqlx::query_as::<_, BalanceDiscrepancy>(
+            r#"
+            SELECT id, run_id, account_id, stored_balance, expected_balance, drift, account_frozen, created_at
+            FROM balance_discrepancies
+            WHERE run_id = $1
+            ORDER BY account_id
+            "#,
+        )
+        .bind(run_id)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        Ok(ReconciliationRunResponse { run, discrepancies })
+    }
+}
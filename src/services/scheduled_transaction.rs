@@ -0,0 +1,800 @@
+use crate::{
+    database::Database,
+    error::{AppError, Result},
+    events,
+    models::{
+        Actor, AuditContext, BatchItemStatus, CreateScheduledTransactionRequest, CreateTransactionRequest,
+        ListScheduledTransactionsQuery, ListScheduledTransactionsResponse, ScheduleFrequency, ScheduleStatus,
+        ScheduledTransaction, ScheduledTransactionResponse, ScheduledTransactionRun, Transaction,
+    },
+    services::{
+        audit::{self, AuditRecord},
+        transaction::{self, publish_transaction, validation_error},
+        TransactionService, WebhookService,
+    },
+};
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use serde_json::json;
+use sqlx::Postgres;
+use std::{str::FromStr, sync::Arc};
+use uuid::Uuid;
+use validator::Validate;
+
+pub const RUN_EXECUTED: &str = "executed";
+/// Failed for insufficient funds; the occurrence will be tried again.
+pub const RUN_RETRYING: &str = "retrying";
+pub const RUN_FAILED: &str = "failed";
+pub const RUN_SKIPPED: &str = "skipped";
+
+const DEFAULT_EVERY: i32 = 1;
+const DEFAULT_MAX_RETRIES: i32 = 3;
+const DEFAULT_RETRY_INTERVAL_MINUTES: i32 = 60;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const RECENT_RUNS: i64 = 20;
+
+#[derive(Clone)]
+pub struct ScheduledTransactionService {
+    database: Arc<Database>,
+    transaction_service: TransactionService,
+    webhook_service: WebhookService,
+}
+
+/// A change a merchant can make to their schedule.
+#[derive(Clone, Copy)]
+enum ScheduleAction {
+    Pause,
+    Resume,
+    Skip,
+    Cancel,
+}
+
+impl ScheduleAction {
+    fn name(self) -> &'static str {
+        match self {
+            ScheduleAction::Pause => "pause",
+            ScheduleAction::Resume => "resume",
+            ScheduleAction::Skip => "skip",
+            ScheduleAction::Cancel => "cancel",
+        }
+    }
+
+    fn audit_action(self) -> &'static str {
+        match self {
+            ScheduleAction::Pause => audit::SCHEDULED_TRANSACTION_PAUSED,
+            ScheduleAction::Resume => audit::SCHEDULED_TRANSACTION_RESUMED,
+            ScheduleAction::Skip => audit::SCHEDULED_TRANSACTION_SKIPPED,
+            ScheduleAction::Cancel => audit::SCHEDULED_TRANSACTION_CANCELLED,
+        }
+    }
+}
+
+impl ScheduledTransactionService {
+    pub fn new(
+        database: Arc<Database>,
+        transaction_service: TransactionService,
+        webhook_service: WebhookService,
+    ) -> Self {
+        Self {
+            database,
+            transaction_service,
+            webhook_service,
+        }
+    }
+
+    pub async fn create(
+        &self,
+        account_id: Uuid,
+        req: CreateScheduledTransactionRequest,
+        ctx: &AuditContext,
+    ) -> Result<ScheduledTransaction> {
+        req.validate()?;
+        transaction::validate_request(&CreateTransactionRequest {
+            idempotency_key: None,
+            r#type: req.r#type.clone(),
+            amount: req.amount,
+            description: req.description.clone(),
+            counterparty_account_id: req.counterparty_account_id,
+        })?;
+
+        let cron = match req.cron_expression.as_deref() {
+            Some(expression) => Some(
+                cron::Schedule::from_str(expression)
+                    .map_err(|_| validation_error("cron_expression", "invalid_cron_expression"))?,
+            ),
+            None => None,
+        };
+
+        let now = Utc::now();
+        let start_at = req.start_at.unwrap_or(now);
+        if req.start_at.is_some() && start_at <= now {
+            return Err(validation_error("start_at", "must_be_in_the_future"));
+        }
+
+        let every = req.every.unwrap_or(DEFAULT_EVERY);
+        let next_run_at = occurrence_after(req.frequency, every, cron.as_ref(), start_at, None)
+            .filter(|first| req.end_at.is_none_or(|end_at| *first <= end_at))
+            .ok_or_else(|| validation_error("end_at", "no_occurrences_before_end_at"))?;
+
+        let api_key_id = match ctx.actor {
+            Actor::ApiKey(key_id) => Some(key_id),
+            _ => None,
+        };
+
+        let mut tx = self.database.begin_transaction().await?;
+
+        let schedule = sqlx::query_as::<_, ScheduledTransaction>(
+            r#"
+            INSERT INTO scheduled_transactions (
+                account_id, type, amount, description, counterparty_account_id, frequency, every,
+                cron_expression, start_at, end_at, max_occurrences, next_run_at, max_retries,
+                retry_interval_minutes, api_key_id, request_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            RETURNING id, account_id, type, amount, description, counterparty_account_id, frequency, every,
+                cron_expression, start_at, end_at, max_occurrences, status, next_run_at, retry_at, retry_attempt,
+                max_retries, retry_interval_minutes, occurrences, last_run_at, api_key_id, request_id,
+                created_at, updated_at
+            "#,
+        )
+        .bind(account_id)
+        .bind(&req.r#type)
+        .bind(req.amount)
+        .bind(&req.description)
+        .bind(req.counterparty_account_id)
+        .bind(req.frequency)
+        .bind(every)
+        .bind(&req.cron_expression)
+        .bind(start_at)
+        .bind(req.end_at)
+        .bind(req.max_occurrences)
+        .bind(next_run_at)
+        .bind(req.max_retries.unwrap_or(DEFAULT_MAX_RETRIES))
+        .bind(req.retry_interval_minutes.unwrap_or(DEFAULT_RETRY_INTERVAL_MINUTES))
+        .bind(api_key_id)
+        .bind(&ctx.request_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::SCHEDULED_TRANSACTION_CREATED,
+                resource_type: "scheduled_transaction",
+                resource_id: schedule.id,
+                before: None,
+                after: audit::snapshot(&schedule),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(
+            schedule_id = %schedule.id,
+            account_id = %account_id,
+            next_run_at = %next_run_at,
+            "Scheduled transaction created"
+        );
+
+        Ok(schedule)
+    }
+
+    pub async fn list(
+        &self,
+        account_id: Uuid,
+        query: ListScheduledTransactionsQuery,
+    ) -> Result<ListScheduledTransactionsResponse> {
+        query.validate()?;
+
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        let offset = query.offset.unwrap_or(0);
+
+        let scheduled_transactions = sqlx::query_as::<_, ScheduledTransaction>(
+            r#"
+            SELECT id, account_id, type, amount, description, counterparty_account_id, frequency, every,
+                cron_expression, start_at, end_at, max_occurrences, status, next_run_at, retry_at, retry_attempt,
+                max_retries, retry_interval_minutes, occurrences, last_run_at, api_key_id, request_id,
+                created_at, updated_at
+            FROM scheduled_transactions
+            WHERE account_id = $1 AND ($2::schedule_status IS NULL OR status = $2)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3 OFFSET $4
+            "#,
+        )
+        .bind(account_id)
+        .bind(query.status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let total = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM scheduled_transactions
+            WHERE account_id = $1 AND ($2::schedule_status IS NULL OR status = $2)
+            "#,
+        )
+        .bind(account_id)
+        .bind(query.status)
+        .fetch_one(self.database.pool())
+        .await?;
+
+        Ok(ListScheduledTransactionsResponse {
+            scheduled_transactions,
+            total,
+            limit,
+            offset,
+        })
+    }
+
+    pub async fn get(&self, account_id: Uuid, schedule_id: Uuid) -> Result<ScheduledTransactionResponse> {
+        let scheduled_transaction = sqlx::query_as::<_, ScheduledTransaction>(
+            r#"
+            SELECT id, account_id, type, amount, description, counterparty_account_id, frequency, every,
+                cron_expression, start_at, end_at, max_occurrences, status, next_run_at, retry_at, retry_attempt,
+                max_retries, retry_interval_minutes, occurrences, last_run_at, api_key_id, request_id,
+                created_at, updated_at
+            FROM scheduled_transactions
+            WHERE id = $1 AND account_id = $2
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(account_id)
+        .fetch_optional(self.database.pool())
+        .await?
+        .ok_or_else(|| AppError::ScheduledTransactionNotFound {
+            schedule_id: schedule_id.to_string(),
+        })?;
+
+        let runs = sqlx::query_as::<_, ScheduledTransactionRun>(
+            r#"
+            SELECT id, schedule_id, scheduled_for, attempt, status, transaction_id, error_code, error, created_at
+            FROM scheduled_transaction_runs
+            WHERE schedule_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(RECENT_RUNS)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        Ok(ScheduledTransactionResponse {
+            scheduled_transaction,
+            runs,
+        })
+    }
+
+    pub async fn pause(&self, account_id: Uuid, schedule_id: Uuid, ctx: &AuditContext) -> Result<ScheduledTransaction> {
+        self.apply_action(account_id, schedule_id, ScheduleAction::Pause, ctx).await
+    }
+
+    /// Occurrences that came due while paused are not made up; a recurring
+    /// schedule continues from its next occurrence after now.
+    pub async fn resume(&self, account_id: Uuid, schedule_id: Uuid, ctx: &AuditContext) -> Result<ScheduledTransaction> {
+        self.apply_action(account_id, schedule_id, ScheduleAction::Resume, ctx).await
+    }
+
+    /// Skips the occurrence currently or next due, including one waiting for a retry.
+    pub async fn skip(&self, account_id: Uuid, schedule_id: Uuid, ctx: &AuditContext) -> Result<ScheduledTransaction> {
+        self.apply_action(account_id, schedule_id, ScheduleAction::Skip, ctx).await
+    }
+
+    pub async fn cancel(&self, account_id: Uuid, schedule_id: Uuid, ctx: &AuditContext) -> Result<ScheduledTransaction> {
+        self.apply_action(account_id, schedule_id, ScheduleAction::Cancel, ctx).await
+    }
+
+    async fn apply_action(
+        &self,
+        account_id: Uuid,
+        schedule_id: Uuid,
+        action: ScheduleAction,
+        ctx: &AuditContext,
+    ) -> Result<ScheduledTransaction> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        // Locked so the action can't interleave with an execution in progress.
+        let before = sqlx::query_as::<_, ScheduledTransaction>(
+            r#"
+            SELECT id, account_id, type, amount, description, counterparty_account_id, frequency, every,
+                cron_expression, start_at, end_at, max_occurrences, status, next_run_at, retry_at, retry_attempt,
+                max_retries, retry_interval_minutes, occurrences, last_run_at, api_key_id, request_id,
+                created_at, updated_at
+            FROM scheduled_transactions
+            WHERE id = $1 AND account_id = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(account_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::ScheduledTransactionNotFound {
+            schedule_id: schedule_id.to_string(),
+        })?;
+
+        let now = Utc::now();
+        let mut schedule = before.clone();
+        match (action, before.status) {
+            (ScheduleAction::Pause, ScheduleStatus::Active) => schedule.status = ScheduleStatus::Paused,
+            (ScheduleAction::Resume, ScheduleStatus::Paused) => {
+                schedule.status = ScheduleStatus::Active;
+                if schedule.frequency != ScheduleFrequency::Once
+                    && schedule.next_run_at.is_some_and(|next_run_at| next_run_at < now)
+                {
+                    schedule.retry_at = None;
+                    schedule.retry_attempt = 0;
+                    schedule.next_run_at = next_occurrence(&schedule, now);
+                    if schedule.next_run_at.is_none() {
+                        schedule.status = ScheduleStatus::Completed;
+                    }
+                }
+            }
+            (ScheduleAction::Skip, ScheduleStatus::Active | ScheduleStatus::Paused) => {
+                if let Some(scheduled_for) = schedule.next_run_at {
+                    Self::record_run(&mut tx, schedule.id, scheduled_for, 0, RUN_SKIPPED, None, None).await?;
+                }
+                advance(&mut schedule, now, ScheduleStatus::Completed);
+            }
+            (ScheduleAction::Cancel, ScheduleStatus::Active | ScheduleStatus::Paused) => {
+                schedule.status = ScheduleStatus::Cancelled;
+                schedule.next_run_at = None;
+                schedule.retry_at = None;
+            }
+            (_, status) => {
+                return Err(AppError::InvalidScheduleAction {
+                    action: action.name().to_string(),
+                    status: status.to_string(),
+                });
+            }
+        }
+
+        let schedule = Self::save(&mut tx, &schedule).await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: action.audit_action(),
+                resource_type: "scheduled_transaction",
+                resource_id: schedule.id,
+                before: audit::snapshot(&before),
+                after: audit::snapshot(&schedule),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(
+            schedule_id = %schedule.id,
+            action = action.name(),
+            status = %schedule.status,
+            actor = %ctx.actor,
+            "Scheduled transaction updated"
+        );
+
+        Ok(schedule)
+    }
+
+    /// Executes every due schedule, one at a time. Several instances can run
+    /// this side by side; each due occurrence is claimed by one of them.
+    pub async fn run_due(&self) -> Result<()> {
+        while self.run_next().await? {}
+        Ok(())
+    }
+
+    /// Executes the most overdue schedule, if any is due.
+    async fn run_next(&self) -> Result<bool> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        // The lock is held until the run is recorded. Should the process die
+        // in between, the next attempt reuses the occurrence's idempotency
+        // key and picks up the transaction already created.
+        let schedule = sqlx::query_as::<_, ScheduledTransaction>(
+            r#"
+            SELECT id, account_id, type, amount, description, counterparty_account_id, frequency, every,
+                cron_expression, start_at, end_at, max_occurrences, status, next_run_at, retry_at, retry_attempt,
+                max_retries, retry_interval_minutes, occurrences, last_run_at, api_key_id, request_id,
+                created_at, updated_at
+            FROM scheduled_transactions
+            WHERE status = 'active' AND COALESCE(retry_at, next_run_at) <= NOW()
+            ORDER BY COALESCE(retry_at, next_run_at)
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(schedule) = schedule else {
+            return Ok(false);
+        };
+
+        let now = Utc::now();
+        // Always set for an active schedule.
+        let scheduled_for = schedule.next_run_at.unwrap_or(now);
+        let attempt = schedule.retry_attempt + 1;
+
+        let req = CreateTransactionRequest {
+            idempotency_key: Some(format!("schedule:{}:{}", schedule.id, scheduled_for.timestamp())),
+            r#type: schedule.r#type.clone(),
+            amount: schedule.amount,
+            description: schedule.description.clone(),
+            counterparty_account_id: schedule.counterparty_account_id,
+        };
+        let ctx = AuditContext {
+            actor: schedule.api_key_id.map(Actor::ApiKey).unwrap_or(Actor::System),
+            request_id: schedule.request_id.clone(),
+            ip: None,
+        };
+
+        let outcome = self
+            .transaction_service
+            .create_or_get(schedule.account_id, &req, &ctx)
+            .await;
+
+        let mut updated = schedule.clone();
+        updated.last_run_at = Some(now);
+        let (run_status, executed, error) = match outcome {
+            Ok(executed) => {
+                advance(&mut updated, now, ScheduleStatus::Completed);
+                (RUN_EXECUTED, Some(executed), None)
+            }
+            // Tried again on the next tick, not counted against the schedule.
+            Err(e @ AppError::Database(_)) => return Err(e),
+            Err(e @ AppError::InsufficientFunds { .. }) if schedule.retry_attempt < schedule.max_retries => {
+                updated.retry_attempt = attempt;
+                updated.retry_at = Some(now + Duration::minutes(i64::from(schedule.retry_interval_minutes)));
+                (RUN_RETRYING, None, Some(e))
+            }
+            Err(e) => {
+                advance(&mut updated, now, ScheduleStatus::Failed);
+                (RUN_FAILED, None, Some(e))
+            }
+        };
+
+        let error = error.map(|e| {
+            let (status, message) = e.status_and_message();
+            (i32::from(status.as_u16()), message)
+        });
+
+        Self::record_run(
+            &mut tx,
+            schedule.id,
+            scheduled_for,
+            attempt,
+            run_status,
+            executed.as_ref().map(|(_, transaction)| transaction.id),
+            error.as_ref(),
+        )
+        .await?;
+        let updated = Self::save(&mut tx, &updated).await?;
+
+        tx.commit().await?;
+
+        tracing::info!(
+            schedule_id = %schedule.id,
+            account_id = %schedule.account_id,
+            scheduled_for = %scheduled_for,
+            attempt,
+            run_status,
+            "Scheduled transaction run"
+        );
+
+        match (executed, error) {
+            (Some((status, transaction)), _) => {
+                if status == BatchItemStatus::Created {
+                    publish_transaction(&self.webhook_service, &transaction).await;
+                }
+                self.notify(
+                    &updated,
+                    events::SCHEDULED_TRANSACTION_EXECUTED,
+                    Some(&transaction),
+                    json!({
+                        "scheduled_transaction_id": updated.id,
+                        "scheduled_for": scheduled_for,
+                        "attempt": attempt,
+                        "next_run_at": updated.next_run_at,
+                    }),
+                )
+                .await;
+            }
+            (None, Some((code, message))) => {
+                self.notify(
+                    &updated,
+                    events::SCHEDULED_TRANSACTION_FAILED,
+                    None,
+                    json!({
+                        "scheduled_transaction_id": updated.id,
+                        "scheduled_for": scheduled_for,
+                        "attempt": attempt,
+                        "error": { "code": code, "error": message },
+                        "retry_at": updated.retry_at,
+                        "next_run_at": updated.next_run_at,
+                    }),
+                )
+                .await;
+            }
+            (None, None) => {}
+        }
+
+        Ok(true)
+    }
+
+    async fn notify(
+        &self,
+        schedule: &ScheduledTransaction,
+        event: &'static str,
+        transaction: Option<&Transaction>,
+        data: serde_json::Value,
+    ) {
+        if let Err(e) = self
+            .webhook_service
+            .deliver_event(schedule.account_id, event, transaction, data)
+            .await
+        {
+            tracing::error!(schedule_id = %schedule.id, event, "Failed to enqueue webhooks: {}", e);
+        }
+    }
+
+    async fn record_run(
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        schedule_id: Uuid,
+        scheduled_for: DateTime<Utc>,
+        attempt: i32,
+        status: &str,
+        transaction_id: Option<Uuid>,
+        error: Option<&(i32, String)>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO scheduled_transaction_runs (schedule_id, scheduled_for, attempt, status, transaction_id, error_code, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(scheduled_for)
+        .bind(attempt)
+        .bind(status)
+        .bind(transaction_id)
+        .bind(error.map(|(code, _)| *code))
+        .bind(error.map(|(_, message)| message))
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Writes the fields that change as a schedule runs.
+    async fn save(
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        schedule: &ScheduledTransaction,
+    ) -> Result<ScheduledTransaction> {
+        let schedule = sqlx::query_as::<_, ScheduledTransaction>(
+            r#"
+            UPDATE scheduled_transactions
+            SET status = $2, next_run_at = $3, retry_at = $4, retry_attempt = $5, occurrences = $6,
+                last_run_at = $7, updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, account_id, type, amount, description, counterparty_account_id, frequency, every,
+                cron_expression, start_at, end_at, max_occurrences, status, next_run_at, retry_at, retry_attempt,
+                max_retries, retry_interval_minutes, occurrences, last_run_at, api_key_id, request_id,
+                created_at, updated_at
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(schedule.status)
+        .bind(schedule.next_run_at)
+        .bind(schedule.retry_at)
+        .bind(schedule.retry_attempt)
+        .bind(schedule.occurrences)
+        .bind(schedule.last_run_at)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(schedule)
+    }
+}
+
+/// Moves a schedule past its current occurrence to the next one after `now`,
+/// or ends it with `ended_status` when there are none left. Occurrences
+/// missed in the meantime, e.g. while a retry was pending, are not made up.
+fn advance(schedule: &mut ScheduledTransaction, now: DateTime<Utc>, ended_status: ScheduleStatus) {
+    schedule.occurrences += 1;
+    schedule.retry_at = None;
+    schedule.retry_attempt = 0;
+
+    let after = schedule.next_run_at.map_or(now, |next_run_at| next_run_at.max(now));
+    schedule.next_run_at = if schedule.max_occurrences.is_some_and(|max| schedule.occurrences >= max) {
+        None
+    } else {
+        next_occurrence(schedule, after)
+    };
+
+    if schedule.next_run_at.is_none() {
+        schedule.status = ended_status;
+    }
+}
+
+/// The schedule's first occurrence after `after` that falls before its `end_at`.
+fn next_occurrence(schedule: &ScheduledTransaction, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let cron = schedule
+        .cron_expression
+        .as_deref()
+        .and_then(|expression| cron::Schedule::from_str(expression).ok());
+
+    occurrence_after(schedule.frequency, schedule.every, cron.as_ref(), schedule.start_at, Some(after))
+        .filter(|next| schedule.end_at.is_none_or(|end_at| *next <= end_at))
+}
+
+/// Occurrences of a rule start at `start_at`. Returns the first one strictly
+/// after `after`, or the very first when `after` is `None`. Monthly
+/// occurrences keep `start_at`'s day, or use the month's last day when it is
+/// shorter.
+fn occurrence_after(
+    frequency: ScheduleFrequency,
+    every: i32,
+    cron: Option<&cron::Schedule>,
+    start_at: DateTime<Utc>,
+    after: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    let Some(after) = after.filter(|after| *after >= start_at) else {
+        return match frequency {
+            ScheduleFrequency::Cron => cron?
+                .after(&(start_at - Duration::seconds(1)))
+                .find(|occurrence| *occurrence >= start_at),
+            _ => Some(start_at),
+        };
+    };
+
+    let every = i64::from(every.max(1));
+    let step = |period: Duration| {
+        let periods = (after - start_at).num_seconds() / period.num_seconds() + 1;
+        Some(start_at + Duration::seconds(periods * period.num_seconds()))
+    };
+
+    match frequency {
+        ScheduleFrequency::Once => None,
+        ScheduleFrequency::Daily => step(Duration::days(every)),
+        ScheduleFrequency::Weekly => step(Duration::weeks(every)),
+        ScheduleFrequency::Monthly => {
+            let months_elapsed = i64::from(after.year() - start_at.year()) * 12 + i64::from(after.month())
+                - i64::from(start_at.month());
+            // Start one period early, since a shorter month may not have reached `after` yet.
+            let mut periods = (months_elapsed / every - 1).max(0);
+            loop {
+                let months = u32::try_from(periods * every).ok()?;
+                let occurrence = start_at.checked_add_months(Months::new(months))?;
+                if occurrence > after {
+                    return Some(occurrence);
+                }
+                periods += 1;
+            }
+        }
+        ScheduleFrequency::Cron => cron?.after(&after).next(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(frequency: ScheduleFrequency, every: i32, start_at: &str, after: Option<&str>) -> Option<DateTime<Utc>> {
+        occurrence_after(frequency, every, None, at(start_at), after.map(at))
+    }
+
+    #[test]
+    fn first_occurrence_is_the_start() {
+        for frequency in [
+            ScheduleFrequency::Once,
+            ScheduleFrequency::Daily,
+            ScheduleFrequency::Weekly,
+            ScheduleFrequency::Monthly,
+        ] {
+            assert_eq!(next(frequency, 1, "2024-01-10T09:00:00Z", None), Some(at("2024-01-10T09:00:00Z")));
+            // `after` before the start doesn't skip it
+            assert_eq!(
+                next(frequency, 1, "2024-01-10T09:00:00Z", Some("2024-01-01T00:00:00Z")),
+                Some(at("2024-01-10T09:00:00Z"))
+            );
+        }
+    }
+
+    #[test]
+    fn once_never_repeats() {
+        assert_eq!(next(ScheduleFrequency::Once, 1, "2024-01-10T09:00:00Z", Some("2024-01-10T09:00:00Z")), None);
+    }
+
+    #[test]
+    fn daily_and_weekly_step_from_the_start() {
+        let start = "2024-01-10T09:00:00Z";
+        assert_eq!(
+            next(ScheduleFrequency::Daily, 1, start, Some("2024-01-10T09:00:00Z")),
+            Some(at("2024-01-11T09:00:00Z"))
+        );
+        // Strictly after: an occurrence at `after` itself doesn't count.
+        assert_eq!(
+            next(ScheduleFrequency::Daily, 3, start, Some("2024-01-13T09:00:00Z")),
+            Some(at("2024-01-16T09:00:00Z"))
+        );
+        // A late run doesn't shift later occurrences.
+        assert_eq!(
+            next(ScheduleFrequency::Daily, 1, start, Some("2024-01-15T17:30:00Z")),
+            Some(at("2024-01-16T09:00:00Z"))
+        );
+        assert_eq!(
+            next(ScheduleFrequency::Weekly, 2, start, Some("2024-01-10T09:00:00Z")),
+            Some(at("2024-01-24T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn every_below_one_is_treated_as_one() {
+        assert_eq!(
+            next(ScheduleFrequency::Daily, 0, "2024-01-10T09:00:00Z", Some("2024-01-10T09:00:00Z")),
+            Some(at("2024-01-11T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn monthly_keeps_the_start_day_and_clamps_short_months() {
+        let start = "2024-01-31T12:00:00Z";
+        let mut after = at(start);
+        let mut occurrences = Vec::new();
+        for _ in 0..4 {
+            after = occurrence_after(ScheduleFrequency::Monthly, 1, None, at(start), Some(after)).unwrap();
+            occurrences.push(after.to_rfc3339());
+        }
+        assert_eq!(
+            occurrences,
+            [
+                "2024-02-29T12:00:00+00:00",
+                "2024-03-31T12:00:00+00:00",
+                "2024-04-30T12:00:00+00:00",
+                "2024-05-31T12:00:00+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_steps_by_every_across_years() {
+        assert_eq!(
+            next(ScheduleFrequency::Monthly, 3, "2024-11-15T00:00:00Z", Some("2025-01-20T00:00:00Z")),
+            Some(at("2025-02-15T00:00:00Z"))
+        );
+        assert_eq!(
+            next(ScheduleFrequency::Monthly, 12, "2024-02-29T00:00:00Z", Some("2024-02-29T00:00:00Z")),
+            Some(at("2025-02-28T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn cron_follows_the_expression() {
+        // 09:00 every Monday
+        let cron = cron::Schedule::from_str("0 0 9 * * Mon *").unwrap();
+        let start_at = at("2024-01-10T00:00:00Z");
+
+        assert_eq!(
+            occurrence_after(ScheduleFrequency::Cron, 1, Some(&cron), start_at, None),
+            Some(at("2024-01-15T09:00:00Z"))
+        );
+        assert_eq!(
+            occurrence_after(ScheduleFrequency::Cron, 1, Some(&cron), start_at, Some(at("2024-01-15T09:00:00Z"))),
+            Some(at("2024-01-22T09:00:00Z"))
+        );
+        // The start itself counts when it matches.
+        assert_eq!(
+            occurrence_after(ScheduleFrequency::Cron, 1, Some(&cron), at("2024-01-15T09:00:00Z"), None),
+            Some(at("2024-01-15T09:00:00Z"))
+        );
+        assert_eq!(occurrence_after(ScheduleFrequency::Cron, 1, None, start_at, None), None);
+    }
+}
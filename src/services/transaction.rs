@@ -1,27 +1,60 @@
 use crate::{
+    config::Config,
     database::Database,
     error::{AppError, Result},
+    events,
     models::{
-        CreateTransactionRequest, Transaction, TransactionResponse, TransactionType,
+        AccountStatus, AuditContext, BatchItemError, BatchItemResult, BatchItemStatus,
+        BatchTransactionResponse, CreateSplitTransactionRequest, CreateTransactionRequest, Transaction,
+        TransactionResponse,
+        TransactionType,
     },
+    services::{
+        audit::{self, AuditRecord},
+        event, fee, limit, webhook, WebhookService,
+    },
+};
+use serde_json::json;
+use sqlx::Postgres;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
 };
-use std::sync::Arc;
 use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 #[derive(Clone)]
 pub struct TransactionService {
     database: Arc<Database>,
+    batch_max_items: usize,
+    /// Where fees are paid; no fees are charged without one.
+    revenue_account_id: Option<Uuid>,
+}
+
+/// A transaction written inside a database transaction that has not been
+/// committed yet, with the initiating account's balance either side of it
+/// and any fees charged on it.
+struct AppliedTransaction {
+    transaction: Transaction,
+    fees: Vec<Transaction>,
+    balance_before: i64,
+    balance_after: i64,
 }
 
 impl TransactionService {
-    pub fn new(database: Arc<Database>) -> Self {
-        Self { database }
+    pub fn new(database: Arc<Database>, config: &Config) -> Self {
+        Self {
+            database,
+            batch_max_items: config.batch_max_items,
+            revenue_account_id: config.platform_revenue_account_id,
+        }
     }
 
     pub async fn create_transaction(
         &self,
         account_id: Uuid,
         req: CreateTransactionRequest,
+        ctx: &AuditContext,
      ) -> Result<TransactionResponse> {
         let span = tracing::info_span!(
             "create_transaction",
@@ -34,58 +67,565 @@ impl TransactionService {
 
         tracing::info!("Creating transaction");
 
-        let transaction_type = match req.r#type.as_str() {
-            "credit" => TransactionType::Credit,
-            "debit" => TransactionType::Debit,
-            "transfer" => TransactionType::Transfer,
-            _ => return Err(AppError::Internal(anyhow::anyhow!("Invalid transaction type"))),
-        };
+        let transaction_type = parse_transaction_type(&req.r#type)?;
+
+        if let Some(ref key) = req.idempotency_key {
+            if let Some(existing) = Self::find_by_idempotency_key(self.database.pool(), account_id, key).await? {
+                return self.transaction_response(existing).await;
+            }
+        }
+
+        let mut tx = self.database.begin_transaction().await?;
+        let applied = self.apply_transaction(&mut tx, account_id, transaction_type, &req, ctx).await?;
+        tx.commit().await?;
+
+        Self::record_completed(&applied);
+
+        Ok(TransactionResponse {
+            transaction: applied.transaction,
+            legs: Vec::new(),
+            fees: applied.fees,
+        })
+    }
+
+    /// Transfers from `account_id` to every leg's recipient in one database
+    /// transaction. The split is recorded as a parent `split` transaction for
+    /// the total, with each leg a `transfer` pointing back at it. Every leg is
+    /// charged the transfer fee, booked as one `fee` on the parent.
+    pub async fn create_split(
+        &self,
+        account_id: Uuid,
+        req: CreateSplitTransactionRequest,
+        ctx: &AuditContext,
+    ) -> Result<TransactionResponse> {
+        req.validate()?;
+        check_split_recipients(account_id, &req)?;
+        let amounts = split_amounts(&req)?;
+        let total: i64 = amounts.iter().sum();
+
+        tracing::info!(account_id = %account_id, amount = total, legs = amounts.len(), "Creating split transfer");
 
         if let Some(ref key) = req.idempotency_key {
-            if let Some(existing) = self.get_transaction_by_idempotency_key(key).await? {
-                return Ok(TransactionResponse {
-                    transaction: existing,
+            if let Some(existing) = Self::find_by_idempotency_key(self.database.pool(), account_id, key).await? {
+                return self.transaction_response(existing).await;
+            }
+        }
+
+        let mut tx = self.database.begin_transaction().await?;
+
+        // Every account involved, the revenue account included, locked in id
+        // order so concurrent splits and transfers can't deadlock.
+        let revenue_account_id = self.revenue_account_id.filter(|id| *id != account_id);
+        let mut account_ids: Vec<Uuid> = req.legs.iter().map(|leg| leg.counterparty_account_id).collect();
+        account_ids.push(account_id);
+        account_ids.extend(revenue_account_id);
+        account_ids.sort();
+        account_ids.dedup();
+
+        let accounts: HashMap<Uuid, (i64, AccountStatus, i64)> = sqlx::query_as::<_, (Uuid, i64, AccountStatus, i64)>(
+            r#"
+            SELECT id, balance, status, low_balance_threshold
+            FROM accounts
+            WHERE id = ANY($1)
+            ORDER BY id
+            FOR UPDATE
+            "#,
+        )
+        .bind(&account_ids)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|(id, balance, status, low_balance_threshold)| (id, (balance, status, low_balance_threshold)))
+        .collect();
+
+        let (balance_before, status, low_balance_threshold) = *accounts.get(&account_id).ok_or_else(|| AppError::AccountNotFound {
+            account_id: account_id.to_string(),
+        })?;
+        match status {
+            AccountStatus::Closed => {
+                return Err(AppError::AccountClosed {
+                    account_id: account_id.to_string(),
                 });
             }
+            AccountStatus::Frozen => {
+                return Err(AppError::AccountFrozen {
+                    account_id: account_id.to_string(),
+                });
+            }
+            AccountStatus::Active => {}
+        }
+
+        // Frozen accounts can still receive transfers.
+        for leg in &req.legs {
+            match accounts.get(&leg.counterparty_account_id) {
+                None => {
+                    return Err(AppError::AccountNotFound {
+                        account_id: leg.counterparty_account_id.to_string(),
+                    });
+                }
+                Some((_, AccountStatus::Closed, _)) => {
+                    return Err(AppError::AccountClosed {
+                        account_id: leg.counterparty_account_id.to_string(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        limit::check(&mut tx, account_id, "split", total).await?;
+
+        // Each leg is a transfer, so each is charged the transfer fee; they
+        // are booked together as one fee on the split.
+        let mut fee = None;
+        if let Some(revenue_account_id) = revenue_account_id {
+            let mut total_fee = 0i64;
+            for &amount in &amounts {
+                let leg_fee = fee::fee_for(&mut tx, account_id, &TransactionType::Transfer, amount).await?;
+                total_fee = total_fee.saturating_add(leg_fee.unwrap_or_default().max(0));
+            }
+            fee = Some((revenue_account_id, total_fee)).filter(|(_, fee)| *fee > 0);
+        }
+        let fee_amount = fee.map_or(0, |(_, fee)| fee);
+        if let Some((revenue_account_id, _)) = fee.filter(|(id, _)| !accounts.contains_key(id)) {
+            return Err(AppError::Internal(anyhow::anyhow!(
+                "platform revenue account {} does not exist",
+                revenue_account_id
+            )));
+        }
+
+        if balance_before < total.saturating_add(fee_amount) {
+            return Err(AppError::InsufficientFunds {
+                account_id: account_id.to_string(),
+                balance: balance_before,
+                required: total.saturating_add(fee_amount),
+            });
+        }
+
+        let parent = sqlx::query_as::<_, Transaction>(
+            r#"
+            INSERT INTO transactions (account_id, type, amount, description, status, idempotency_key)
+            VALUES ($1, 'split', $2, $3, 'completed', $4)
+            RETURNING id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
+            "#,
+        )
+        .bind(account_id)
+        .bind(total)
+        .bind(&req.description)
+        .bind(&req.idempotency_key)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut deltas: HashMap<Uuid, i64> = HashMap::from([(account_id, -total)]);
+        let mut legs = Vec::with_capacity(req.legs.len());
+        for (leg, &amount) in req.legs.iter().zip(&amounts) {
+            let transaction = sqlx::query_as::<_, Transaction>(
+                r#"
+                INSERT INTO transactions (account_id, counterparty_account_id, type, amount, description, status, parent_transaction_id)
+                VALUES ($1, $2, 'transfer', $3, $4, 'completed', $5)
+                RETURNING id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
+                "#,
+            )
+            .bind(account_id)
+            .bind(leg.counterparty_account_id)
+            .bind(amount)
+            .bind(leg.description.as_ref().or(req.description.as_ref()))
+            .bind(parent.id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            *deltas.entry(leg.counterparty_account_id).or_default() += amount;
+            legs.push(transaction);
+        }
+
+        let mut fees = Vec::new();
+        if let Some((revenue_account_id, fee)) = fee {
+            let fee_transaction = sqlx::query_as::<_, Transaction>(
+                r#"
+                INSERT INTO transactions (account_id, counterparty_account_id, type, amount, description, status, parent_transaction_id)
+                VALUES ($1, $2, 'fee', $3, 'split fee', 'completed', $4)
+                RETURNING id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
+                "#,
+            )
+            .bind(account_id)
+            .bind(revenue_account_id)
+            .bind(fee)
+            .bind(parent.id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            *deltas.entry(account_id).or_default() -= fee;
+            *deltas.entry(revenue_account_id).or_default() += fee;
+            fees.push(fee_transaction);
+        }
+
+        for transaction in std::iter::once(&parent).chain(&legs).chain(&fees) {
+            audit::record(
+                &mut tx,
+                ctx,
+                AuditRecord {
+                    action: audit::TRANSACTION_CREATED,
+                    resource_type: "transaction",
+                    resource_id: transaction.id,
+                    before: None,
+                    after: audit::snapshot(transaction),
+                },
+            )
+            .await?;
+        }
+        for transaction in std::iter::once(&parent).chain(&legs) {
+            webhook::enqueue_transaction(&mut tx, transaction).await?;
+        }
+
+        for changed_account_id in account_ids {
+            let delta = deltas.get(&changed_account_id).copied().unwrap_or_default();
+            if delta == 0 {
+                continue;
+            }
+            let before = accounts[&changed_account_id].0;
+
+            sqlx::query(
+                r#"
+                UPDATE accounts
+                SET balance = $1
+                WHERE id = $2
+                "#,
+            )
+            .bind(before + delta)
+            .bind(changed_account_id)
+            .execute(&mut *tx)
+            .await?;
+
+            audit::record(
+                &mut tx,
+                ctx,
+                AuditRecord {
+                    action: audit::ACCOUNT_BALANCE_CHANGED,
+                    resource_type: "account",
+                    resource_id: changed_account_id,
+                    before: Some(json!({ "balance": before })),
+                    after: Some(json!({ "balance": before + delta, "transaction_id": parent.id })),
+                },
+            )
+            .await?;
+        }
+
+        let balances_after: HashMap<Uuid, i64> = accounts
+            .iter()
+            .map(|(&id, &(before, _, _))| (id, before + deltas.get(&id).copied().unwrap_or_default()))
+            .collect();
+        for transaction in std::iter::once(&parent).chain(&legs) {
+            event::record_transaction_events(&mut tx, transaction, &balances_after).await?;
+        }
+        notify_low_balance(
+            &mut tx,
+            &parent,
+            low_balance_threshold,
+            balance_before,
+            balance_before + deltas[&account_id],
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Self::record_completed(&AppliedTransaction {
+            transaction: parent.clone(),
+            fees: fees.clone(),
+            balance_before,
+            balance_after: balance_before + deltas[&account_id],
+        });
+
+        Ok(TransactionResponse {
+            transaction: parent,
+            legs,
+            fees,
+        })
+    }
+
+    /// Creates up to `batch_max_items` transactions for one account. Atomic
+    /// batches run in a single database transaction and stop at the first
+    /// failure, rolling back everything before it; otherwise every item is
+    /// committed or rejected on its own. Idempotency keys apply per item, so a
+    /// retried batch returns the transactions it already created.
+    pub async fn create_batch(
+        &self,
+        account_id: Uuid,
+        items: Vec<CreateTransactionRequest>,
+        atomic: bool,
+        ctx: &AuditContext,
+    ) -> Result<BatchTransactionResponse> {
+        if items.is_empty() {
+            return Err(AppError::InvalidBatch("no transactions".to_string()));
+        }
+        if items.len() > self.batch_max_items {
+            return Err(AppError::InvalidBatch(format!(
+                "{} transactions exceeds the limit of {}",
+                items.len(),
+                self.batch_max_items
+            )));
+        }
+
+        self.run_batch(account_id, items, atomic, ctx).await
+    }
+
+    /// `create_batch` without the size limit, for callers such as background
+    /// jobs that bound the work themselves.
+    pub async fn run_batch(
+        &self,
+        account_id: Uuid,
+        items: Vec<CreateTransactionRequest>,
+        atomic: bool,
+        ctx: &AuditContext,
+    ) -> Result<BatchTransactionResponse> {
+        tracing::info!(account_id = %account_id, items = items.len(), atomic, "Creating transaction batch");
+
+        let results = if atomic {
+            self.create_batch_atomic(account_id, items, ctx).await?
+        } else {
+            self.create_batch_best_effort(account_id, items, ctx).await?
+        };
+
+        let failed = results
+            .iter()
+            .filter(|r| r.status == BatchItemStatus::Failed)
+            .count();
+        let succeeded = results
+            .iter()
+            .filter(|r| matches!(r.status, BatchItemStatus::Created | BatchItemStatus::Existing))
+            .count();
+
+        Ok(BatchTransactionResponse {
+            atomic,
+            succeeded,
+            failed,
+            results,
+        })
+    }
+
+    async fn create_batch_best_effort(
+        &self,
+        account_id: Uuid,
+        items: Vec<CreateTransactionRequest>,
+        ctx: &AuditContext,
+    ) -> Result<Vec<BatchItemResult>> {
+        let mut results = Vec::with_capacity(items.len());
+
+        for (index, req) in items.into_iter().enumerate() {
+            let idempotency_key = req.idempotency_key.clone();
+
+            let outcome = self.create_or_get(account_id, &req, ctx).await;
+
+            results.push(match outcome {
+                Ok((status, transaction)) => BatchItemResult {
+                    index,
+                    status,
+                    idempotency_key,
+                    transaction: Some(transaction),
+                    error: None,
+                },
+                Err(e) => failed_item(index, idempotency_key, &e),
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Creates one transaction in its own database transaction, or returns
+    /// the one its idempotency key already refers to. The status says which.
+    pub async fn create_or_get(
+        &self,
+        account_id: Uuid,
+        req: &CreateTransactionRequest,
+        ctx: &AuditContext,
+    ) -> Result<(BatchItemStatus, Transaction)> {
+        req.validate()?;
+        let transaction_type = parse_transaction_type(&req.r#type)?;
+
+        if let Some(ref key) = req.idempotency_key {
+            if let Some(existing) = Self::find_by_idempotency_key(self.database.pool(), account_id, key).await? {
+                return Ok((BatchItemStatus::Existing, existing));
+            }
         }
 
         let mut tx = self.database.begin_transaction().await?;
+        let applied = self.apply_transaction(&mut tx, account_id, transaction_type, req, ctx).await?;
+        tx.commit().await?;
+
+        Self::record_completed(&applied);
+        Ok((BatchItemStatus::Created, applied.transaction))
+    }
+
+    async fn create_batch_atomic(
+        &self,
+        account_id: Uuid,
+        items: Vec<CreateTransactionRequest>,
+        ctx: &AuditContext,
+    ) -> Result<Vec<BatchItemResult>> {
+        let mut tx = self.database.begin_transaction().await?;
+        let mut results = Vec::with_capacity(items.len());
+        let mut applied = Vec::new();
+        let mut failure = None;
+
+        for (index, req) in items.iter().enumerate() {
+            let outcome = async {
+                req.validate()?;
+                let transaction_type = parse_transaction_type(&req.r#type)?;
+
+                // Looked up inside the transaction so a key repeated within the
+                // batch resolves to the item that used it first.
+                if let Some(ref key) = req.idempotency_key {
+                    if let Some(existing) = Self::find_by_idempotency_key(&mut *tx, account_id, key).await? {
+                        return Ok((BatchItemStatus::Existing, existing, None));
+                    }
+                }
+
+                let item = self.apply_transaction(&mut tx, account_id, transaction_type, req, ctx).await?;
+                Ok::<_, AppError>((BatchItemStatus::Created, item.transaction.clone(), Some(item)))
+            }
+            .await;
+
+            match outcome {
+                Ok((status, transaction, item)) => {
+                    applied.extend(item);
+                    results.push(BatchItemResult {
+                        index,
+                        status,
+                        idempotency_key: req.idempotency_key.clone(),
+                        transaction: Some(transaction),
+                        error: None,
+                    });
+                }
+                Err(e) => {
+                    failure = Some(index);
+                    results.push(failed_item(index, req.idempotency_key.clone(), &e));
+                    break;
+                }
+            }
+        }
 
-        let current_balance = sqlx::query_scalar::<_, i64>(
+        let Some(failed_index) = failure else {
+            tx.commit().await?;
+            for item in &applied {
+                Self::record_completed(item);
+            }
+            return Ok(results);
+        };
+
+        tx.rollback().await?;
+        tracing::info!(account_id = %account_id, failed_index, "Atomic transaction batch rolled back");
+
+        // Includes repeats of a key first used earlier in this batch.
+        let rolled_back: HashSet<Uuid> = applied.iter().map(|item| item.transaction.id).collect();
+        for result in results.iter_mut() {
+            if matches!(&result.transaction, Some(t) if rolled_back.contains(&t.id)) {
+                result.status = BatchItemStatus::RolledBack;
+                result.transaction = None;
+            }
+        }
+        results.extend(items.iter().enumerate().skip(failed_index + 1).map(|(index, req)| BatchItemResult {
+            index,
+            status: BatchItemStatus::Skipped,
+            idempotency_key: req.idempotency_key.clone(),
+            transaction: None,
+            error: None,
+        }));
+
+        Ok(results)
+    }
+
+    /// Locks the account, moves the money, books any fee and writes the audit
+    /// entries, all within `tx`. The caller commits.
+    async fn apply_transaction(
+        &self,
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        account_id: Uuid,
+        transaction_type: TransactionType,
+        req: &CreateTransactionRequest,
+        ctx: &AuditContext,
+    ) -> Result<AppliedTransaction> {
+        req.validate()?;
+        if transaction_type == TransactionType::Transfer && req.counterparty_account_id == Some(account_id) {
+            return Err(validation_error("counterparty_account_id", "same_as_account"));
+        }
+
+        // Both sides of a transfer are locked up front, in id order, so two
+        // opposite transfers between the same accounts can't deadlock.
+        if let Some(counterparty_id) = req.counterparty_account_id.filter(|_| transaction_type == TransactionType::Transfer) {
+            let mut account_ids = [account_id, counterparty_id];
+            account_ids.sort();
+            sqlx::query("SELECT id FROM accounts WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+                .bind(&account_ids[..])
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        // Lock the account so a concurrent freeze or close can't slip in.
+        let (current_balance, status, low_balance_threshold) = sqlx::query_as::<_, (i64, AccountStatus, i64)>(
             r#"
-            SELECT balance
+            SELECT balance, status, low_balance_threshold
             FROM accounts
             WHERE id = $1
+            FOR UPDATE
             "#,
         )
         .bind(account_id)
-        .fetch_optional(&mut *tx)
+        .fetch_optional(&mut **tx)
         .await?
         .ok_or_else(|| AppError::AccountNotFound {
             account_id: account_id.to_string(),
         })?;
 
-        if transaction_type == TransactionType::Transfer {
-           if req.counterparty_account_id.is_none() {
-                return Err(AppError::Internal(anyhow::anyhow!("Missing counterparty account for transfer")));
+        match status {
+            AccountStatus::Closed => {
+                return Err(AppError::AccountClosed {
+                    account_id: account_id.to_string(),
+                });
             }
-        }
-
-        if matches!(transaction_type, TransactionType::Debit | TransactionType::Transfer) {
-            if current_balance < req.amount {
-                return Err(AppError::InsufficientFunds {
+            AccountStatus::Frozen
+                if matches!(transaction_type, TransactionType::Debit | TransactionType::Transfer) =>
+            {
+                return Err(AppError::AccountFrozen {
                     account_id: account_id.to_string(),
-                    balance: current_balance,
-                    required: req.amount,
                 });
             }
+            _ => {}
+        }
+
+        if transaction_type == TransactionType::Transfer && req.counterparty_account_id.is_none() {
+            return Err(validation_error("counterparty_account_id", "required_for_transfer"));
+        }
+
+        limit::check(tx, account_id, &transaction_type.to_string(), req.amount).await?;
+
+        // The revenue account pays no fees on its own transactions.
+        let fee = match self.revenue_account_id {
+            Some(revenue_account_id) if revenue_account_id != account_id => {
+                fee::fee_for(tx, account_id, &transaction_type, req.amount)
+                    .await?
+                    .filter(|fee| *fee > 0)
+                    .map(|fee| (revenue_account_id, fee))
+            }
+            _ => None,
+        };
+        let fee_amount = fee.map_or(0, |(_, fee)| fee);
+
+        // A credit's fee comes out of the credited amount.
+        let (debited, credited) = match transaction_type {
+            TransactionType::Credit => (fee_amount, req.amount),
+            TransactionType::Debit | TransactionType::Transfer => (req.amount.saturating_add(fee_amount), 0),
+        };
+        if current_balance.saturating_add(credited) < debited {
+            return Err(AppError::InsufficientFunds {
+                account_id: account_id.to_string(),
+                balance: current_balance,
+                required: debited - credited,
+            });
         }
 
         let transaction = sqlx::query_as::<_, Transaction>(
             r#"
             INSERT INTO transactions (account_id, counterparty_account_id, type, amount, description, idempotency_key)
             VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, created_at, updated_at
+            RETURNING id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
             "#,
         )
         .bind(account_id)
@@ -94,49 +634,102 @@ impl TransactionService {
         .bind(req.amount)
         .bind(&req.description)
         .bind(&req.idempotency_key)
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut **tx)
         .await?;
 
 
-        let new_balance = match transaction_type {
+        // (account, balance before, balance after) for the audit log
+        let mut balance_changes = Vec::with_capacity(2);
+
+        let mut new_balance = match transaction_type {
             TransactionType::Credit => current_balance + req.amount,
             TransactionType::Debit => current_balance - req.amount,
             TransactionType::Transfer => {
                 if let Some(counterparty_id) = req.counterparty_account_id {
-                    let counterparty_balance = sqlx::query_scalar::<_, i64>(
+                    let counterparty_status = sqlx::query_scalar::<_, AccountStatus>(
                         r#"
-                        SELECT balance
+                        SELECT status
                         FROM accounts
                         WHERE id = $1
                         "#,
                     )
                     .bind(counterparty_id)
-                    .fetch_optional(&mut *tx)
+                    .fetch_optional(&mut **tx)
                     .await?
                     .ok_or_else(|| AppError::AccountNotFound {
                         account_id: counterparty_id.to_string(),
                     })?;
 
+                    // Frozen accounts can still receive transfers.
+                    if counterparty_status == AccountStatus::Closed {
+                        return Err(AppError::AccountClosed {
+                            account_id: counterparty_id.to_string(),
+                        });
+                    }
 
-                    sqlx::query(
+                    let counterparty_balance = sqlx::query_scalar::<_, i64>(
                         r#"
                         UPDATE accounts
-                        SET balance = $1
+                        SET balance = balance + $1
                         WHERE id = $2
+                        RETURNING balance
                         "#,
                     )
-                    .bind(counterparty_balance + req.amount)
+                    .bind(req.amount)
                     .bind(counterparty_id)
-                    .execute(&mut *tx)
+                    .fetch_one(&mut **tx)
                     .await?;
 
+                    balance_changes.push((counterparty_id, counterparty_balance - req.amount, counterparty_balance));
+
                     current_balance - req.amount
                 } else {
-                    return Err(AppError::Internal(anyhow::anyhow!("Missing counterparty account for transfer")));
+                    return Err(validation_error("counterparty_account_id", "required_for_transfer"));
                 }
             }
         };
 
+        let mut fees = Vec::new();
+        if let Some((revenue_account_id, fee)) = fee {
+            let fee_transaction = sqlx::query_as::<_, Transaction>(
+                r#"
+                INSERT INTO transactions (account_id, counterparty_account_id, type, amount, description, status, parent_transaction_id)
+                VALUES ($1, $2, 'fee', $3, $4, 'completed', $5)
+                RETURNING id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
+                "#,
+            )
+            .bind(account_id)
+            .bind(revenue_account_id)
+            .bind(fee)
+            .bind(format!("{} fee", transaction_type))
+            .bind(transaction.id)
+            .fetch_one(&mut **tx)
+            .await?;
+
+            let revenue_balance = sqlx::query_scalar::<_, i64>(
+                r#"
+                UPDATE accounts
+                SET balance = balance + $1
+                WHERE id = $2
+                RETURNING balance
+                "#,
+            )
+            .bind(fee)
+            .bind(revenue_account_id)
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| {
+                AppError::Internal(anyhow::anyhow!(
+                    "platform revenue account {} does not exist",
+                    revenue_account_id
+                ))
+            })?;
+
+            balance_changes.push((revenue_account_id, revenue_balance - fee, revenue_balance));
+            new_balance -= fee;
+            fees.push(fee_transaction);
+        }
+
         sqlx::query(
             r#"
             UPDATE accounts
@@ -146,9 +739,11 @@ impl TransactionService {
         )
         .bind(new_balance)
         .bind(account_id)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
 
+        balance_changes.push((account_id, current_balance, new_balance));
+
        
         sqlx::query(
             r#"
@@ -158,56 +753,93 @@ impl TransactionService {
             "#,
         )
         .bind(transaction.id)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
 
-        tx.commit().await?;
-
         let completed_transaction = sqlx::query_as::<_, Transaction>(
             r#"
-            SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, created_at, updated_at
+            SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
             FROM transactions
             WHERE id = $1
             "#,
         )
         .bind(transaction.id)
-        .fetch_one(&*self.database.pool())
+        .fetch_one(&mut **tx)
         .await?;
 
+        for transaction in std::iter::once(&completed_transaction).chain(&fees) {
+            audit::record(
+                tx,
+                ctx,
+                AuditRecord {
+                    action: audit::TRANSACTION_CREATED,
+                    resource_type: "transaction",
+                    resource_id: transaction.id,
+                    before: None,
+                    after: audit::snapshot(transaction),
+                },
+            )
+            .await?;
+        }
+
+        for &(changed_account_id, before, after) in &balance_changes {
+            audit::record(
+                tx,
+                ctx,
+                AuditRecord {
+                    action: audit::ACCOUNT_BALANCE_CHANGED,
+                    resource_type: "account",
+                    resource_id: changed_account_id,
+                    before: Some(json!({ "balance": before })),
+                    after: Some(json!({ "balance": after, "transaction_id": completed_transaction.id })),
+                },
+            )
+            .await?;
+        }
+
+        webhook::enqueue_transaction(tx, &completed_transaction).await?;
+        let balances_after: HashMap<Uuid, i64> = balance_changes.iter().map(|&(id, _, after)| (id, after)).collect();
+        event::record_transaction_events(tx, &completed_transaction, &balances_after).await?;
+        notify_low_balance(tx, &completed_transaction, low_balance_threshold, current_balance, new_balance).await?;
+
+        Ok(AppliedTransaction {
+            transaction: completed_transaction,
+            fees,
+            balance_before: current_balance,
+            balance_after: new_balance,
+        })
+    }
+
+    fn record_completed(applied: &AppliedTransaction) {
+        let transaction = &applied.transaction;
+
         tracing::info!(
-            transaction_id = %completed_transaction.id,
-            account_id = %completed_transaction.account_id,
-            transaction_type = %completed_transaction.r#type,
-            amount = completed_transaction.amount,
-            new_balance = new_balance,
+            transaction_id = %transaction.id,
+            account_id = %transaction.account_id,
+            transaction_type = %transaction.r#type,
+            amount = transaction.amount,
+            new_balance = applied.balance_after,
             "Transaction completed successfully"
         );
 
-        crate::metrics::record_transaction_created(
-            &completed_transaction.r#type,
-            completed_transaction.amount as f64,
-        );
+        crate::metrics::record_transaction_created(&transaction.r#type, transaction.amount as f64);
         crate::metrics::record_balance_change(
-            &completed_transaction.account_id.to_string(),
-            current_balance as f64,
-            new_balance as f64,
+            &transaction.account_id.to_string(),
+            applied.balance_before as f64,
+            applied.balance_after as f64,
         );
-
-        Ok(TransactionResponse {
-            transaction: completed_transaction,
-        })
     }
 
     pub async fn get_transaction(&self, transaction_id: Uuid) -> Result<Transaction> {
         let transaction = sqlx::query_as::<_, Transaction>(
             r#"
-            SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, created_at, updated_at
+            SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
             FROM transactions
             WHERE id = $1
             "#,
         )
         .bind(transaction_id)
-        .fetch_optional(&*self.database.pool())
+        .fetch_optional(self.database.pool())
         .await?
         .ok_or_else(|| AppError::TransactionNotFound {
             transaction_id: transaction_id.to_string(),
@@ -216,18 +848,295 @@ impl TransactionService {
         Ok(transaction)
     }
 
-    async fn get_transaction_by_idempotency_key(&self, key: &str) -> Result<Option<Transaction>> {
+    /// `transaction` with its split legs and fees.
+    pub async fn transaction_response(&self, transaction: Transaction) -> Result<TransactionResponse> {
+        let children = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
+            FROM transactions
+            WHERE parent_transaction_id = $1
+            ORDER BY counterparty_account_id, id
+            "#,
+        )
+        .bind(transaction.id)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let (fees, legs) = children.into_iter().partition(|child| child.r#type == "fee");
+        Ok(TransactionResponse {
+            transaction,
+            legs,
+            fees,
+        })
+    }
+
+    /// The account's transaction with idempotency key `key`, if any. Keys
+    /// are scoped to the account, so another account's key never matches.
+    async fn find_by_idempotency_key<'e, E>(executor: E, account_id: Uuid, key: &str) -> Result<Option<Transaction>>
+    where
+        E: sqlx::PgExecutor<'e>,
+    {
         let transaction = sqlx::query_as::<_, Transaction>(
             r#"
-            SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, created_at, updated_at
+            SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
             FROM transactions
             WHERE idempotency_key = $1
+            AND account_id = $2
             "#,
         )
         .bind(key)
-        .fetch_optional(&*self.database.pool())
+        .bind(account_id)
+        .fetch_optional(executor)
         .await?;
 
         Ok(transaction)
-     }
+    }
+}
+
+/// Post-commit side effect of a new transaction: sending the webhook
+/// deliveries queued with it. Its account events were written in the same
+/// database transaction. Failures are logged rather than returned, since the
+/// transaction itself has already happened.
+pub async fn publish_transaction(webhook_service: &WebhookService, transaction: &Transaction) {
+    // The deliveries were queued with the transaction; this only sends them.
+    // Any left unsent are picked up by the retry scheduler.
+    if let Err(e) = webhook_service.deliver_webhook(transaction).await {
+        tracing::error!(transaction_id = %transaction.id, "Failed to send webhooks: {}", e);
+    }
+}
+
+/// Queues `balance.low` for the account that made `transaction`, within
+/// `tx`, if it took the balance from `before` to below the account's
+/// threshold. Only the sender's balance can drop.
+async fn notify_low_balance(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    transaction: &Transaction,
+    threshold: i64,
+    before: i64,
+    after: i64,
+) -> Result<()> {
+    if !events::crossed_low_balance(threshold, before, after) {
+        return Ok(());
+    }
+
+    let data = json!({
+        "account_id": transaction.account_id,
+        "balance": after,
+        "low_balance_threshold": threshold,
+    });
+    webhook::enqueue_transaction_event(tx, transaction.account_id, events::BALANCE_LOW, transaction, &data).await?;
+    event::record_transaction_event(tx, transaction.account_id, events::BALANCE_LOW, transaction, &data).await
+}
+
+/// A split pays other accounts; like a transfer, no leg may go back to the
+/// sender.
+fn check_split_recipients(account_id: Uuid, req: &CreateSplitTransactionRequest) -> Result<()> {
+    if req.legs.iter().any(|leg| leg.counterparty_account_id == account_id) {
+        return Err(validation_error("counterparty_account_id", "same_as_account"));
+    }
+    Ok(())
+}
+
+/// Leg amounts in request order. Percentage legs get their share of `amount`
+/// rounded down; the units left over go one each to the legs with the
+/// largest remainders, earlier legs first on ties, so the legs always add up
+/// to `amount` and the same request always splits the same way.
+fn split_amounts(req: &CreateSplitTransactionRequest) -> Result<Vec<i64>> {
+    if req.legs.iter().all(|leg| leg.amount.is_some()) {
+        let amounts: Vec<i64> = req.legs.iter().filter_map(|leg| leg.amount).collect();
+        let total = amounts
+            .iter()
+            .try_fold(0i64, |total, &amount| total.checked_add(amount))
+            .ok_or_else(|| validation_error("legs", "total_too_large"))?;
+        if req.amount.is_some_and(|amount| amount != total) {
+            return Err(validation_error("amount", "must_equal_sum_of_legs"));
+        }
+        return Ok(amounts);
+    }
+
+    if req.legs.iter().any(|leg| leg.amount.is_some()) {
+        return Err(validation_error("legs", "cannot_mix_amounts_and_percentages"));
+    }
+    let amount = req
+        .amount
+        .ok_or_else(|| validation_error("amount", "required_for_percentage_legs"))?;
+
+    // Hundredths of a percent, so the arithmetic below is exact.
+    let basis_points: Vec<i128> = req
+        .legs
+        .iter()
+        .map(|leg| (leg.percentage.unwrap_or_default() * 100.0).round() as i128)
+        .collect();
+    if basis_points.iter().sum::<i128>() != 10_000 {
+        return Err(validation_error("legs", "percentages_must_total_100"));
+    }
+
+    let shares: Vec<(i64, i128)> = basis_points
+        .iter()
+        .map(|&bp| {
+            let share = i128::from(amount) * bp;
+            ((share / 10_000) as i64, share % 10_000)
+        })
+        .collect();
+    let mut amounts: Vec<i64> = shares.iter().map(|&(floor, _)| floor).collect();
+
+    let leftover = amount - amounts.iter().sum::<i64>();
+    let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+    by_remainder.sort_by_key(|&index| (std::cmp::Reverse(shares[index].1), index));
+    for &index in by_remainder.iter().take(leftover as usize) {
+        amounts[index] += 1;
+    }
+
+    if amounts.contains(&0) {
+        return Err(validation_error("legs", "leg_amount_rounds_to_zero"));
+    }
+
+    Ok(amounts)
+}
+
+/// The checks `create_or_get` makes before touching the database, for
+/// requests that are stored now and executed later.
+pub fn validate_request(req: &CreateTransactionRequest) -> Result<()> {
+    req.validate()?;
+    let transaction_type = parse_transaction_type(&req.r#type)?;
+    if transaction_type == TransactionType::Transfer && req.counterparty_account_id.is_none() {
+        return Err(validation_error("counterparty_account_id", "required_for_transfer"));
+    }
+    Ok(())
+}
+
+pub fn parse_transaction_type(value: &str) -> Result<TransactionType> {
+    match value {
+        "credit" => Ok(TransactionType::Credit),
+        "debit" => Ok(TransactionType::Debit),
+        "transfer" => Ok(TransactionType::Transfer),
+        _ => Err(validation_error("type", "invalid_transaction_type")),
+    }
+}
+
+pub fn validation_error(field: &'static str, code: &'static str) -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.add(field, ValidationError::new(code));
+    errors.into()
+}
+
+fn failed_item(index: usize, idempotency_key: Option<String>, error: &AppError) -> BatchItemResult {
+    let (status, message) = error.status_and_message();
+    BatchItemResult {
+        index,
+        status: BatchItemStatus::Failed,
+        idempotency_key,
+        transaction: None,
+        error: Some(BatchItemError {
+            code: status.as_u16(),
+            error: message,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SplitLegRequest;
+
+    fn split(amount: Option<i64>, legs: &[(Option<i64>, Option<f64>)]) -> CreateSplitTransactionRequest {
+        CreateSplitTransactionRequest {
+            idempotency_key: None,
+            amount,
+            description: None,
+            legs: legs
+                .iter()
+                .map(|&(amount, percentage)| SplitLegRequest {
+                    counterparty_account_id: Uuid::new_v4(),
+                    amount,
+                    percentage,
+                    description: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn percentages(amount: i64, percentages: &[f64]) -> Result<Vec<i64>> {
+        let legs: Vec<_> = percentages.iter().map(|&p| (None, Some(p))).collect();
+        split_amounts(&split(Some(amount), &legs))
+    }
+
+    fn error_code<T: std::fmt::Debug>(result: Result<T>) -> String {
+        match result {
+            Err(AppError::Validation(errors)) => errors.field_errors().values().next().unwrap()[0].code.to_string(),
+            other => panic!("expected a validation error, got {:?}", other.map_err(|e| e.to_string())),
+        }
+    }
+
+    #[test]
+    fn fixed_amounts_are_used_as_given() {
+        assert_eq!(split_amounts(&split(None, &[(Some(700), None), (Some(300), None)])).unwrap(), [700, 300]);
+        assert_eq!(
+            split_amounts(&split(Some(1000), &[(Some(700), None), (Some(300), None)])).unwrap(),
+            [700, 300]
+        );
+        assert_eq!(
+            error_code(split_amounts(&split(Some(999), &[(Some(700), None), (Some(300), None)]))),
+            "must_equal_sum_of_legs"
+        );
+        assert_eq!(
+            error_code(split_amounts(&split(None, &[(Some(i64::MAX), None), (Some(1), None)]))),
+            "total_too_large"
+        );
+    }
+
+    #[test]
+    fn percentages_divide_exactly_when_they_can() {
+        assert_eq!(percentages(1000, &[50.0, 30.0, 20.0]).unwrap(), [500, 300, 200]);
+        assert_eq!(percentages(10_000, &[12.5, 87.5]).unwrap(), [1250, 8750]);
+        assert_eq!(percentages(7, &[100.0]).unwrap(), [7]);
+    }
+
+    #[test]
+    fn leftover_units_go_to_the_largest_remainders() {
+        // 3.333, 3.333 and 3.334: the leftover unit goes to the last leg
+        assert_eq!(percentages(10, &[33.33, 33.33, 33.34]).unwrap(), [3, 3, 4]);
+        // 2.5 each: two units left over, to the first legs on the tie
+        assert_eq!(percentages(10, &[25.0, 25.0, 25.0, 25.0]).unwrap(), [3, 3, 2, 2]);
+        // 2.5 and 7.5: equal remainders, so the earlier leg gets the unit
+        assert_eq!(percentages(10, &[25.0, 75.0]).unwrap(), [3, 7]);
+        assert_eq!(percentages(3, &[50.0, 50.0]).unwrap(), [2, 1]);
+    }
+
+    #[test]
+    fn legs_always_add_up_to_the_amount() {
+        for amount in [1, 2, 3, 7, 99, 101, 1_000_003, i64::MAX / 10_000] {
+            let Ok(amounts) = percentages(amount, &[33.33, 33.33, 33.34]) else {
+                continue;
+            };
+            assert_eq!(amounts.iter().sum::<i64>(), amount);
+        }
+        let amounts = percentages(i64::MAX, &[0.01, 99.99]).unwrap();
+        assert_eq!(amounts.iter().map(|&a| i128::from(a)).sum::<i128>(), i128::from(i64::MAX));
+    }
+
+    #[test]
+    fn rejects_invalid_percentage_splits() {
+        assert_eq!(error_code(percentages(1000, &[50.0, 49.0])), "percentages_must_total_100");
+        assert_eq!(error_code(percentages(1000, &[50.0, 50.01])), "percentages_must_total_100");
+        assert_eq!(error_code(percentages(1, &[50.0, 50.0])), "leg_amount_rounds_to_zero");
+        assert_eq!(
+            error_code(split_amounts(&split(None, &[(None, Some(100.0))]))),
+            "required_for_percentage_legs"
+        );
+        assert_eq!(
+            error_code(split_amounts(&split(Some(100), &[(Some(50), None), (None, Some(50.0))]))),
+            "cannot_mix_amounts_and_percentages"
+        );
+    }
+
+    #[test]
+    fn rejects_a_leg_paying_the_sender() {
+        let mut req = split(None, &[(Some(700), None), (Some(300), None)]);
+        let account_id = Uuid::new_v4();
+        assert!(check_split_recipients(account_id, &req).is_ok());
+
+        req.legs[1].counterparty_account_id = account_id;
+        assert_eq!(error_code(check_split_recipients(account_id, &req)), "same_as_account");
+    }
 }
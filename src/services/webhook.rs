@@ -1,39 +1,207 @@
 use crate::{
+    config::Config,
+    crypto::SecretCipher,
     database::Database,
     error::{AppError, Result},
+    events,
+    services::audit::{self, AuditRecord},
+    url_policy::{PolicyResolver, UrlPolicy},
     models::{
-        CreateWebhookRequest, Transaction, Webhook, WebhookPayload, WebhookResponse,
+        AuditContext, CreateWebhookRequest, EnableWebhookRequest, EnableWebhookResponse, ReplayWebhookRequest, ReplayWebhookResponse,
+        RotateWebhookSecretRequest, Transaction, Webhook, WebhookBatchPayload, WebhookDelivery,
+        WebhookDeliveryStatus, WebhookPayload,
+        WebhookResponse, WebhookSecretResponse,
     },
 };
 use chrono::Utc;
-use hmac::{Hmac, Mac};
-use reqwest::Client;
+use governor::{clock::DefaultClock, state::keyed::DefaultKeyedStateStore, Quota, RateLimiter};
+use nonzero_ext::nonzero;
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde::Deserialize;
 use serde_json::json;
-use sha2::Sha256;
-use std::sync::Arc;
+use sqlx::Postgres;
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroU32,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+const DEFAULT_MAX_ATTEMPTS: i32 = 3;
+/// Transactions covered by one replay request; larger ranges are continued
+/// from the `next_from`/`next_after` cursor in the response.
+const REPLAY_PAGE_SIZE: i64 = 1_000;
 
-type HmacSha256 = Hmac<Sha256>;
+/// Paces manual redeliveries and replays per webhook so a replay cannot flood the receiver.
+type DeliveryRateLimiter = RateLimiter<Uuid, DefaultKeyedStateStore<Uuid>, DefaultClock>;
+
+/// When an endpoint is considered persistently failing and gets disabled.
+#[derive(Clone)]
+struct CircuitBreakerPolicy {
+    max_consecutive_failures: i32,
+    failure_rate_threshold: f64,
+    window_minutes: i32,
+    min_deliveries: i64,
+}
+
+/// Result of one signed POST to a webhook endpoint.
+struct DeliveryOutcome {
+    success: bool,
+    response_status: Option<i32>,
+    response_body: Option<String>,
+}
+
+impl DeliveryOutcome {
+    /// A failed attempt where the request never left this service, e.g.
+    /// because the webhook's secret can't be decrypted.
+    fn not_sent(reason: impl std::fmt::Display) -> Self {
+        Self {
+            success: false,
+            response_status: None,
+            response_body: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Optional JSON body a receiver may return for a batched delivery. Events
+/// listed in `failed` are retried; the rest of the batch is acknowledged.
+#[derive(Deserialize)]
+struct BatchAcknowledgement {
+    #[serde(default)]
+    failed: Vec<Uuid>,
+}
+
+/// A delivery that has been recorded and is waiting to be sent.
+struct QueuedDelivery {
+    webhook: Webhook,
+    event: String,
+    data: Option<serde_json::Value>,
+    delivery_id: Uuid,
+    sequence: i64,
+}
+
+/// Key space for the advisory locks that let one drain or flush per webhook
+/// run at a time across every instance.
+const WEBHOOK_QUEUE_LOCK: i32 = 0x5748_5100;
 
 #[derive(Clone)]
 pub struct WebhookService {
     database: Arc<Database>,
     client: Client,
+    replay_limiter: Arc<DeliveryRateLimiter>,
+    cipher: SecretCipher,
+    circuit_breaker: CircuitBreakerPolicy,
+    url_policy: Arc<UrlPolicy>,
+    /// Batched webhooks with a linger timer armed for a partial batch.
+    batch_timers: Arc<Mutex<HashSet<Uuid>>>,
 }
 
 impl WebhookService {
-    pub fn new(database: Arc<Database>) -> Self {
+    pub fn new(database: Arc<Database>, config: &Config, cipher: SecretCipher) -> Self {
+        let replay_rate = NonZeroU32::new(config.webhook_replay_rate_per_second)
+            .unwrap_or(nonzero!(5u32));
+
+        let url_policy = Arc::new(UrlPolicy::from_config(config));
+        let client = Client::builder()
+            .no_proxy()
+            .dns_resolver(Arc::new(PolicyResolver::new(url_policy.clone())))
+            .redirect(url_policy.redirect_policy())
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Failed to build webhook HTTP client");
+
         Self {
             database,
-            client: Client::new(),
+            client,
+            replay_limiter: Arc::new(RateLimiter::keyed(Quota::per_second(replay_rate))),
+            cipher,
+            circuit_breaker: CircuitBreakerPolicy {
+                max_consecutive_failures: config.webhook_disable_after_failures,
+                failure_rate_threshold: config.webhook_failure_rate_threshold,
+                window_minutes: config.webhook_failure_rate_window_minutes,
+                min_deliveries: config.webhook_failure_rate_min_deliveries,
+            },
+            url_policy,
+            batch_timers: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
+    /// Rewrites webhook secrets not yet encrypted under the current key:
+    /// plaintext from before encryption at rest, the unversioned legacy
+    /// format, and values from a retired key. Fails if one can't be read.
+    pub async fn reencrypt_secrets(&self) -> Result<()> {
+        #[derive(sqlx::FromRow)]
+        struct SecretRow {
+            id: Uuid,
+            secret: String,
+            previous_secret: Option<String>,
+        }
+
+        let rows = sqlx::query_as::<_, SecretRow>("SELECT id, secret, previous_secret FROM webhooks")
+            .fetch_all(self.database.pool())
+            .await?;
+
+        let reencrypt = |stored: &str| -> Result<String> {
+            let plaintext = self.cipher.decrypt(stored)?;
+            Ok(self.cipher.encrypt(&plaintext)?)
+        };
+
+        let mut count = 0;
+        for row in &rows {
+            let stale_previous = row
+                .previous_secret
+                .as_deref()
+                .filter(|previous| self.cipher.needs_reencryption(previous));
+            if !self.cipher.needs_reencryption(&row.secret) && stale_previous.is_none() {
+                continue;
+            }
+
+            let secret = if self.cipher.needs_reencryption(&row.secret) {
+                reencrypt(&row.secret)?
+            } else {
+                row.secret.clone()
+            };
+            let previous_secret = match stale_previous {
+                Some(previous) => Some(reencrypt(previous)?),
+                None => row.previous_secret.clone(),
+            };
+
+            sqlx::query(
+                r#"
+                UPDATE webhooks
+                SET secret = $1, previous_secret = $2
+                WHERE id = $3
+                "#,
+            )
+            .bind(secret)
+            .bind(previous_secret)
+            .bind(row.id)
+            .execute(self.database.pool())
+            .await?;
+            count += 1;
+        }
+
+        if count > 0 {
+            tracing::info!(count, "Re-encrypted webhook secrets under the current key");
+        }
+
+        Ok(())
+    }
+
     pub async fn create_webhook(
         &self,
         account_id: Uuid,
         req: CreateWebhookRequest,
-    ) -> Result<WebhookResponse> {
+        ctx: &AuditContext,
+    ) -> Result<WebhookSecretResponse> {
+        req.validate()?;
+        let ordered = req.ordered.unwrap_or(false);
+        let batch_max_size = req.batch_max_size.unwrap_or(1);
+        validate_delivery_mode(ordered, batch_max_size)?;
+        self.check_url_policy(&req.url).await?;
+
         let span = tracing::info_span!(
             "create_webhook",
             account_id = %account_id,
@@ -42,34 +210,125 @@ impl WebhookService {
         let _enter = span.enter();
         
         tracing::info!("Creating webhook");
-        
+
+        let secret = self.cipher.generate_secret()?;
+
+        let mut tx = self.database.begin_transaction().await?;
+
         let webhook = sqlx::query_as::<_, Webhook>(
             r#"
-            INSERT INTO webhooks (account_id, url, events, secret)
-            VALUES ($1, $2, $3, $4)
-            RETURNING id, account_id, url, events, secret, is_active, created_at, updated_at
+            INSERT INTO webhooks (account_id, url, events, secret, ordered, batch_max_size, batch_max_linger_ms)
+            VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, 1000))
+            RETURNING id, account_id, url, events, secret, previous_secret, previous_secret_expires_at, is_active, ordered, batch_max_size, batch_max_linger_ms, consecutive_failures, disabled_at, disabled_reason, created_at, updated_at
             "#,
         )
         .bind(account_id)
         .bind(&req.url)
         .bind(&req.events)
-        .bind(Uuid::new_v4().to_string())
-        .fetch_one(&*self.database.pool())
+        .bind(self.cipher.encrypt(&secret)?)
+        .bind(ordered)
+        .bind(batch_max_size)
+        .bind(req.batch_max_linger_ms)
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(WebhookResponse { webhook })
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::WEBHOOK_CREATED,
+                resource_type: "webhook",
+                resource_id: webhook.id,
+                before: None,
+                after: audit::snapshot(&webhook),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(WebhookSecretResponse { webhook, secret })
+    }
+
+    pub async fn rotate_secret(
+        &self,
+        account_id: Uuid,
+        webhook_id: Uuid,
+        req: RotateWebhookSecretRequest,
+        ctx: &AuditContext,
+    ) -> Result<WebhookSecretResponse> {
+        req.validate()?;
+
+        let existing = self.get_account_webhook(account_id, webhook_id).await?;
+        let overlap = chrono::Duration::seconds(req.overlap_seconds.unwrap_or(86400));
+        let secret = self.cipher.generate_secret()?;
+
+        let mut tx = self.database.begin_transaction().await?;
+
+        let webhook = sqlx::query_as::<_, Webhook>(
+            r#"
+            UPDATE webhooks
+            SET previous_secret = secret, previous_secret_expires_at = $1, secret = $2, updated_at = NOW()
+            WHERE id = $3
+            RETURNING id, account_id, url, events, secret, previous_secret, previous_secret_expires_at, is_active, ordered, batch_max_size, batch_max_linger_ms, consecutive_failures, disabled_at, disabled_reason, created_at, updated_at
+            "#,
+        )
+        .bind(Utc::now() + overlap)
+        .bind(self.cipher.encrypt(&secret)?)
+        .bind(existing.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::WEBHOOK_SECRET_ROTATED,
+                resource_type: "webhook",
+                resource_id: webhook.id,
+                before: audit::snapshot(&existing),
+                after: audit::snapshot(&webhook),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(
+            webhook_id = %webhook.id,
+            previous_secret_expires_at = ?webhook.previous_secret_expires_at,
+            "Webhook secret rotated"
+        );
+
+        Ok(WebhookSecretResponse { webhook, secret })
     }
 
-    pub async fn get_webhook(&self, webhook_id: Uuid) -> Result<Webhook> {
+    /// Secrets currently used to sign deliveries: the active one, plus the
+    /// previous one while its rotation overlap window is still open.
+    fn signing_secrets(&self, webhook: &Webhook) -> Result<Vec<String>> {
+        let mut secrets = vec![self.cipher.decrypt(&webhook.secret)?];
+
+        if let (Some(previous), Some(expires_at)) =
+            (&webhook.previous_secret, webhook.previous_secret_expires_at)
+        {
+            if expires_at > Utc::now() {
+                secrets.push(self.cipher.decrypt(previous)?);
+            }
+        }
+
+        Ok(secrets)
+    }
+
+    async fn get_webhook(&self, webhook_id: Uuid) -> Result<Webhook> {
         let webhook = sqlx::query_as::<_, Webhook>(
             r#"
-            SELECT id, account_id, url, events, secret, is_active, created_at, updated_at
+            SELECT id, account_id, url, events, secret, previous_secret, previous_secret_expires_at, is_active, ordered, batch_max_size, batch_max_linger_ms, consecutive_failures, disabled_at, disabled_reason, created_at, updated_at
             FROM webhooks
             WHERE id = $1
             "#,
         )
         .bind(webhook_id)
-        .fetch_optional(&*self.database.pool())
+        .fetch_optional(self.database.pool())
         .await?
         .ok_or_else(|| AppError::WebhookNotFound {
             webhook_id: webhook_id.to_string(),
@@ -80,163 +339,1128 @@ impl WebhookService {
 
     pub async fn update_webhook(
         &self,
+        account_id: Uuid,
         webhook_id: Uuid,
         req: CreateWebhookRequest,
+        ctx: &AuditContext,
     ) -> Result<WebhookResponse> {
+        req.validate()?;
+        let existing = self.get_account_webhook(account_id, webhook_id).await?;
+        let ordered = req.ordered.unwrap_or(existing.ordered);
+        let batch_max_size = req.batch_max_size.unwrap_or(existing.batch_max_size);
+        validate_delivery_mode(ordered, batch_max_size)?;
+        self.check_url_policy(&req.url).await?;
+
+        let mut tx = self.database.begin_transaction().await?;
+
         let webhook = sqlx::query_as::<_, Webhook>(
             r#"
             UPDATE webhooks
-            SET url = $1, events = $2, updated_at = NOW()
-            WHERE id = $3
-            RETURNING id, account_id, url, events, secret, is_active, created_at, updated_at
+            SET url = $1, events = $2, ordered = $3, batch_max_size = $4,
+                batch_max_linger_ms = COALESCE($5, batch_max_linger_ms), updated_at = NOW()
+            WHERE id = $6
+            RETURNING id, account_id, url, events, secret, previous_secret, previous_secret_expires_at, is_active, ordered, batch_max_size, batch_max_linger_ms, consecutive_failures, disabled_at, disabled_reason, created_at, updated_at
             "#,
         )
         .bind(&req.url)
         .bind(&req.events)
+        .bind(ordered)
+        .bind(batch_max_size)
+        .bind(req.batch_max_linger_ms)
         .bind(webhook_id)
-        .fetch_optional(&*self.database.pool())
+        .fetch_optional(&mut *tx)
         .await?
         .ok_or_else(|| AppError::WebhookNotFound {
             webhook_id: webhook_id.to_string(),
         })?;
 
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::WEBHOOK_UPDATED,
+                resource_type: "webhook",
+                resource_id: webhook.id,
+                before: audit::snapshot(&existing),
+                after: audit::snapshot(&webhook),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
         Ok(WebhookResponse { webhook })
     }
 
-    pub async fn delete_webhook(&self, webhook_id: Uuid) -> Result<()> {
-        let result = sqlx::query(
+    pub async fn delete_webhook(&self, account_id: Uuid, webhook_id: Uuid, ctx: &AuditContext) -> Result<()> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        let webhook = sqlx::query_as::<_, Webhook>(
             r#"
             DELETE FROM webhooks
-            WHERE id = $1
+            WHERE id = $1 AND account_id = $2
+            RETURNING id, account_id, url, events, secret, previous_secret, previous_secret_expires_at, is_active, ordered, batch_max_size, batch_max_linger_ms, consecutive_failures, disabled_at, disabled_reason, created_at, updated_at
             "#,
         )
         .bind(webhook_id)
-        .execute(&*self.database.pool())
+        .bind(account_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::WebhookNotFound {
+            webhook_id: webhook_id.to_string(),
+        })?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::WEBHOOK_DELETED,
+                resource_type: "webhook",
+                resource_id: webhook.id,
+                before: audit::snapshot(&webhook),
+                after: None,
+            },
+        )
         .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(AppError::WebhookNotFound {
-                webhook_id: webhook_id.to_string(),
-            });
-        }
+        tx.commit().await?;
 
         Ok(())
     }
 
+    /// Sends the deliveries [`enqueue_transaction`] queued for `transaction`,
+    /// once the database transaction that wrote both has committed. The HTTP
+    /// requests are made in the background.
     pub async fn deliver_webhook(&self, transaction: &Transaction) -> Result<()> {
-        let webhooks = sqlx::query_as::<_, Webhook>(
+        #[derive(sqlx::FromRow)]
+        struct PendingRow {
+            id: Uuid,
+            webhook_id: Uuid,
+            event: String,
+            data: Option<serde_json::Value>,
+            sequence: i64,
+        }
+
+        let pending = sqlx::query_as::<_, PendingRow>(
             r#"
-            SELECT id, account_id, url, events, secret, is_active, created_at, updated_at
-            FROM webhooks
-            WHERE account_id = $1 AND is_active = true
+            SELECT id, webhook_id, event, data, sequence
+            FROM webhook_deliveries
+            WHERE transaction_id = $1
+            AND status = 'pending'
+            AND attempts = 0
+            AND sequence IS NOT NULL
+            ORDER BY webhook_id, sequence
             "#,
         )
-        .bind(transaction.account_id)
-        .fetch_all(&*self.database.pool())
+        .bind(transaction.id)
+        .fetch_all(self.database.pool())
         .await?;
 
-        for webhook in webhooks {
-            let event_type = match transaction.r#type.as_str() {
-                "credit" => "transaction.credit",
-                "debit" => "transaction.debit",
-                "transfer" => "transaction.transfer",
-                _ => continue,
+        let mut webhooks: HashMap<Uuid, Webhook> = HashMap::new();
+        let mut queued = Vec::with_capacity(pending.len());
+        for delivery in pending {
+            let webhook = match webhooks.get(&delivery.webhook_id) {
+                Some(webhook) => webhook.clone(),
+                None => {
+                    let webhook = self.get_webhook(delivery.webhook_id).await?;
+                    webhooks.insert(webhook.id, webhook.clone());
+                    webhook
+                }
             };
+            queued.push(QueuedDelivery {
+                webhook,
+                event: delivery.event,
+                data: delivery.data,
+                delivery_id: delivery.id,
+                sequence: delivery.sequence,
+            });
+        }
+
+        self.dispatch(queued, Some(transaction.clone()));
+
+        Ok(())
+    }
+
+    /// Notifies `account_id` of an event outside a transaction's own
+    /// lifecycle. `data` becomes the payload's `data` field and is stored with
+    /// each delivery so retries send the same body.
+    pub async fn deliver_event(
+        &self,
+        account_id: Uuid,
+        event: &'static str,
+        transaction: Option<&Transaction>,
+        data: serde_json::Value,
+    ) -> Result<()> {
+        let mut tx = self.database.begin_transaction().await?;
+        let queued = enqueue_for_accounts(&mut tx, &[(account_id, vec![event])], transaction, Some(&data)).await?;
+        tx.commit().await?;
+
+        self.dispatch(queued, transaction.cloned());
+
+        Ok(())
+    }
 
-            if !webhook.events.contains(&event_type.to_string()) {
+    /// Sends freshly queued deliveries in the background, honouring each
+    /// webhook's ordered or batched delivery mode. Every endpoint gets its own
+    /// task, so one that is slow to answer can't hold up the others.
+    fn dispatch(&self, queued: Vec<QueuedDelivery>, transaction: Option<Transaction>) {
+        let mut ordered = HashSet::new();
+        let mut batched = HashMap::new();
+        for delivery in queued {
+            if delivery.webhook.ordered {
+                ordered.insert(delivery.webhook.id);
+                continue;
+            }
+            if delivery.webhook.batch_max_size > 1 {
+                batched.insert(delivery.webhook.id, delivery.webhook);
                 continue;
             }
+            let service = self.clone();
+            let transaction = transaction.clone();
+            tokio::spawn(async move {
+                service
+                    .deliver_webhook_async(
+                        delivery.webhook,
+                        &delivery.event,
+                        transaction.as_ref(),
+                        delivery.data.as_ref(),
+                        delivery.delivery_id,
+                        Some(delivery.sequence),
+                    )
+                    .await;
+            });
+        }
+
+        for webhook_id in ordered {
+            let service = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = service.drain_ordered(webhook_id, false).await {
+                    tracing::error!(webhook_id = %webhook_id, "Failed to drain ordered webhook: {}", e);
+                }
+            });
+        }
+
+        for webhook in batched.into_values() {
+            let service = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = service.schedule_batch(&webhook, false).await {
+                    tracing::error!(webhook_id = %webhook.id, "Failed to flush webhook batch: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Sends an ordered webhook's outstanding deliveries strictly in sequence.
+    ///
+    /// Stops at the first delivery still waiting for a retry, so later events
+    /// are held back until it succeeds or exhausts its attempts. Only one drain
+    /// per webhook runs at a time across all instances; concurrent callers
+    /// return immediately and their deliveries are picked up by the running
+    /// drain.
+    async fn drain_ordered(&self, webhook_id: Uuid, paced: bool) -> Result<()> {
+        loop {
+            let drained = self
+                .with_queue_lock(webhook_id, self.drain_ordered_once(webhook_id, paced))
+                .await?;
+            if drained.is_none() {
+                return Ok(());
+            }
+
+            // A delivery may have been queued after our last check but before we released the lock.
+            if self.next_ordered_delivery(webhook_id).await?.is_none() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Runs `work` holding the webhook's queue lock, a session advisory lock
+    /// on a dedicated connection, so it is released even if this process dies.
+    /// Returns `None` without running `work` if another drain or flush holds it.
+    async fn with_queue_lock<T>(
+        &self,
+        webhook_id: Uuid,
+        work: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<Option<T>> {
+        let mut conn = self.database.pool().acquire().await?;
+
+        let locked = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1, hashtext($2::text))")
+            .bind(WEBHOOK_QUEUE_LOCK)
+            .bind(webhook_id)
+            .fetch_one(&mut *conn)
+            .await?;
+        if !locked {
+            return Ok(None);
+        }
+
+        let result = work.await;
+
+        let unlocked = sqlx::query("SELECT pg_advisory_unlock($1, hashtext($2::text))")
+            .bind(WEBHOOK_QUEUE_LOCK)
+            .bind(webhook_id)
+            .execute(&mut *conn)
+            .await;
+        if let Err(e) = unlocked {
+            // Closing the connection releases the lock instead.
+            tracing::warn!(webhook_id = %webhook_id, "Failed to release webhook queue lock: {}", e);
+            drop(conn.detach());
+        }
+
+        result.map(Some)
+    }
+
+    async fn drain_ordered_once(&self, webhook_id: Uuid, paced: bool) -> Result<()> {
+        while let Some(delivery) = self.next_ordered_delivery(webhook_id).await? {
+            let webhook = self.get_webhook(webhook_id).await?;
+            if paced {
+                self.replay_limiter.until_key_ready(&webhook_id).await;
+            }
+
+            let transaction = match delivery.transaction_id {
+                Some(transaction_id) => Some(self.get_transaction(transaction_id).await?),
+                None => None,
+            };
+
+            self.deliver_webhook_async(
+                webhook,
+                &delivery.event,
+                transaction.as_ref(),
+                delivery.data.as_ref(),
+                delivery.id,
+                delivery.sequence,
+            )
+            .await;
+
+            let delivery = self.get_delivery(webhook_id, delivery.id).await?;
+            if matches!(delivery.status, WebhookDeliveryStatus::Retrying) {
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The lowest-sequence outstanding delivery for an active webhook, if it is due.
+    async fn next_ordered_delivery(&self, webhook_id: Uuid) -> Result<Option<WebhookDelivery>> {
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(
+            r#"
+            SELECT wd.id, wd.webhook_id, wd.transaction_id, wd.event, wd.data, wd.sequence, wd.batch_id, wd.status, wd.response_status, wd.response_body, wd.attempts, wd.max_attempts, wd.next_retry_at, wd.created_at, wd.updated_at
+            FROM webhook_deliveries wd
+            JOIN webhooks w ON wd.webhook_id = w.id
+            WHERE wd.webhook_id = $1
+            AND wd.sequence IS NOT NULL
+            AND wd.status IN ('pending', 'retrying')
+            AND w.is_active = true
+            ORDER BY wd.sequence
+            LIMIT 1
+            "#,
+        )
+        .bind(webhook_id)
+        .fetch_optional(self.database.pool())
+        .await?;
+
+        Ok(delivery.filter(|d| d.next_retry_at.is_none_or(|at| at <= Utc::now())))
+    }
+
+    /// Sends any full batches right away and arms a linger timer so a partial
+    /// batch goes out at most `batch_max_linger_ms` later.
+    async fn schedule_batch(&self, webhook: &Webhook, paced: bool) -> Result<()> {
+        self.flush_batches(webhook.id, false, paced).await?;
+
+        if !self.batch_timers.lock().expect("batch timers lock poisoned").insert(webhook.id) {
+            return Ok(());
+        }
+
+        let service = self.clone();
+        let webhook_id = webhook.id;
+        let linger = Duration::from_millis(webhook.batch_max_linger_ms.max(10) as u64);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(linger).await;
+                // Disarm first so events queued during the flush arm a new timer.
+                service.batch_timers.lock().expect("batch timers lock poisoned").remove(&webhook_id);
+
+                match service.flush_batches(webhook_id, true, paced).await {
+                    Ok(true) => return,
+                    // Another flush is running and may leave a partial batch behind; try again.
+                    Ok(false) => {
+                        if !service.batch_timers.lock().expect("batch timers lock poisoned").insert(webhook_id) {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!(webhook_id = %webhook_id, "Failed to flush webhook batch: {}", e);
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Sends a batched webhook's due deliveries, `batch_max_size` at a time.
+    ///
+    /// Without `force` only full batches are sent. Returns `false` without
+    /// sending anything if another flush for the webhook is already running.
+    async fn flush_batches(&self, webhook_id: Uuid, force: bool, paced: bool) -> Result<bool> {
+        let flushed = self
+            .with_queue_lock(webhook_id, self.flush_batches_locked(webhook_id, force, paced))
+            .await?;
+        Ok(flushed.is_some())
+    }
+
+    async fn flush_batches_locked(&self, webhook_id: Uuid, force: bool, paced: bool) -> Result<()> {
+        loop {
+            let webhook = self.get_webhook(webhook_id).await?;
+            if !webhook.is_active {
+                return Ok(());
+            }
+
+            let max_size = webhook.batch_max_size.max(1) as usize;
+            let batch = self.next_batch(&webhook).await?;
+            if batch.is_empty() || (!force && batch.len() < max_size) {
+                return Ok(());
+            }
+
+            if paced {
+                self.replay_limiter.until_key_ready(&webhook_id).await;
+            }
+
+            let full = batch.len() == max_size;
+            self.deliver_batch(&webhook, batch).await?;
+            if !full {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Up to `batch_max_size` queued deliveries that are due, lowest sequence first.
+    async fn next_batch(&self, webhook: &Webhook) -> Result<Vec<WebhookDelivery>> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            r#"
+            SELECT id, webhook_id, transaction_id, event, data, sequence, batch_id, status, response_status, response_body, attempts, max_attempts, next_retry_at, created_at, updated_at
+            FROM webhook_deliveries
+            WHERE webhook_id = $1
+            AND sequence IS NOT NULL
+            AND (status = 'pending' OR (status = 'retrying' AND next_retry_at <= NOW()))
+            ORDER BY sequence
+            LIMIT $2
+            "#,
+        )
+        .bind(webhook.id)
+        .bind(i64::from(webhook.batch_max_size.max(1)))
+        .fetch_all(self.database.pool())
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Sends `deliveries` as one signed request and records the outcome of each.
+    ///
+    /// A non-2xx response fails the whole batch. On a 2xx response every event
+    /// is acknowledged except those the receiver lists in `failed`.
+    async fn deliver_batch(&self, webhook: &Webhook, deliveries: Vec<WebhookDelivery>) -> Result<()> {
+        let batch_id = Uuid::new_v4();
+
+        let mut events = Vec::with_capacity(deliveries.len());
+        for delivery in &deliveries {
+            let transaction = match delivery.transaction_id {
+                Some(transaction_id) => Some(self.get_transaction(transaction_id).await?),
+                None => None,
+            };
+            events.push(build_payload(
+                webhook,
+                &delivery.event,
+                transaction.as_ref(),
+                delivery.data.as_ref(),
+                delivery.id,
+                delivery.sequence,
+            ));
+        }
+
+        let payload = WebhookBatchPayload {
+            batch_id,
+            events,
+            timestamp: Utc::now(),
+        };
+        let body = serde_json::to_vec(&payload).map_err(anyhow::Error::from)?;
+
+        tracing::info!(
+            webhook_id = %webhook.id,
+            batch_id = %batch_id,
+            size = deliveries.len(),
+            "Sending webhook batch"
+        );
+
+        let outcome = match self
+            .post_signed(
+                webhook,
+                body,
+                payload.timestamp.timestamp(),
+                &[("X-Webhook-Batch-Size", deliveries.len().to_string())],
+            )
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                // Count the attempt so the events still run out of retries.
+                let ids: Vec<Uuid> = deliveries.iter().map(|d| d.id).collect();
+                self.record_outcome(&ids, false, &DeliveryOutcome::not_sent(&e), Some(batch_id))
+                    .await?;
+                return Err(e);
+            }
+        };
+
+        let rejected = if outcome.success {
+            outcome
+                .response_body
+                .as_deref()
+                .and_then(|body| serde_json::from_str::<BatchAcknowledgement>(body).ok())
+                .map(|ack| ack.failed)
+                .unwrap_or_default()
+        } else {
+            deliveries.iter().map(|d| d.id).collect()
+        };
+        let (failed, delivered): (Vec<Uuid>, Vec<Uuid>) = deliveries
+            .iter()
+            .map(|d| d.id)
+            .partition(|id| rejected.contains(id));
+
+        self.record_outcome(&delivered, true, &outcome, Some(batch_id)).await?;
+        self.record_outcome(&failed, false, &outcome, Some(batch_id)).await?;
+
+        if let Err(e) = self.record_endpoint_health(webhook, outcome.success).await {
+            tracing::error!(webhook_id = %webhook.id, "Failed to record webhook health: {}", e);
+        }
+
+        Ok(())
+    }
+
+    pub async fn redeliver(
+        &self,
+        account_id: Uuid,
+        webhook_id: Uuid,
+        delivery_id: Uuid,
+    ) -> Result<WebhookDelivery> {
+        let webhook = self.get_account_webhook(account_id, webhook_id).await?;
+        let original = self.get_delivery(webhook.id, delivery_id).await?;
+
+        if self.replay_limiter.check_key(&webhook.id).is_err() {
+            return Err(AppError::RateLimitExceeded);
+        }
+
+        tracing::info!(
+            webhook_id = %webhook.id,
+            delivery_id = %original.id,
+            transaction_id = ?original.transaction_id,
+            "Redelivering webhook"
+        );
+
+        // On an ordered webhook the redelivery joins the back of the queue
+        // under a new sequence number. Reusing the original's would put it at
+        // the head, where a failing attempt would hold up everything behind it.
+        let mut tx = self.database.begin_transaction().await?;
+        let sequence = match (webhook.ordered, original.sequence) {
+            (true, Some(_)) => Some(next_sequence(&mut tx, webhook.id).await?),
+            (_, sequence) => sequence,
+        };
+        let new_delivery_id = enqueue_delivery(
+            &mut tx,
+            webhook.id,
+            original.transaction_id,
+            &original.event,
+            original.data.as_ref(),
+            original.max_attempts,
+            sequence,
+        )
+        .await?;
+        tx.commit().await?;
+
+        if webhook.ordered && sequence.is_some() {
+            self.drain_ordered(webhook.id, false).await?;
+            return self.get_delivery(webhook_id, new_delivery_id).await;
+        }
+
+        let transaction = match original.transaction_id {
+            Some(transaction_id) => Some(self.get_transaction(transaction_id).await?),
+            None => None,
+        };
+
+        self.deliver_webhook_async(
+            webhook,
+            &original.event,
+            transaction.as_ref(),
+            original.data.as_ref(),
+            new_delivery_id,
+            sequence,
+        )
+        .await;
+
+        self.get_delivery(webhook_id, new_delivery_id).await
+    }
+
+    /// Sends a `webhook.test` event to the endpoint and returns the attempt once it completes.
+    ///
+    /// Test deliveries ignore the webhook's subscriptions and are never retried.
+    pub async fn send_test_event(
+        &self,
+        account_id: Uuid,
+        webhook_id: Uuid,
+    ) -> Result<WebhookDelivery> {
+        let webhook = self.get_account_webhook(account_id, webhook_id).await?;
+
+        if self.replay_limiter.check_key(&webhook.id).is_err() {
+            return Err(AppError::RateLimitExceeded);
+        }
+
+        let mut tx = self.database.begin_transaction().await?;
+        let delivery_id = enqueue_delivery(&mut tx, webhook.id, None, events::WEBHOOK_TEST, None, 1, None).await?;
+        tx.commit().await?;
+
+        self.deliver_webhook_async(webhook, events::WEBHOOK_TEST, None, None, delivery_id, None)
+            .await;
+
+        self.get_delivery(webhook_id, delivery_id).await
+    }
+
+    pub async fn replay(
+        &self,
+        account_id: Uuid,
+        webhook_id: Uuid,
+        req: ReplayWebhookRequest,
+    ) -> Result<ReplayWebhookResponse> {
+        req.validate()?;
+
+        let webhook = self.get_account_webhook(account_id, webhook_id).await?;
+
+        let span = tracing::info_span!(
+            "replay_webhook",
+            webhook_id = %webhook.id,
+            from = %req.from,
+            to = %req.to
+        );
+        let _enter = span.enter();
+
+        let mut transactions = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
+            FROM transactions
+            WHERE (account_id = $1 OR counterparty_account_id = $1)
+            AND (created_at > $2 OR (created_at = $2 AND ($4::UUID IS NULL OR id > $4)))
+            AND created_at < $3
+            ORDER BY created_at, id
+            LIMIT $5
+            "#,
+        )
+        .bind(webhook.account_id)
+        .bind(req.from)
+        .bind(req.to)
+        .bind(req.after)
+        .bind(REPLAY_PAGE_SIZE + 1)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let truncated = transactions.len() as i64 > REPLAY_PAGE_SIZE;
+        transactions.truncate(REPLAY_PAGE_SIZE as usize);
+        let (next_from, next_after) = match transactions.last() {
+            Some(last) if truncated => (Some(last.created_at), Some(last.id)),
+            _ => (None, None),
+        };
+
+        let mut tx = self.database.begin_transaction().await?;
+        let mut queued = Vec::new();
+        for transaction in transactions {
+            for event in events::events_for_account(&transaction, webhook.account_id) {
+                if !events::subscription_matches(&webhook.events, event) {
+                    continue;
+                }
+
+                if let Some(ref filter) = req.events {
+                    if !events::subscription_matches(filter, event) {
+                        continue;
+                    }
+                }
+
+                let sequence = next_sequence(&mut tx, webhook.id).await?;
+                let delivery_id = enqueue_delivery(
+                    &mut tx,
+                    webhook.id,
+                    Some(transaction.id),
+                    event,
+                    None,
+                    DEFAULT_MAX_ATTEMPTS,
+                    Some(sequence),
+                )
+                .await?;
+                queued.push((delivery_id, event, sequence, transaction.clone()));
+            }
+        }
+        tx.commit().await?;
+
+        let deliveries_enqueued = queued.len();
+        tracing::info!(deliveries_enqueued, "Webhook replay enqueued");
+
+        let service = self.clone();
+        let replay_webhook = webhook.clone();
+        tokio::spawn(async move {
+            if replay_webhook.ordered {
+                if let Err(e) = service.drain_ordered(replay_webhook.id, true).await {
+                    tracing::error!(webhook_id = %replay_webhook.id, "Failed to drain ordered webhook: {}", e);
+                }
+                return;
+            }
+
+            if replay_webhook.batch_max_size > 1 {
+                if let Err(e) = service.schedule_batch(&replay_webhook, true).await {
+                    tracing::error!(webhook_id = %replay_webhook.id, "Failed to flush webhook batch: {}", e);
+                }
+                return;
+            }
+
+            for (delivery_id, event, sequence, transaction) in queued {
+                service.replay_limiter.until_key_ready(&replay_webhook.id).await;
+                service
+                    .deliver_webhook_async(
+                        replay_webhook.clone(),
+                        event,
+                        Some(&transaction),
+                        None,
+                        delivery_id,
+                        Some(sequence),
+                    )
+                    .await;
+            }
+        });
+
+        Ok(ReplayWebhookResponse {
+            webhook_id: webhook.id,
+            deliveries_enqueued,
+            next_from,
+            next_after,
+        })
+    }
+
+    /// Re-activates a webhook, optionally replaying events missed while it was disabled.
+    pub async fn enable_webhook(
+        &self,
+        account_id: Uuid,
+        webhook_id: Uuid,
+        req: EnableWebhookRequest,
+        ctx: &AuditContext,
+    ) -> Result<EnableWebhookResponse> {
+        let existing = self.get_account_webhook(account_id, webhook_id).await?;
+
+        let mut tx = self.database.begin_transaction().await?;
+
+        let webhook = sqlx::query_as::<_, Webhook>(
+            r#"
+            UPDATE webhooks
+            SET is_active = true, consecutive_failures = 0, disabled_at = NULL, disabled_reason = NULL, updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, account_id, url, events, secret, previous_secret, previous_secret_expires_at, is_active, ordered, batch_max_size, batch_max_linger_ms, consecutive_failures, disabled_at, disabled_reason, created_at, updated_at
+            "#,
+        )
+        .bind(existing.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        audit::record(
+            &mut tx,
+            ctx,
+            AuditRecord {
+                action: audit::WEBHOOK_ENABLED,
+                resource_type: "webhook",
+                resource_id: webhook.id,
+                before: audit::snapshot(&existing),
+                after: audit::snapshot(&webhook),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::info!(webhook_id = %webhook.id, "Webhook enabled");
+
+        let deliveries_enqueued = match (req.replay_backlog, existing.disabled_at) {
+            (true, Some(disabled_at)) => {
+                let to = Utc::now();
+                let mut from = disabled_at;
+                let mut after = None;
+                let mut enqueued = 0;
+                loop {
+                    let page = self
+                        .replay(
+                            account_id,
+                            webhook.id,
+                            ReplayWebhookRequest {
+                                from,
+                                to,
+                                events: None,
+                                after,
+                            },
+                        )
+                        .await?;
+                    enqueued += page.deliveries_enqueued;
+                    match (page.next_from, page.next_after) {
+                        (Some(next_from), Some(next_after)) => {
+                            from = next_from;
+                            after = Some(next_after);
+                        }
+                        _ => break enqueued,
+                    }
+                }
+            }
+            _ => 0,
+        };
+
+        Ok(EnableWebhookResponse {
+            webhook,
+            deliveries_enqueued,
+        })
+    }
 
-            let delivery_id = sqlx::query_scalar::<_, Uuid>(
+    /// Tracks delivery outcomes per endpoint and disables the webhook once it
+    /// exceeds the consecutive-failure or failure-rate limits.
+    async fn record_endpoint_health(&self, webhook: &Webhook, success: bool) -> Result<()> {
+        if success {
+            sqlx::query(
                 r#"
-                INSERT INTO webhook_deliveries (webhook_id, transaction_id, max_attempts)
-                VALUES ($1, $2, $3)
-                RETURNING id
+                UPDATE webhooks
+                SET consecutive_failures = 0
+                WHERE id = $1 AND consecutive_failures > 0
                 "#,
             )
             .bind(webhook.id)
-            .bind(transaction.id)
-            .bind(3)
-            .fetch_one(&*self.database.pool())
+            .execute(self.database.pool())
             .await?;
+            return Ok(());
+        }
+
+        let consecutive_failures = sqlx::query_scalar::<_, i32>(
+            r#"
+            UPDATE webhooks
+            SET consecutive_failures = consecutive_failures + 1
+            WHERE id = $1
+            RETURNING consecutive_failures
+            "#,
+        )
+        .bind(webhook.id)
+        .fetch_one(self.database.pool())
+        .await?;
 
-            self.deliver_webhook_async(webhook, transaction, delivery_id).await;
+        let policy = &self.circuit_breaker;
+        let reason = if consecutive_failures >= policy.max_consecutive_failures {
+            Some(format!("{} consecutive delivery failures", consecutive_failures))
+        } else {
+            let (failed, total) = sqlx::query_as::<_, (i64, i64)>(
+                r#"
+                SELECT COUNT(*) FILTER (WHERE status IN ('retrying', 'failed')), COUNT(*)
+                FROM webhook_deliveries
+                WHERE webhook_id = $1
+                AND event <> $2
+                AND attempts > 0
+                AND updated_at >= NOW() - make_interval(mins => $3)
+                "#,
+            )
+            .bind(webhook.id)
+            .bind(events::WEBHOOK_TEST)
+            .bind(policy.window_minutes)
+            .fetch_one(self.database.pool())
+            .await?;
+
+            (total >= policy.min_deliveries
+                && failed as f64 / total as f64 >= policy.failure_rate_threshold)
+                .then(|| {
+                    format!(
+                        "{} of {} deliveries failed in the last {} minutes",
+                        failed, total, policy.window_minutes
+                    )
+                })
+        };
+
+        if let Some(reason) = reason {
+            self.disable_webhook(webhook, &reason).await?;
         }
 
         Ok(())
     }
 
+    async fn disable_webhook(&self, webhook: &Webhook, reason: &str) -> Result<()> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        let disabled = sqlx::query_as::<_, Webhook>(
+            r#"
+            UPDATE webhooks
+            SET is_active = false, disabled_at = NOW(), disabled_reason = $1, updated_at = NOW()
+            WHERE id = $2 AND is_active = true
+            RETURNING id, account_id, url, events, secret, previous_secret, previous_secret_expires_at, is_active, ordered, batch_max_size, batch_max_linger_ms, consecutive_failures, disabled_at, disabled_reason, created_at, updated_at
+            "#,
+        )
+        .bind(reason)
+        .bind(webhook.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        // Another delivery already tripped the breaker.
+        let Some(disabled) = disabled else {
+            return Ok(());
+        };
+
+        audit::record(
+            &mut tx,
+            &AuditContext::system(),
+            AuditRecord {
+                action: audit::WEBHOOK_DISABLED,
+                resource_type: "webhook",
+                resource_id: disabled.id,
+                before: audit::snapshot(webhook),
+                after: audit::snapshot(&disabled),
+            },
+        )
+        .await?;
+
+        let data = json!({
+            "webhook_id": webhook.id,
+            "url": webhook.url,
+            "reason": reason,
+        });
+
+        sqlx::query(
+            r#"
+            INSERT INTO account_notifications (account_id, event, data)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(webhook.account_id)
+        .bind(events::WEBHOOK_DISABLED)
+        .bind(&data)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        tracing::warn!(
+            webhook_id = %webhook.id,
+            account_id = %webhook.account_id,
+            reason = %reason,
+            "Webhook disabled after repeated delivery failures"
+        );
+
+        // The disabled endpoint is skipped; any other active webhook of the
+        // account subscribed to the event hears about it.
+        self.deliver_event(webhook.account_id, events::WEBHOOK_DISABLED, None, data)
+            .await
+    }
+
+    async fn check_url_policy(&self, url: &str) -> Result<()> {
+        self.url_policy
+            .check_registration(url)
+            .await
+            .map_err(|e| AppError::WebhookUrlNotAllowed(e.to_string()))
+    }
+
+    pub async fn get_account_webhook(&self, account_id: Uuid, webhook_id: Uuid) -> Result<Webhook> {
+        let webhook = self.get_webhook(webhook_id).await?;
+        if webhook.account_id != account_id {
+            return Err(AppError::WebhookNotFound {
+                webhook_id: webhook_id.to_string(),
+            });
+        }
+        Ok(webhook)
+    }
+
+    async fn get_delivery(&self, webhook_id: Uuid, delivery_id: Uuid) -> Result<WebhookDelivery> {
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(
+            r#"
+            SELECT id, webhook_id, transaction_id, event, data, sequence, batch_id, status, response_status, response_body, attempts, max_attempts, next_retry_at, created_at, updated_at
+            FROM webhook_deliveries
+            WHERE id = $1 AND webhook_id = $2
+            "#,
+        )
+        .bind(delivery_id)
+        .bind(webhook_id)
+        .fetch_optional(self.database.pool())
+        .await?
+        .ok_or_else(|| AppError::WebhookDeliveryNotFound {
+            delivery_id: delivery_id.to_string(),
+        })?;
+
+        Ok(delivery)
+    }
+
+    async fn get_transaction(&self, transaction_id: Uuid) -> Result<Transaction> {
+        let transaction = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, parent_transaction_id, created_at, updated_at
+            FROM transactions
+            WHERE id = $1
+            "#,
+        )
+        .bind(transaction_id)
+        .fetch_one(self.database.pool())
+        .await?;
+
+        Ok(transaction)
+    }
+
     async fn deliver_webhook_async(
         &self,
         webhook: Webhook,
-        transaction: &Transaction,
+        event: &str,
+        transaction: Option<&Transaction>,
+        data: Option<&serde_json::Value>,
         delivery_id: Uuid,
+        sequence: Option<i64>,
     ) {
-        let payload = WebhookPayload {
-            event: match transaction.r#type.as_str() {
-                "credit" => "transaction.credit",
-                "debit" => "transaction.debit",
-                "transfer" => "transaction.transfer",
-                _ => "transaction.unknown",
+        let payload = build_payload(&webhook, event, transaction, data, delivery_id, sequence);
+
+        // Sign the exact bytes we send so receivers can verify the raw body.
+        let sent = match serde_json::to_vec(&payload) {
+            Ok(body) => self
+                .post_signed(
+                    &webhook,
+                    body,
+                    payload.timestamp.timestamp(),
+                    &[("X-Webhook-Event", payload.event.clone())],
+                )
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Failed to serialize webhook payload: {}", e)),
+        };
+
+        // A request that couldn't be prepared still counts as an attempt, so
+        // the delivery gives up after max_attempts instead of being retried
+        // forever; it says nothing about the endpoint's health, though.
+        let (outcome, reached_endpoint) = match sent {
+            Ok(outcome) => (outcome, true),
+            Err(e) => {
+                tracing::error!(webhook_id = %webhook.id, delivery_id = %delivery_id, "Failed to send webhook: {}", e);
+                (DeliveryOutcome::not_sent(e), false)
             }
-            .to_string(),
-            transaction: transaction.clone(),
-            timestamp: Utc::now(),
-            signature: self.generate_signature(&webhook.secret, &json!(transaction).to_string()),
         };
 
-        let response = self
-            .client
-            .post(&webhook.url)
-            .json(&payload)
-            .header("X-Webhook-Signature", &payload.signature)
-            .header("X-Webhook-Event", &payload.event)
-            .send()
-            .await;
+        if let Err(e) = self
+            .record_outcome(&[delivery_id], outcome.success, &outcome, None)
+            .await
+        {
+            tracing::error!(delivery_id = %delivery_id, "Failed to record webhook delivery: {}", e);
+        }
+
+        // Test pings are on-demand and shouldn't count towards endpoint health.
+        if reached_endpoint && event != events::WEBHOOK_TEST {
+            if let Err(e) = self.record_endpoint_health(&webhook, outcome.success).await {
+                tracing::error!(webhook_id = %webhook.id, "Failed to record webhook health: {}", e);
+            }
+        }
+    }
 
-        match response {
+    /// Signs `body` with every active secret and POSTs it to the webhook URL.
+    ///
+    /// Transport errors and non-2xx responses are reported as an unsuccessful
+    /// outcome; an `Err` means the request could not be prepared at all.
+    async fn post_signed(
+        &self,
+        webhook: &Webhook,
+        body: Vec<u8>,
+        timestamp: i64,
+        headers: &[(&'static str, String)],
+    ) -> Result<DeliveryOutcome> {
+        let secrets = self.signing_secrets(webhook)?;
+        let secrets: Vec<&str> = secrets.iter().map(String::as_str).collect();
+        let signature = webhook_signature::signature_header(&secrets, timestamp, &body);
+
+        let started = Instant::now();
+        let response = match self.url_policy.check_url(&webhook.url) {
+            Ok(url) => {
+                let mut request = self
+                    .client
+                    .post(url)
+                    .header(CONTENT_TYPE, "application/json")
+                    .header(webhook_signature::SIGNATURE_HEADER, signature);
+                for (name, value) in headers {
+                    request = request.header(*name, value);
+                }
+                request.body(body).send().await.map_err(|e| error_chain(&e))
+            }
+            Err(e) => Err(e.to_string()),
+        };
+
+        let outcome = match response {
             Ok(resp) => {
-                let status = resp.status().as_u16() as i32;
+                let status = resp.status();
                 let body = resp.text().await.unwrap_or_default();
-
-                sqlx::query(
-                    r#"
-                    UPDATE webhook_deliveries
-                    SET status = 'delivered', response_status = $1, response_body = $2, attempts = attempts + 1, updated_at = NOW()
-                    WHERE id = $3
-                    "#,
-                )
-                .bind(status)
-                .bind(body)
-                .bind(delivery_id)
-                .execute(&*self.database.pool())
-                .await
-                .ok();
+                DeliveryOutcome {
+                    success: status.is_success(),
+                    response_status: Some(status.as_u16() as i32),
+                    response_body: Some(body),
+                }
             }
-            Err(_) => {
-                let next_retry = Utc::now() + chrono::Duration::minutes(5);
-
-                sqlx::query(
-                    r#"
-                    UPDATE webhook_deliveries
-                    SET status = 'retrying', attempts = attempts + 1, next_retry_at = $1, updated_at = NOW()
-                    WHERE id = $2
-                    "#,
-                )
-                .bind(next_retry)
-                .bind(delivery_id)
-                .execute(&*self.database.pool())
-                .await
-                .ok();
+            Err(e) => {
+                tracing::warn!(webhook_id = %webhook.id, "Webhook request failed: {}", e);
+                DeliveryOutcome {
+                    success: false,
+                    response_status: None,
+                    response_body: Some(e),
+                }
             }
-        }
+        };
+
+        crate::metrics::record_webhook_delivered(outcome.success, started.elapsed().as_secs_f64());
+
+        Ok(outcome)
     }
 
-    fn generate_signature(&self, secret: &str, payload: &str) -> String {
-        let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
-            .expect("HMAC can take key of any size");
-        mac.update(payload.as_bytes());
-        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    /// Marks deliveries as delivered, or as retrying/failed depending on their remaining attempts.
+    async fn record_outcome(
+        &self,
+        delivery_ids: &[Uuid],
+        delivered: bool,
+        outcome: &DeliveryOutcome,
+        batch_id: Option<Uuid>,
+    ) -> Result<()> {
+        if delivery_ids.is_empty() {
+            return Ok(());
+        }
+
+        if delivered {
+            sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET status = 'delivered', response_status = $1, response_body = $2, batch_id = $3, attempts = attempts + 1, updated_at = NOW()
+                WHERE id = ANY($4)
+                "#,
+            )
+            .bind(outcome.response_status)
+            .bind(&outcome.response_body)
+            .bind(batch_id)
+            .bind(delivery_ids)
+            .execute(self.database.pool())
+            .await?;
+        } else {
+            let next_retry = Utc::now() + chrono::Duration::minutes(5);
+
+            // Non-2xx responses are failures too; give up once attempts are exhausted.
+            sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET status = CASE
+                        WHEN attempts + 1 >= max_attempts THEN 'failed'::webhook_delivery_status
+                        ELSE 'retrying'::webhook_delivery_status
+                    END,
+                    response_status = $1, response_body = $2, batch_id = $3, attempts = attempts + 1, next_retry_at = $4, updated_at = NOW()
+                WHERE id = ANY($5)
+                "#,
+            )
+            .bind(outcome.response_status)
+            .bind(&outcome.response_body)
+            .bind(batch_id)
+            .bind(next_retry)
+            .bind(delivery_ids)
+            .execute(self.database.pool())
+            .await?;
+        }
+
+        Ok(())
     }
 
     pub async fn retry_failed_deliveries(&self) -> Result<()> {
@@ -244,40 +1468,300 @@ impl WebhookService {
         struct DeliveryRow {
             id: Uuid,
             webhook_id: Uuid,
-            transaction_id: Uuid,
+            transaction_id: Option<Uuid>,
+            event: String,
+            data: Option<serde_json::Value>,
+            sequence: Option<i64>,
         }
 
         let deliveries = sqlx::query_as::<_, DeliveryRow>(
             r#"
-            SELECT wd.id, wd.webhook_id, wd.transaction_id
+            SELECT wd.id, wd.webhook_id, wd.transaction_id, wd.event, wd.data, wd.sequence
             FROM webhook_deliveries wd
             JOIN webhooks w ON wd.webhook_id = w.id
-            WHERE wd.status = 'retrying' 
-            AND wd.next_retry_at <= NOW()
+            WHERE (
+                (wd.status = 'retrying' AND wd.next_retry_at <= NOW())
+                -- Committed but never sent, e.g. the process stopped before dispatching.
+                OR (wd.status = 'pending' AND wd.attempts = 0 AND wd.created_at <= NOW() - INTERVAL '5 minutes')
+            )
             AND wd.attempts < wd.max_attempts
             AND w.is_active = true
+            AND w.ordered = false
+            AND w.batch_max_size = 1
             "#,
         )
-        .fetch_all(&*self.database.pool())
+        .fetch_all(self.database.pool())
         .await?;
 
         for delivery in deliveries {
             let webhook = self.get_webhook(delivery.webhook_id).await?;
-            let transaction = sqlx::query_as::<_, Transaction>(
-                r#"
-                SELECT id, account_id, counterparty_account_id, type, amount, description, status, idempotency_key, created_at, updated_at
-                FROM transactions
-                WHERE id = $1
-                "#,
+            let transaction = match delivery.transaction_id {
+                Some(transaction_id) => Some(self.get_transaction(transaction_id).await?),
+                None => None,
+            };
+
+            self.deliver_webhook_async(
+                webhook,
+                &delivery.event,
+                transaction.as_ref(),
+                delivery.data.as_ref(),
+                delivery.id,
+                delivery.sequence,
             )
-            .bind(delivery.transaction_id)
-            .fetch_one(&*self.database.pool())
-            .await?;
+            .await;
+        }
 
-            self.deliver_webhook_async(webhook, &transaction, delivery.id)
-                .await;
+        // Ordered webhooks retry from the head of their queue so nothing overtakes it.
+        let ordered_webhooks = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT DISTINCT wd.webhook_id
+            FROM webhook_deliveries wd
+            JOIN webhooks w ON wd.webhook_id = w.id
+            WHERE wd.status IN ('pending', 'retrying')
+            AND wd.sequence IS NOT NULL
+            AND COALESCE(wd.next_retry_at, wd.created_at) <= NOW()
+            AND w.is_active = true
+            AND w.ordered = true
+            "#,
+        )
+        .fetch_all(self.database.pool())
+        .await?;
+
+        for webhook_id in ordered_webhooks {
+            self.drain_ordered(webhook_id, false).await?;
+        }
+
+        // Batched webhooks resend failed events as part of their next batch.
+        let batched_webhooks = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT DISTINCT wd.webhook_id
+            FROM webhook_deliveries wd
+            JOIN webhooks w ON wd.webhook_id = w.id
+            WHERE (wd.status = 'pending' OR (wd.status = 'retrying' AND wd.next_retry_at <= NOW()))
+            AND wd.sequence IS NOT NULL
+            AND w.is_active = true
+            AND w.batch_max_size > 1
+            "#,
+        )
+        .fetch_all(self.database.pool())
+        .await?;
+
+        for webhook_id in batched_webhooks {
+            self.flush_batches(webhook_id, true, false).await?;
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Queues the webhook deliveries for `transaction` within `tx`, the database
+/// transaction that writes it. Sequence numbers are allocated under a row lock
+/// held until commit, so per webhook they follow commit order, and the
+/// deliveries exist exactly when the transaction does. Once committed,
+/// [`WebhookService::deliver_webhook`] sends them.
+pub async fn enqueue_transaction(tx: &mut sqlx::Transaction<'_, Postgres>, transaction: &Transaction) -> Result<()> {
+    let mut notified = vec![(
+        transaction.account_id,
+        events::events_for_account(transaction, transaction.account_id),
+    )];
+    if let Some(counterparty_id) = transaction.counterparty_account_id.filter(|id| *id != transaction.account_id) {
+        notified.push((counterparty_id, events::events_for_account(transaction, counterparty_id)));
+    }
+
+    enqueue_for_accounts(tx, &notified, Some(transaction), None).await?;
+    Ok(())
+}
+
+/// Queues `event` with `data` for `account_id`'s webhooks within `tx`, as
+/// part of `transaction`, so [`WebhookService::deliver_webhook`] sends it
+/// along with the transaction's own events.
+pub async fn enqueue_transaction_event(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    account_id: Uuid,
+    event: &'static str,
+    transaction: &Transaction,
+    data: &serde_json::Value,
+) -> Result<()> {
+    enqueue_for_accounts(tx, &[(account_id, vec![event])], Some(transaction), Some(data)).await?;
+    Ok(())
+}
+
+/// Records a delivery of each event to each of the account's active webhooks
+/// subscribed to it, allocating sequence numbers in webhook id order so
+/// concurrent callers can't deadlock on them.
+async fn enqueue_for_accounts(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    notified: &[(Uuid, Vec<&'static str>)],
+    transaction: Option<&Transaction>,
+    data: Option<&serde_json::Value>,
+) -> Result<Vec<QueuedDelivery>> {
+    let account_ids: Vec<Uuid> = notified
+        .iter()
+        .filter(|(_, account_events)| !account_events.is_empty())
+        .map(|(account_id, _)| *account_id)
+        .collect();
+    if account_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let webhooks = sqlx::query_as::<_, Webhook>(
+        r#"
+        SELECT id, account_id, url, events, secret, previous_secret, previous_secret_expires_at, is_active, ordered, batch_max_size, batch_max_linger_ms, consecutive_failures, disabled_at, disabled_reason, created_at, updated_at
+        FROM webhooks
+        WHERE account_id = ANY($1) AND is_active = true
+        ORDER BY id
+        "#,
+    )
+    .bind(&account_ids)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let mut queued = Vec::new();
+    for webhook in webhooks {
+        let account_events = notified
+            .iter()
+            .filter(|(account_id, _)| *account_id == webhook.account_id)
+            .flat_map(|(_, account_events)| account_events);
+        for &event in account_events {
+            if !events::subscription_matches(&webhook.events, event) {
+                continue;
+            }
+
+            let sequence = next_sequence(tx, webhook.id).await?;
+            let delivery_id = enqueue_delivery(
+                tx,
+                webhook.id,
+                transaction.map(|t| t.id),
+                event,
+                data,
+                DEFAULT_MAX_ATTEMPTS,
+                Some(sequence),
+            )
+            .await?;
+
+            queued.push(QueuedDelivery {
+                webhook: webhook.clone(),
+                event: event.to_string(),
+                data: data.cloned(),
+                delivery_id,
+                sequence,
+            });
+        }
+    }
+
+    Ok(queued)
+}
+
+async fn enqueue_delivery(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    webhook_id: Uuid,
+    transaction_id: Option<Uuid>,
+    event: &str,
+    data: Option<&serde_json::Value>,
+    max_attempts: i32,
+    sequence: Option<i64>,
+) -> Result<Uuid> {
+    let delivery_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO webhook_deliveries (webhook_id, transaction_id, event, data, max_attempts, sequence)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+    )
+    .bind(webhook_id)
+    .bind(transaction_id)
+    .bind(event)
+    .bind(data)
+    .bind(max_attempts)
+    .bind(sequence)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(delivery_id)
+}
+
+/// Allocates the next per-webhook event sequence number, starting at 1. The
+/// counter row stays locked until `tx` ends, so a later caller's number can't
+/// become visible before this one's.
+async fn next_sequence(tx: &mut sqlx::Transaction<'_, Postgres>, webhook_id: Uuid) -> Result<i64> {
+    let sequence = sqlx::query_scalar::<_, i64>(
+        r#"
+        INSERT INTO webhook_sequences (webhook_id, last_sequence)
+        VALUES ($1, 1)
+        ON CONFLICT (webhook_id) DO UPDATE
+        SET last_sequence = webhook_sequences.last_sequence + 1
+        RETURNING last_sequence
+        "#,
+    )
+    .bind(webhook_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(sequence)
+}
+
+/// Ordered delivery sends one event at a time, so it cannot be combined with batching.
+fn validate_delivery_mode(ordered: bool, batch_max_size: i32) -> Result<()> {
+    if ordered && batch_max_size > 1 {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "batch_max_size",
+            ValidationError::new("batching_not_supported_for_ordered_webhooks"),
+        );
+        return Err(errors.into());
+    }
+    Ok(())
+}
+
+fn build_payload(
+    webhook: &Webhook,
+    event: &str,
+    transaction: Option<&Transaction>,
+    data: Option<&serde_json::Value>,
+    delivery_id: Uuid,
+    sequence: Option<i64>,
+) -> WebhookPayload {
+    let mut payload = match transaction {
+        Some(transaction) => events::transaction_payload(event, transaction),
+        None => WebhookPayload {
+            event: event.to_string(),
+            delivery_id: None,
+            sequence: None,
+            transaction: None,
+            transfer: None,
+            data: None,
+            timestamp: Utc::now(),
+        },
+    };
+
+    if event == events::WEBHOOK_TEST {
+        payload.data = Some(json!({
+            "webhook_id": webhook.id,
+            "message": "This is a test event sent to verify your webhook endpoint",
+        }));
+    }
+    if let Some(data) = data {
+        payload.data = Some(data.clone());
+    }
+
+    payload.delivery_id = Some(delivery_id);
+    payload.sequence = sequence;
+    payload
+}
+
+/// Formats an error with its sources, so e.g. a blocked DNS resolution is visible
+/// in the delivery's `response_body` rather than just "error sending request".
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(e) = source {
+        let text = e.to_string();
+        // hyper and reqwest already inline some of their sources.
+        if !message.contains(&text) {
+            message.push_str(": ");
+            message.push_str(&text);
+        }
+        source = e.source();
+    }
+    message
+}
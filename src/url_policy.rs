@@ -0,0 +1,424 @@
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    redirect, Url,
+};
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+use thiserror::Error;
+
+use crate::config::Config;
+
+#[derive(Error, Debug)]
+pub enum UrlPolicyError {
+    #[error("invalid URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("scheme '{0}' is not allowed")]
+    SchemeNotAllowed(String),
+
+    #[error("port {0} is not allowed")]
+    PortBlocked(u16),
+
+    #[error("host '{0}' resolves to a private or reserved address")]
+    HostNotAllowed(String),
+
+    #[error("host '{0}' could not be resolved")]
+    ResolutionFailed(String),
+
+    #[error("more than {0} redirects")]
+    TooManyRedirects(usize),
+}
+
+/// Which webhook URLs we are willing to send requests to.
+///
+/// Applied when a webhook is registered and again on every delivery: the
+/// delivery client resolves hostnames through [`PolicyResolver`] and checks
+/// every redirect hop, so DNS changes after registration cannot be used to
+/// reach internal hosts.
+#[derive(Debug, Clone)]
+pub struct UrlPolicy {
+    require_https: bool,
+    blocked_ports: Vec<u16>,
+    allowed_hosts: Vec<String>,
+    max_redirects: usize,
+}
+
+impl UrlPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            require_https: config.webhook_require_https,
+            blocked_ports: config.webhook_blocked_ports.clone(),
+            allowed_hosts: config
+                .webhook_allowed_hosts
+                .iter()
+                .map(|h| h.to_ascii_lowercase())
+                .collect(),
+            max_redirects: config.webhook_max_redirects,
+        }
+    }
+
+    /// Checks scheme, port and IP-literal hosts without resolving DNS.
+    pub fn check_url(&self, url: &str) -> Result<Url, UrlPolicyError> {
+        let url = Url::parse(url).map_err(|e| UrlPolicyError::InvalidUrl(e.to_string()))?;
+
+        match url.scheme() {
+            "https" => {}
+            "http" if !self.require_https => {}
+            scheme => return Err(UrlPolicyError::SchemeNotAllowed(scheme.to_string())),
+        }
+
+        let host = url
+            .host_str()
+            .ok_or_else(|| UrlPolicyError::InvalidUrl("missing host".to_string()))?;
+
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| UrlPolicyError::InvalidUrl("missing port".to_string()))?;
+        if self.blocked_ports.contains(&port) {
+            return Err(UrlPolicyError::PortBlocked(port));
+        }
+
+        if !self.is_host_allowlisted(host) {
+            let literal = host.trim_start_matches('[').trim_end_matches(']');
+            if let Ok(ip) = literal.parse::<IpAddr>() {
+                if !is_public_ip(ip) {
+                    return Err(UrlPolicyError::HostNotAllowed(host.to_string()));
+                }
+            }
+        }
+
+        Ok(url)
+    }
+
+    /// Full registration-time check: [`UrlPolicy::check_url`] plus resolving the
+    /// host and rejecting it if any address is private or reserved.
+    pub async fn check_registration(&self, url: &str) -> Result<(), UrlPolicyError> {
+        let url = self.check_url(url)?;
+        let host = url.host_str().unwrap_or_default();
+
+        if self.is_host_allowlisted(host) {
+            return Ok(());
+        }
+
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| UrlPolicyError::ResolutionFailed(host.to_string()))?
+            .collect();
+
+        if addrs.is_empty() {
+            return Err(UrlPolicyError::ResolutionFailed(host.to_string()));
+        }
+        if addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+            return Err(UrlPolicyError::HostNotAllowed(host.to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub fn redirect_policy(self: &Arc<Self>) -> redirect::Policy {
+        let policy = self.clone();
+        redirect::Policy::custom(move |attempt| {
+            match policy.check_redirect(attempt.url(), attempt.previous().len()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        })
+    }
+
+    /// Whether to follow a redirect to `url`, `hops` requests into the chain.
+    fn check_redirect(&self, url: &Url, hops: usize) -> Result<(), UrlPolicyError> {
+        if hops > self.max_redirects {
+            return Err(UrlPolicyError::TooManyRedirects(self.max_redirects));
+        }
+        self.check_url(url.as_str()).map(|_| ())
+    }
+
+    fn is_host_allowlisted(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.allowed_hosts.contains(&host)
+    }
+}
+
+/// DNS resolver for the delivery client that drops private and reserved addresses.
+pub struct PolicyResolver {
+    policy: Arc<UrlPolicy>,
+}
+
+impl PolicyResolver {
+    pub fn new(policy: Arc<UrlPolicy>) -> Self {
+        Self { policy }
+    }
+}
+
+impl Resolve for PolicyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let allowlisted = policy.is_host_allowlisted(&host);
+
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| allowlisted || is_public_ip(addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(Box::new(UrlPolicyError::HostNotAllowed(host)) as _);
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether `ip` is publicly routable, i.e. not loopback, private, link-local,
+/// shared (CGNAT), multicast, documentation or otherwise reserved. IPv4
+/// addresses embedded in IPv6 (mapped, NAT64, 6to4) are judged as IPv4.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => is_public_ipv6(ip),
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || a >= 240
+        || (a == 100 && (b & 0xc0) == 64)
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (b & 0xfe) == 18))
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(v4);
+    }
+
+    let segments = ip.segments();
+
+    // NAT64 (64:ff9b::/96) embeds an IPv4 address in the last 32 bits.
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        return is_public_ipv4(embedded_ipv4(segments[6], segments[7]));
+    }
+
+    // 6to4 (2002::/16) embeds the relay's IPv4 address in bits 16-48.
+    if segments[0] == 0x2002 {
+        return is_public_ipv4(embedded_ipv4(segments[1], segments[2]));
+    }
+
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Deprecated IPv4-compatible addresses (::a.b.c.d)
+        || segments[..6] == [0; 6]
+        || (segments[0] & 0xfe00) == 0xfc00
+        || (segments[0] & 0xffc0) == 0xfe80
+        // Teredo (2001::/32) tunnels to an obfuscated IPv4 address
+        || (segments[0] == 0x2001 && segments[1] == 0)
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+}
+
+fn embedded_ipv4(hi: u16, lo: u16) -> Ipv4Addr {
+    Ipv4Addr::new((hi >> 8) as u8, hi as u8, (lo >> 8) as u8, lo as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> UrlPolicy {
+        UrlPolicy {
+            require_https: false,
+            blocked_ports: vec![22, 25, 5432],
+            allowed_hosts: vec!["localhost".to_string()],
+            max_redirects: 3,
+        }
+    }
+
+    #[test]
+    fn classifies_ip_addresses() {
+        let cases = [
+            // IPv4
+            ("93.184.216.34", true),
+            ("8.8.8.8", true),
+            ("127.0.0.1", false),
+            ("127.255.255.254", false),
+            ("10.0.0.1", false),
+            ("172.16.0.1", false),
+            ("172.31.255.255", false),
+            ("172.32.0.1", true),
+            ("192.168.1.1", false),
+            ("169.254.169.254", false),
+            ("100.64.0.1", false),
+            ("100.127.255.255", false),
+            ("100.128.0.1", true),
+            ("0.0.0.0", false),
+            ("0.1.2.3", false),
+            ("255.255.255.255", false),
+            ("224.0.0.1", false),
+            ("240.0.0.1", false),
+            ("192.0.0.8", false),
+            ("192.0.2.1", false),
+            ("198.18.0.1", false),
+            ("198.19.255.255", false),
+            ("198.20.0.1", true),
+            // IPv6
+            ("2606:4700:4700::1111", true),
+            ("::1", false),
+            ("::", false),
+            ("fc00::1", false),
+            ("fd12:3456::1", false),
+            ("fe80::1", false),
+            ("ff02::1", false),
+            ("2001:db8::1", false),
+            ("2001:0:4136:e378::1", false),
+            ("::127.0.0.1", false),
+            ("::a00:1", false),
+            // IPv4-mapped
+            ("::ffff:127.0.0.1", false),
+            ("::ffff:10.0.0.1", false),
+            ("::ffff:169.254.169.254", false),
+            ("::ffff:93.184.216.34", true),
+            // NAT64
+            ("64:ff9b::7f00:1", false),
+            ("64:ff9b::a9fe:a9fe", false),
+            ("64:ff9b::5db8:d822", true),
+            // 6to4
+            ("2002:7f00:1::1", false),
+            ("2002:a00:1::", false),
+            ("2002:c0a8:101::1", false),
+            ("2002:5db8:d822::1", true),
+        ];
+
+        for (ip, public) in cases {
+            assert_eq!(is_public_ip(ip.parse().unwrap()), public, "{}", ip);
+        }
+    }
+
+    #[test]
+    fn checks_urls() {
+        let policy = policy();
+        let https_only = UrlPolicy {
+            require_https: true,
+            ..policy.clone()
+        };
+
+        let cases: [(&UrlPolicy, &str, Result<(), &str>); 17] = [
+            (&policy, "https://example.com/hook", Ok(())),
+            (&policy, "http://example.com/hook", Ok(())),
+            (&policy, "https://93.184.216.34/hook", Ok(())),
+            (&policy, "https://example.com:8443/hook", Ok(())),
+            (&policy, "ftp://example.com/hook", Err("scheme")),
+            (&policy, "file:///etc/passwd", Err("scheme")),
+            (&policy, "not a url", Err("invalid")),
+            (&policy, "https://example.com:22/hook", Err("port")),
+            (&policy, "http://example.com:5432/", Err("port")),
+            (&policy, "http://127.0.0.1/hook", Err("host")),
+            (&policy, "http://10.1.2.3:8080/hook", Err("host")),
+            (&policy, "http://169.254.169.254/latest/meta-data", Err("host")),
+            (&policy, "http://[::1]/hook", Err("host")),
+            (&policy, "http://[::ffff:127.0.0.1]/hook", Err("host")),
+            (&policy, "http://localhost:8080/hook", Ok(())),
+            (&https_only, "http://example.com/hook", Err("scheme")),
+            (&https_only, "https://example.com/hook", Ok(())),
+        ];
+
+        for (policy, url, expected) in cases {
+            let result = policy.check_url(url).map(|_| ()).map_err(|e| match e {
+                UrlPolicyError::InvalidUrl(_) => "invalid",
+                UrlPolicyError::SchemeNotAllowed(_) => "scheme",
+                UrlPolicyError::PortBlocked(_) => "port",
+                UrlPolicyError::HostNotAllowed(_) => "host",
+                UrlPolicyError::ResolutionFailed(_) => "resolution",
+                UrlPolicyError::TooManyRedirects(_) => "redirects",
+            });
+            assert_eq!(result, expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn checks_redirect_targets_and_hops() {
+        let policy = UrlPolicy {
+            require_https: true,
+            ..policy()
+        };
+        let url = |u: &str| Url::parse(u).unwrap();
+
+        assert!(policy.check_redirect(&url("https://example.com/next"), 1).is_ok());
+        assert!(policy.check_redirect(&url("https://example.com/next"), 3).is_ok());
+        assert!(matches!(
+            policy.check_redirect(&url("https://example.com/next"), 4),
+            Err(UrlPolicyError::TooManyRedirects(3))
+        ));
+        assert!(matches!(
+            policy.check_redirect(&url("https://169.254.169.254/"), 1),
+            Err(UrlPolicyError::HostNotAllowed(_))
+        ));
+        assert!(matches!(
+            policy.check_redirect(&url("http://example.com/"), 1),
+            Err(UrlPolicyError::SchemeNotAllowed(_))
+        ));
+        assert!(matches!(
+            policy.check_redirect(&url("https://example.com:25/"), 1),
+            Err(UrlPolicyError::PortBlocked(25))
+        ));
+    }
+
+    /// Serves `302 Found` to each connection, pointing at `location`.
+    async fn redirecting_server(location: &'static str) -> SocketAddr {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let location = location.replace("{addr}", &addr.to_string());
+                let response = format!(
+                    "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    location
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn delivery_client_refuses_unsafe_redirects() {
+        let policy = Arc::new(UrlPolicy {
+            allowed_hosts: vec!["127.0.0.1".to_string()],
+            ..policy()
+        });
+        let client = reqwest::Client::builder()
+            .redirect(policy.redirect_policy())
+            .build()
+            .unwrap();
+
+        for location in ["http://10.0.0.1/internal", "http://[::1]/", "http://127.0.0.1:22/"] {
+            let addr = redirecting_server(location).await;
+            let err = client.get(format!("http://{}/hook", addr)).send().await.unwrap_err();
+            assert!(err.is_redirect(), "{}: {}", location, err);
+        }
+
+        let addr = redirecting_server("http://{addr}/again").await;
+        let err = client.get(format!("http://{}/hook", addr)).send().await.unwrap_err();
+        assert!(err.is_redirect());
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "more than 3 redirects");
+    }
+}
@@ -0,0 +1,243 @@
+//! Signing and verification of transaction-service webhook deliveries.
+//!
+//! Every delivery carries a header of the form
+//!
+//! ```text
+//! X-Webhook-Signature: t=1700000000,v1=<hex hmac>[,v1=<hex hmac>...]
+//! ```
+//!
+//! Each `v1` value is HMAC-SHA256 over `"<t>.<raw request body>"`, keyed with
+//! one of the endpoint's active secrets. While a secret is being rotated the
+//! header carries one `v1` entry per active secret, so a receiver holding
+//! either the old or the new secret can verify the request.
+//!
+//! Receivers must verify against the raw body bytes exactly as received,
+//! before any JSON parsing, and should reject requests whose timestamp is
+//! further than [`DEFAULT_TOLERANCE_SECS`] from their own clock to limit
+//! replay of captured requests.
+//!
+//! # Test vectors
+//!
+//! | secret              | t            | body                                             | v1 |
+//! |---------------------|--------------|--------------------------------------------------|----|
+//! | `whsec_test_secret` | `1700000000` | `{"event":"transaction.credit","id":"evt_1"}`    | `7878dc8941e6a3e39552c63f596fc4c6ac0d735a61f2b36a96bb47e13d96ab3b` |
+//! | `whsec_old_secret`  | `1700000000` | `{"event":"transaction.credit","id":"evt_1"}`    | `9f3b7ac031f42900ac3f95dc90afd7c9fc76db1e80fd9bf57c08abbf73dfdf27` |
+//!
+//! ```
+//! let body = br#"{"event":"transaction.credit","id":"evt_1"}"#;
+//! let header = webhook_signature::signature_header(
+//!     &["whsec_test_secret", "whsec_old_secret"],
+//!     1_700_000_000,
+//!     body,
+//! );
+//! assert_eq!(
+//!     header,
+//!     "t=1700000000,\
+//!      v1=7878dc8941e6a3e39552c63f596fc4c6ac0d735a61f2b36a96bb47e13d96ab3b,\
+//!      v1=9f3b7ac031f42900ac3f95dc90afd7c9fc76db1e80fd9bf57c08abbf73dfdf27"
+//! );
+//!
+//! // A receiver that only knows the old secret still accepts the delivery.
+//! let timestamp = webhook_signature::verify(
+//!     &header,
+//!     body,
+//!     &["whsec_old_secret"],
+//!     webhook_signature::DEFAULT_TOLERANCE_SECS,
+//!     1_700_000_120,
+//! )
+//! .unwrap();
+//! assert_eq!(timestamp, 1_700_000_000);
+//!
+//! // Too old to be accepted.
+//! assert_eq!(
+//!     webhook_signature::verify(&header, body, &["whsec_test_secret"], 300, 1_700_000_301),
+//!     Err(webhook_signature::SignatureError::TimestampOutsideTolerance),
+//! );
+//! ```
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use thiserror::Error;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Recommended maximum age, in seconds, of a delivery accepted by a receiver.
+pub const DEFAULT_TOLERANCE_SECS: i64 = 300;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    #[error("Malformed signature header")]
+    MalformedHeader,
+
+    #[error("Signature timestamp outside tolerance")]
+    TimestampOutsideTolerance,
+
+    #[error("No signature matches any of the provided secrets")]
+    NoMatchingSignature,
+}
+
+/// Hex HMAC-SHA256 of `"<timestamp>.<body>"` keyed with `secret`.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    hex::encode(mac_for(secret, timestamp, body).finalize().into_bytes())
+}
+
+/// Builds the `X-Webhook-Signature` value with one `v1` entry per secret.
+pub fn signature_header(secrets: &[&str], timestamp: i64, body: &[u8]) -> String {
+    let mut header = format!("t={}", timestamp);
+    for secret in secrets {
+        header.push_str(",v1=");
+        header.push_str(&sign(secret, timestamp, body));
+    }
+    header
+}
+
+/// Verifies a signature header against the raw body and returns its timestamp.
+///
+/// The delivery is accepted if any `v1` entry matches any of `secrets` and the
+/// timestamp is within `tolerance_secs` of `now` (unix seconds).
+pub fn verify(
+    header: &str,
+    body: &[u8],
+    secrets: &[&str],
+    tolerance_secs: i64,
+    now: i64,
+) -> Result<i64, SignatureError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => {
+                timestamp = Some(
+                    value
+                        .parse::<i64>()
+                        .map_err(|_| SignatureError::MalformedHeader)?,
+                );
+            }
+            Some(("v1", value)) => {
+                signatures.push(hex::decode(value).map_err(|_| SignatureError::MalformedHeader)?);
+            }
+            Some(_) => {}
+            None => return Err(SignatureError::MalformedHeader),
+        }
+    }
+
+    let timestamp = timestamp.ok_or(SignatureError::MalformedHeader)?;
+    if signatures.is_empty() {
+        return Err(SignatureError::MalformedHeader);
+    }
+
+    if now.abs_diff(timestamp) > tolerance_secs.unsigned_abs() {
+        return Err(SignatureError::TimestampOutsideTolerance);
+    }
+
+    let matched = secrets.iter().any(|secret| {
+        signatures
+            .iter()
+            .any(|signature| mac_for(secret, timestamp, body).verify_slice(signature).is_ok())
+    });
+
+    if matched {
+        Ok(timestamp)
+    } else {
+        Err(SignatureError::NoMatchingSignature)
+    }
+}
+
+fn mac_for(secret: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = br#"{"event":"transaction.credit","id":"evt_1"}"#;
+    const NOW: i64 = 1_700_000_000;
+
+    fn check(header: &str, secrets: &[&str]) -> Result<i64, SignatureError> {
+        verify(header, BODY, secrets, DEFAULT_TOLERANCE_SECS, NOW)
+    }
+
+    #[test]
+    fn accepts_a_valid_signature() {
+        let header = signature_header(&["whsec_a"], NOW, BODY);
+        assert_eq!(check(&header, &["whsec_a"]), Ok(NOW));
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        let v1 = sign("whsec_a", NOW, BODY);
+        for header in [
+            String::new(),
+            "garbage".to_string(),
+            format!("v1={}", v1),
+            format!("t={}", NOW),
+            format!("t=abc,v1={}", v1),
+            format!("t={},v1=not-hex", NOW),
+            format!("t={},v1={},stray", NOW, v1),
+        ] {
+            assert_eq!(check(&header, &["whsec_a"]), Err(SignatureError::MalformedHeader), "{}", header);
+        }
+    }
+
+    #[test]
+    fn ignores_unknown_entries() {
+        let header = format!("t={},v0=abc,v1={}", NOW, sign("whsec_a", NOW, BODY));
+        assert_eq!(check(&header, &["whsec_a"]), Ok(NOW));
+    }
+
+    #[test]
+    fn rejects_timestamps_outside_tolerance() {
+        for timestamp in [NOW - DEFAULT_TOLERANCE_SECS - 1, NOW + DEFAULT_TOLERANCE_SECS + 1] {
+            let header = signature_header(&["whsec_a"], timestamp, BODY);
+            assert_eq!(check(&header, &["whsec_a"]), Err(SignatureError::TimestampOutsideTolerance));
+        }
+        for timestamp in [NOW - DEFAULT_TOLERANCE_SECS, NOW + DEFAULT_TOLERANCE_SECS] {
+            let header = signature_header(&["whsec_a"], timestamp, BODY);
+            assert_eq!(check(&header, &["whsec_a"]), Ok(timestamp));
+        }
+    }
+
+    #[test]
+    fn rejects_extreme_timestamps_without_overflowing() {
+        for timestamp in [i64::MIN, i64::MAX] {
+            let header = format!("t={},v1={}", timestamp, sign("whsec_a", timestamp, BODY));
+            assert_eq!(check(&header, &["whsec_a"]), Err(SignatureError::TimestampOutsideTolerance));
+        }
+
+        let header = signature_header(&["whsec_a"], i64::MAX, BODY);
+        assert_eq!(
+            verify(&header, BODY, &["whsec_a"], i64::MAX, i64::MIN),
+            Err(SignatureError::TimestampOutsideTolerance)
+        );
+    }
+
+    #[test]
+    fn matches_any_of_several_signatures() {
+        let header = signature_header(&["whsec_new", "whsec_old"], NOW, BODY);
+        assert_eq!(check(&header, &["whsec_old"]), Ok(NOW));
+        assert_eq!(check(&header, &["whsec_new"]), Ok(NOW));
+        assert_eq!(check(&header, &["whsec_other", "whsec_old"]), Ok(NOW));
+        assert_eq!(check(&header, &["whsec_other"]), Err(SignatureError::NoMatchingSignature));
+    }
+
+    #[test]
+    fn rejects_a_tampered_body_or_timestamp() {
+        let header = signature_header(&["whsec_a"], NOW, BODY);
+        assert_eq!(
+            verify(&header, b"{}", &["whsec_a"], DEFAULT_TOLERANCE_SECS, NOW),
+            Err(SignatureError::NoMatchingSignature)
+        );
+
+        let moved = header.replacen(&format!("t={}", NOW), &format!("t={}", NOW + 1), 1);
+        assert_eq!(check(&moved, &["whsec_a"]), Err(SignatureError::NoMatchingSignature));
+    }
+}